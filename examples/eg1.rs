@@ -1,15 +1,19 @@
-use global_secrets_manager::GlobalSecretsManager;
+//! Reads the secret `TwitterSecrets` through its global constant, fetched at its first access.
 
-#[derive(GlobalSecretsManager)]
-#[derive(Debug, serde::Deserialize)]
-pub struct my_secrets{
-	twitter_api_key: String,
-	twitter_api_secret: String,
-}
+// The global constant is named after its structure.
+#![allow(non_upper_case_globals)]
 
+use global_secrets_manager::global_secret;
 
+/// The keys of the secret, named after the structure.
+#[global_secret]
+#[derive(Debug)]
+pub struct TwitterSecrets {
+    twitter_api_key: String,
+    twitter_api_secret: String,
+}
 
-fn main(){
-	dbg!(&my_secrets.twitter_api_key);
-	dbg!(&my_secrets.twitter_api_secret);
+fn main() {
+    dbg!(&TwitterSecrets.twitter_api_key);
+    dbg!(&TwitterSecrets.twitter_api_secret);
 }
@@ -0,0 +1,102 @@
+//! The [`Secrets`] extractor of axum handlers, with the `axum` feature, for the types whose derive has the `axum` feature too.
+//!
+//! ```ignore
+//! async fn handler(secrets: Secrets<SampleSecrets>) -> String {
+//!     secrets.key1.clone()
+//! }
+//! ```
+//!
+//! A secret that can't be loaded rejects the request with [`SecretsRejection`], whose status tells a failure that may pass,
+//! such as throttling, from one that won't, such as a denied access, without exposing the error to the client.
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+
+use crate::{BoxError, GsmError};
+
+/// A type whose global constant is preloaded by the generated `try_preload()`, implemented by the derive.
+pub trait GlobalSecret: Send + Sync + 'static {
+    /// Loads the global constant without blocking the async runtime, as the inherent `try_preload()` does.
+    fn try_preload() -> impl std::future::Future<Output = Result<&'static Self, BoxError>> + Send;
+}
+
+/// The global constant of `T`, extracted by handlers and loaded at the first request when it wasn't preloaded.
+///
+/// It is `Copy`, so that applications loading it at startup can also keep it in their state, and extract it from there with
+/// `State<Secrets<T>>` and an implementation of `FromRef`, such as the one of `#[derive(FromRef)]`.
+pub struct Secrets<T: 'static>(pub &'static T);
+
+impl<T> Secrets<T> {
+    /// Loads the global constant of `T`, for the state of a router.
+    pub async fn load() -> Result<Self, SecretsRejection>
+    where
+        T: GlobalSecret,
+    {
+        T::try_preload()
+            .await
+            .map(Secrets)
+            .map_err(SecretsRejection)
+    }
+}
+
+impl<T> Clone for Secrets<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Secrets<T> {}
+
+impl<T> std::ops::Deref for Secrets<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0
+    }
+}
+
+impl<S: Send + Sync, T: GlobalSecret> FromRequestParts<S> for Secrets<T> {
+    type Rejection = SecretsRejection;
+
+    async fn from_request_parts(_parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::load().await
+    }
+}
+
+/// The rejection of a request whose secret can't be loaded, with the error of the load.
+///
+/// It answers `503 Service Unavailable` when the error may pass, as with throttling, an unreachable or failing service,
+/// and `500 Internal Server Error` otherwise, as with missing credentials, a denied access or a payload that doesn't decode.
+/// Neither the error nor the name of the secret is sent to the client.
+#[derive(Debug)]
+pub struct SecretsRejection(pub BoxError);
+
+impl SecretsRejection {
+    /// The status of the response, from the kind of the [`GsmError`] of the error.
+    pub fn status(&self) -> StatusCode {
+        match GsmError::find(&*self.0).map(GsmError::kind) {
+            Some("throttled" | "service" | "transport") => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl std::fmt::Display for SecretsRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for SecretsRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.0)
+    }
+}
+
+impl IntoResponse for SecretsRejection {
+    fn into_response(self) -> Response {
+        (self.status(), "the secrets of the server can't be loaded").into_response()
+    }
+}
@@ -0,0 +1,102 @@
+//! Checks for build scripts, failing the build when the secrets of the application are missing from the target account
+//! or can't be read with the credentials of the build, instead of failing at the first start of the application.
+//!
+//! The checks are opt-in: they only run when `GSM_BUILD_CHECK` is `1` or `true`, as in the pipelines building for an account,
+//! so that local builds and builds without credentials are left alone.
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     global_secrets_manager_runtime::buildtime::require_secrets(&["prod/app", "prod/db"]);
+//! }
+//! ```
+
+use crate::{aws_sdk_secretsmanager, executor, GsmError};
+
+/// The variable enabling the checks.
+pub const ENV_VAR: &str = "GSM_BUILD_CHECK";
+
+/// Whether the checks are enabled by [`ENV_VAR`].
+pub fn enabled() -> bool {
+    matches!(std::env::var(ENV_VAR).as_deref(), Ok("1" | "true"))
+}
+
+/// Fails the build unless each secret of `secrets` exists and can be read, when the checks are enabled.
+///
+/// The secrets are read with the default configuration of the environment, such as `AWS_PROFILE` and `AWS_REGION`, and their values are dropped unread.
+/// Each failure is reported with what to fix, such as the permission to grant.
+pub fn require_secrets(secrets: &[&str]) {
+    for var in [ENV_VAR, "AWS_PROFILE", "AWS_REGION", "AWS_DEFAULT_REGION"] {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+    if !enabled() {
+        return;
+    }
+    let failures = match check_secrets(secrets) {
+        Ok(failures) => failures,
+        Err(err) => panic!("could not check the secrets ({} is set): {}", ENV_VAR, err),
+    };
+    if failures.is_empty() {
+        return;
+    }
+    let report: Vec<String> = failures
+        .iter()
+        .map(|failure| format!("  - {}", failure))
+        .collect();
+    panic!(
+        "{} of the secrets of the application can't be read ({} is set):\n{}",
+        failures.len(),
+        ENV_VAR,
+        report.join("\n")
+    );
+}
+
+/// The failures of the secrets of `secrets` that can't be read, whether the checks are enabled or not.
+pub fn check_secrets(secrets: &[&str]) -> std::io::Result<Vec<Failure>> {
+    executor::block_on(async {
+        let config = crate::config_loader().load().await;
+        let region = config.region().map(|region| region.to_string());
+        let client = aws_sdk_secretsmanager::Client::new(&config);
+        let mut failures = Vec::new();
+        for secret in secrets {
+            if let Err(err) = client.get_secret_value().secret_id(*secret).send().await {
+                failures.push(Failure {
+                    error: crate::fetch_error(secret, err),
+                    region: region.clone(),
+                });
+            }
+        }
+        failures
+    })
+}
+
+/// A secret that can't be read, with the region it was read in, displayed with what to fix.
+#[derive(Debug)]
+pub struct Failure {
+    pub error: GsmError,
+    pub region: Option<String>,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let region = self.region.as_deref().unwrap_or("no region");
+        match &self.error {
+            GsmError::NotFound { secret } => write!(
+                f,
+                "secret `{}` does not exist in the account of the build, in {}: create it, or check its name and the region",
+                secret, region
+            ),
+            GsmError::AccessDenied { secret, source } => write!(
+                f,
+                "access to secret `{}` is denied: grant `secretsmanager:GetSecretValue` on it, and `kms:Decrypt` on its KMS key, to the identity of the build ({})",
+                secret, source
+            ),
+            GsmError::Credentials { secret, source } => write!(
+                f,
+                "no valid AWS credentials to read secret `{}`: give the build credentials, such as with `AWS_PROFILE` ({})",
+                secret, source
+            ),
+            error => write!(f, "{}, in {}", error, region),
+        }
+    }
+}
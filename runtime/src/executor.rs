@@ -0,0 +1,117 @@
+//! The executor the generated code blocks and waits on: tokio, or async-std with the `async-std` feature.
+//!
+//! The SDK still needs tokio to make its requests, which async-std runs through its `tokio1` compatibility,
+//! so that applications built on async-std don't have to start a tokio runtime of their own.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+/// A task of [`spawn`], whose output is awaited from it, or the message of its panic.
+pub type Task<T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send>>;
+
+/// The lock of the single flight of the first loads of the global constants.
+#[cfg(not(feature = "async-std"))]
+pub use tokio::sync::Mutex;
+
+/// The lock of the single flight of the first loads of the global constants.
+#[cfg(feature = "async-std")]
+pub use async_std::sync::Mutex;
+
+/// Runs `future` to completion on an executor of its own, blocking the thread.
+///
+/// A current-thread runtime is enough to wait for one request, and spares the threads of a multi-threaded one.
+#[cfg(not(feature = "async-std"))]
+pub fn block_on<F: Future>(future: F) -> std::io::Result<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok(runtime.block_on(future))
+}
+
+/// Runs `future` to completion on an executor of its own, blocking the thread.
+#[cfg(feature = "async-std")]
+pub fn block_on<F: Future>(future: F) -> std::io::Result<F::Output> {
+    Ok(async_std::task::block_on(future))
+}
+
+/// Whether the thread runs a task of the executor, which blocking would stall.
+#[cfg(not(feature = "async-std"))]
+pub fn in_async_context() -> bool {
+    tokio::runtime::Handle::try_current().is_ok()
+}
+
+/// Whether the thread runs a task of the executor, which blocking would stall.
+#[cfg(feature = "async-std")]
+pub fn in_async_context() -> bool {
+    async_std::task::try_current().is_some()
+}
+
+/// Waits for `duration`.
+#[cfg(not(feature = "async-std"))]
+pub async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+/// Waits for `duration`.
+#[cfg(feature = "async-std")]
+pub async fn sleep(duration: Duration) {
+    async_std::task::sleep(duration).await
+}
+
+/// Runs `future` as a task of its own.
+#[cfg(not(feature = "async-std"))]
+pub fn spawn<F>(future: F) -> Task<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let task = tokio::spawn(future);
+    Box::pin(async { task.await.map_err(|err| err.to_string()) })
+}
+
+/// Runs `future` as a task of its own. A panic of the task is resumed by the awaiting one, as async-std doesn't catch it.
+#[cfg(feature = "async-std")]
+pub fn spawn<F>(future: F) -> Task<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let task = async_std::task::spawn(future);
+    Box::pin(async { Ok(task.await) })
+}
+
+/// Runs the blocking function `f`, such as a write to a file, on a thread where blocking doesn't stall the tasks of the executor.
+#[cfg(not(feature = "async-std"))]
+pub fn spawn_blocking<F, T>(f: F) -> Task<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let task = tokio::task::spawn_blocking(f);
+    Box::pin(async { task.await.map_err(|err| err.to_string()) })
+}
+
+/// Runs the blocking function `f`, such as a write to a file, on a thread where blocking doesn't stall the tasks of the executor.
+#[cfg(feature = "async-std")]
+pub fn spawn_blocking<F, T>(f: F) -> Task<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let task = async_std::task::spawn_blocking(f);
+    Box::pin(async { Ok(task.await) })
+}
+
+/// The output of `future`, or `None` if it isn't ready by `deadline`.
+#[cfg(not(feature = "async-std"))]
+pub async fn until<F: Future>(deadline: Instant, future: F) -> Option<F::Output> {
+    tokio::time::timeout_at(deadline.into(), future).await.ok()
+}
+
+/// The output of `future`, or `None` if it isn't ready by `deadline`.
+#[cfg(feature = "async-std")]
+pub async fn until<F: Future>(deadline: Instant, future: F) -> Option<F::Output> {
+    let timeout = deadline.saturating_duration_since(Instant::now());
+    async_std::future::timeout(timeout, future).await.ok()
+}
@@ -0,0 +1,111 @@
+//! Fake values of the keys of a secret, for the `fake(seed)` of the generated code in tests.
+//!
+//! The values are derived from the seed, the name of the secret and the name of the key alone, with a hash of this crate rather than
+//! the one of the standard library, whose output may change between releases, so that snapshots taken on any machine match.
+//! Their shape follows the name of the key, such as a URL for `api_url` or a region for `region`, so that the code handling them runs as with real ones.
+
+const WORDS: &[&str] = &[
+    "amber", "birch", "cedar", "delta", "ember", "fjord", "garnet", "harbor", "indigo", "juniper",
+    "kestrel", "lumen", "maple", "nimbus", "onyx", "pine", "quartz", "raven", "sierra", "tundra",
+];
+
+const REGIONS: &[&str] = &[
+    "us-east-1",
+    "us-west-2",
+    "eu-west-1",
+    "eu-central-1",
+    "ap-northeast-1",
+    "ap-southeast-2",
+];
+
+const ALPHANUMERIC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// The JSON values the key `key` of the secret `secret` may take for `seed`, from the most to the least realistic: a string shaped
+/// after the name of the key, a number, a boolean, and empty values. The field takes the first one it accepts.
+pub fn values(seed: u64, secret: &str, key: &str) -> Vec<String> {
+    let mut rng = Rng::new(seed, secret, key);
+    let name = key.to_ascii_lowercase();
+    let has = |parts: &[&str]| parts.iter().any(|part| name.contains(part));
+    let word = rng.pick(WORDS);
+    let number = if has(&["port"]) {
+        rng.below(64512) + 1024
+    } else {
+        rng.below(10000)
+    };
+    let text = if has(&["url", "uri", "endpoint", "dsn"]) {
+        format!("https://{}-{}.example.com", word, rng.hex(4))
+    } else if has(&["host"]) {
+        format!("{}-{}.example.internal", word, rng.hex(4))
+    } else if has(&["email", "mail"]) {
+        format!("{}.{}@example.com", word, rng.below(1000))
+    } else if has(&["region"]) {
+        rng.pick(REGIONS).to_owned()
+    } else if has(&["arn"]) {
+        format!(
+            "arn:aws:iam::{:012}:role/{}",
+            rng.below(1_000_000_000_000),
+            word
+        )
+    } else if has(&["password", "secret", "token", "key"]) {
+        rng.alphanumeric(32)
+    } else if has(&["user", "name"]) {
+        format!("{}_{}", word, rng.below(1000))
+    } else {
+        format!("{}-{}", word, rng.hex(8))
+    };
+    vec![
+        serde_json::Value::String(text).to_string(),
+        number.to_string(),
+        (rng.below(2) == 1).to_string(),
+        "[]".to_owned(),
+        "{}".to_owned(),
+        "null".to_owned(),
+    ]
+}
+
+/// SplitMix64, seeded with the FNV-1a hash of the seed, the secret and the key.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64, secret: &str, key: &str) -> Self {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let bytes = seed.to_le_bytes();
+        for byte in bytes
+            .iter()
+            .chain(secret.as_bytes())
+            .chain(&[0])
+            .chain(key.as_bytes())
+        {
+            hash = (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+        Rng(hash)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+
+    fn hex(&mut self, len: usize) -> String {
+        (0..len)
+            .map(|_| char::from_digit(self.below(16) as u32, 16).unwrap())
+            .collect()
+    }
+
+    fn alphanumeric(&mut self, len: usize) -> String {
+        (0..len)
+            .map(|_| char::from(ALPHANUMERIC[self.below(ALPHANUMERIC.len() as u64) as usize]))
+            .collect()
+    }
+}
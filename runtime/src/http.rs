@@ -0,0 +1,167 @@
+//! Fetches of secrets over HTTP, signed with SigV4, for the targets where neither the SDK nor tokio runs, such as WASI and edge runtimes.
+//!
+//! No HTTP client is bundled: the signed [`Request`] is sent by a function of the application, with the `fetch` of its platform,
+//! so that neither a TLS stack nor an executor is pulled in. The module is compiled on `wasm32` targets, and elsewhere with the `http` feature.
+//! With the `blocking` feature, [`Client::fetch_blocking`] sends it with ureq instead.
+
+pub use crate::sigv4::{Credentials, Request};
+use crate::{BoxError, GsmError};
+use std::future::Future;
+use std::time::SystemTime;
+
+/// The credentials, the region and the endpoint of the requests to Secrets Manager.
+#[derive(Clone, Debug)]
+pub struct Client {
+    pub credentials: Credentials,
+    pub region: String,
+    /// Endpoint replacing `https://secretsmanager.{region}.amazonaws.com`, such as the one of LocalStack.
+    pub endpoint_url: Option<String>,
+}
+
+/// The status and the body of the response to a [`Request`].
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl Client {
+    /// The client of the credentials `credentials` in the region `region`.
+    pub fn new(credentials: Credentials, region: impl Into<String>) -> Self {
+        Client {
+            credentials,
+            region: region.into(),
+            endpoint_url: None,
+        }
+    }
+
+    /// The client of the credentials of `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`,
+    /// in the region of `AWS_REGION` or `AWS_DEFAULT_REGION`, at the endpoint of `AWS_ENDPOINT_URL` if it is set.
+    pub fn from_env() -> Result<Self, BoxError> {
+        let var = |name| {
+            std::env::var(name)
+                .ok()
+                .filter(|value: &String| !value.is_empty())
+        };
+        let (access_key_id, secret_access_key) = var("AWS_ACCESS_KEY_ID")
+            .zip(var("AWS_SECRET_ACCESS_KEY"))
+            .ok_or("`AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY` must be set")?;
+        let region = var("AWS_REGION")
+            .or_else(|| var("AWS_DEFAULT_REGION"))
+            .ok_or("`AWS_REGION` must be set")?;
+        Ok(Client {
+            credentials: Credentials {
+                access_key_id,
+                secret_access_key,
+                session_token: var("AWS_SESSION_TOKEN"),
+            },
+            region,
+            endpoint_url: var("AWS_ENDPOINT_URL"),
+        })
+    }
+
+    /// Fetches the string value of the secret `secret_id`, sending the request signed at `now` with `send`.
+    ///
+    /// `now` is taken rather than read, as `SystemTime::now()` panics on the targets without a clock, where it comes from the platform instead.
+    pub async fn fetch<F, Fut>(
+        &self,
+        secret_id: &str,
+        now: SystemTime,
+        send: F,
+    ) -> Result<String, GsmError>
+    where
+        F: FnOnce(Request) -> Fut,
+        Fut: Future<Output = Result<Response, BoxError>>,
+    {
+        let response = send(self.get_secret_value(secret_id, now))
+            .await
+            .map_err(|source| GsmError::Transport {
+                secret: secret_id.to_owned(),
+                source,
+            })?;
+        secret_string(secret_id, response)
+    }
+
+    /// Fetches the string value of the secret `secret_id` with a blocking request, signed now.
+    #[cfg(all(feature = "blocking", not(target_family = "wasm")))]
+    pub fn fetch_blocking(&self, secret_id: &str) -> Result<String, GsmError> {
+        let transport = |source: BoxError| GsmError::Transport {
+            secret: secret_id.to_owned(),
+            source,
+        };
+        let request = self.get_secret_value(secret_id, SystemTime::now());
+        let mut call = ureq::request(request.method, &request.url);
+        for (name, value) in &request.headers {
+            call = call.set(name, value);
+        }
+        // The errors of Secrets Manager come with a status of 4xx or 5xx, and are read from the body like the values.
+        let response = match call.send_string(&request.body) {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(err) => return Err(transport(err.into())),
+        };
+        let status = response.status();
+        let mut body = Vec::new();
+        std::io::Read::read_to_end(&mut response.into_reader(), &mut body)
+            .map_err(|err| transport(err.into()))?;
+        secret_string(secret_id, Response { status, body })
+    }
+
+    /// The `GetSecretValue` request of the secret `secret_id`, signed at `now`.
+    pub fn get_secret_value(&self, secret_id: &str, now: SystemTime) -> Request {
+        let url = self
+            .endpoint_url
+            .clone()
+            .unwrap_or_else(|| format!("https://secretsmanager.{}.amazonaws.com/", self.region));
+        crate::sigv4::sign(
+            &self.credentials,
+            &self.region,
+            "secretsmanager",
+            url,
+            vec![
+                ("content-type", "application/x-amz-json-1.1".to_owned()),
+                ("x-amz-target", "secretsmanager.GetSecretValue".to_owned()),
+            ],
+            serde_json::json!({ "SecretId": secret_id }).to_string(),
+            now,
+        )
+    }
+}
+
+/// The string value of the secret `secret_id` in the response `response` to `GetSecretValue`, or the error it reports.
+pub fn secret_string(secret_id: &str, response: Response) -> Result<String, GsmError> {
+    let secret = secret_id.to_owned();
+    let body: serde_json::Value = match serde_json::from_slice(&response.body) {
+        Ok(body) => body,
+        Err(_) => {
+            return Err(GsmError::Transport {
+                secret,
+                source: format!(
+                    "the response of status {} isn't the JSON of Secrets Manager",
+                    response.status
+                )
+                .into(),
+            })
+        }
+    };
+    if response.status == 200 {
+        return match body["SecretString"].as_str() {
+            Some(secret_string) => Ok(secret_string.to_owned()),
+            None => Err(GsmError::decode(secret_id, "it has no string value")),
+        };
+    }
+    // The type of the error may be qualified, as in `com.amazonaws.secretsmanager#ResourceNotFoundException`.
+    let code = body["__type"]
+        .as_str()
+        .map(|code| code.rsplit('#').next().unwrap_or(code));
+    let message = body["message"]
+        .as_str()
+        .or_else(|| body["Message"].as_str())
+        .unwrap_or_default();
+    let source = format!(
+        "{} (status {}): {}",
+        code.unwrap_or("error"),
+        response.status,
+        message
+    );
+    Err(crate::service_error(secret, code, source.into()))
+}
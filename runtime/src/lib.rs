@@ -0,0 +1,459 @@
+//! Runtime support of the code generated by [global-secrets-manager](https://docs.rs/global-secrets-manager).
+//!
+//! The generated methods return `Box<dyn std::error::Error + Send + Sync>`, and the errors of fetching, decoding and validating
+//! a secret in it are [`GsmError`]s, which applications find with [`GsmError::find`] to match on their kind:
+//!
+//! ```ignore
+//! match SampleSecrets::try_preload().await {
+//!     Ok(_) => {}
+//!     Err(err) => match GsmError::find(&*err) {
+//!         Some(GsmError::Throttled { .. }) => retry_later(),
+//!         _ => panic!("{}", err),
+//!     },
+//! }
+//! ```
+//!
+//! The generated code reaches the SDK through this crate, which re-exports the version selected by its features:
+//! `aws-sdk-v1`, the default, for the 1.x SDK, or `aws-sdk-v0` for aws-config 0.55 and aws-sdk-secretsmanager 0.28.
+//! The calls whose API differs between the versions go through [`config_loader`] and [`assume_role`], so that upgrading the SDK
+//! is a change of feature, and a mismatch fails in this crate instead of in the expansion.
+//!
+//! Where neither the SDK nor tokio runs, such as on WASI and edge runtimes, the [`http`] module fetches secrets with SigV4-signed requests
+//! sent by the application. It is compiled on `wasm32` targets, which leave the SDK and tokio out, and elsewhere with the `http` feature.
+//! The `blocking` feature sends them with a blocking client instead, for the programs that don't otherwise use async, and,
+//! without the SDK, loads the global constants with it.
+//!
+//! The generated code blocks and waits on tokio, or, with the `async-std` feature, on async-std, through the [`executor`] module.
+//!
+//! The [`telemetry`] module records the loads of secrets as tracing spans and events with the `tracing` feature, as metrics with the `metrics` feature,
+//! and as OpenTelemetry spans with the `opentelemetry` feature.
+//!
+//! With the `axum` feature, the [`axum`] module serves the global constants to axum handlers with the `Secrets` extractor.
+//!
+//! The other items are called by the generated code, and aren't meant to be used directly.
+
+#[cfg(all(
+    any(feature = "aws-sdk-v1", feature = "aws-sdk-v0"),
+    not(target_family = "wasm")
+))]
+mod sdk;
+
+#[cfg(all(feature = "axum", not(target_family = "wasm")))]
+pub mod axum;
+
+#[cfg(all(
+    any(feature = "aws-sdk-v1", feature = "aws-sdk-v0"),
+    not(target_family = "wasm")
+))]
+pub mod buildtime;
+#[cfg(all(
+    any(feature = "aws-sdk-v1", feature = "aws-sdk-v0"),
+    not(target_family = "wasm")
+))]
+pub mod executor;
+pub mod fake;
+#[cfg(any(feature = "http", target_family = "wasm"))]
+pub mod http;
+pub mod sigv4;
+pub mod telemetry;
+
+#[cfg(not(any(
+    feature = "aws-sdk-v1",
+    feature = "aws-sdk-v0",
+    feature = "http",
+    target_family = "wasm"
+)))]
+compile_error!(
+    "global-secrets-manager-runtime needs the SDK of the `aws-sdk-v1` or the `aws-sdk-v0` feature, or the fetch of the `http` feature"
+);
+
+#[cfg(all(
+    any(feature = "aws-sdk-v1", feature = "aws-sdk-v0"),
+    not(target_family = "wasm")
+))]
+pub use sdk::{
+    assume_role, aws_config, aws_sdk_secretsmanager, config_loader, credentials, fetch_error,
+    random_password, PasswordOptions,
+};
+#[cfg(all(
+    any(feature = "aws-sdk-v1", feature = "aws-sdk-v0"),
+    not(target_family = "wasm")
+))]
+pub use tokio;
+pub use {dotenvy, once_cell, serde, serde_json};
+
+use std::time::Duration;
+
+/// Expands to the items of the generated code that need the SDK and tokio, or to nothing when this crate is built without them.
+#[cfg(all(
+    any(feature = "aws-sdk-v1", feature = "aws-sdk-v0"),
+    not(target_family = "wasm")
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __sdk {
+    ($($item:tt)*) => { $($item)* };
+}
+
+/// Expands to the items of the generated code that need the SDK and tokio, or to nothing when this crate is built without them.
+#[cfg(not(all(
+    any(feature = "aws-sdk-v1", feature = "aws-sdk-v0"),
+    not(target_family = "wasm")
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __sdk {
+    ($($item:tt)*) => {};
+}
+
+/// Expands to the items of the generated code that fetch secrets over [`http`], or to nothing when this crate is built without it.
+#[cfg(any(feature = "http", target_family = "wasm"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __http {
+    ($($item:tt)*) => { $($item)* };
+}
+
+/// Expands to the items of the generated code that fetch secrets over `http`, or to nothing when this crate is built without it.
+#[cfg(not(any(feature = "http", target_family = "wasm")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __http {
+    ($($item:tt)*) => {};
+}
+
+/// Expands to the items of the generated code that fetch secrets with the blocking client of the `blocking` feature, or to nothing without it.
+#[cfg(all(feature = "blocking", not(target_family = "wasm")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __blocking {
+    ($($item:tt)*) => { $($item)* };
+}
+
+/// Expands to the items of the generated code that fetch secrets with the blocking client of the `blocking` feature, or to nothing without it.
+#[cfg(not(all(feature = "blocking", not(target_family = "wasm"))))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __blocking {
+    ($($item:tt)*) => {};
+}
+
+/// Expands to the items of the generated code counting the reads of the global constants, or to nothing without the `metrics` feature.
+#[cfg(feature = "metrics")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __metrics {
+    ($($item:tt)*) => { $($item)* };
+}
+
+/// Expands to the items of the generated code counting the reads of the global constants, or to nothing without the `metrics` feature.
+#[cfg(not(feature = "metrics"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __metrics {
+    ($($item:tt)*) => {};
+}
+
+/// Expands to the items of the generated code that stand in for those of [`__metrics`] without the `metrics` feature, or to nothing with it.
+#[cfg(feature = "metrics")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __without_metrics {
+    ($($item:tt)*) => {};
+}
+
+/// Expands to the items of the generated code that stand in for those of [`__metrics`] without the `metrics` feature, or to nothing with it.
+#[cfg(not(feature = "metrics"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __without_metrics {
+    ($($item:tt)*) => { $($item)* };
+}
+
+/// Expands to the items of the generated code for tests, such as the builders, in the tests of the application and, with the `test-util` feature, everywhere.
+#[cfg(feature = "test-util")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __test_util {
+    ($($item:item)*) => { $($item)* };
+}
+
+/// Expands to the items of the generated code for tests, such as the builders, in the tests of the application and, with the `test-util` feature, everywhere.
+#[cfg(not(feature = "test-util"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __test_util {
+    ($($item:item)*) => { $(#[cfg(test)] $item)* };
+}
+
+/// Expands to the items of the generated code that stand in for those of [`__sdk`] when this crate is built without the SDK, or to nothing with it.
+#[cfg(all(
+    any(feature = "aws-sdk-v1", feature = "aws-sdk-v0"),
+    not(target_family = "wasm")
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __without_sdk {
+    ($($item:tt)*) => {};
+}
+
+/// Expands to the items of the generated code that stand in for those of [`__sdk`] when this crate is built without the SDK, or to nothing with it.
+#[cfg(not(all(
+    any(feature = "aws-sdk-v1", feature = "aws-sdk-v0"),
+    not(target_family = "wasm")
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __without_sdk {
+    ($($item:tt)*) => { $($item)* };
+}
+
+/// The error type of the generated methods, which holds a [`GsmError`] when the secret couldn't be loaded.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Why a secret couldn't be loaded.
+///
+/// The messages never quote the values of the secret.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum GsmError {
+    /// No AWS credentials were found, or Secrets Manager rejected them.
+    #[error("no valid AWS credentials to read secret `{secret}`: {source}")]
+    Credentials { secret: String, source: BoxError },
+    /// The secret doesn't exist, or was deleted.
+    #[error("secret `{secret}` does not exist")]
+    NotFound { secret: String },
+    /// The credentials aren't allowed to read the secret, or to decrypt it with its KMS key.
+    #[error("access to secret `{secret}` is denied: {source}")]
+    AccessDenied { secret: String, source: BoxError },
+    /// Secrets Manager throttled the reads of the secret.
+    #[error("reads of secret `{secret}` are throttled: {source}")]
+    Throttled { secret: String, source: BoxError },
+    /// Secrets Manager returned another error.
+    #[error("secret `{secret}` can't be read: {source}")]
+    Service { secret: String, source: BoxError },
+    /// The request couldn't reach Secrets Manager, or its response was lost.
+    #[error("secret `{secret}` can't be fetched: {source}")]
+    Transport { secret: String, source: BoxError },
+    /// The payload isn't what the type reads, such as invalid JSON or a value of the wrong type.
+    #[error("secret `{secret}` can't be decoded: {reason}")]
+    Decode { secret: String, reason: String },
+    /// The values were rejected by the checks of the type.
+    #[error("secret `{secret}` is invalid: {reason}")]
+    Validation { secret: String, reason: String },
+}
+
+impl GsmError {
+    /// The `GsmError` in the error `err` or in its sources, if any.
+    pub fn find<'a>(err: &'a (dyn std::error::Error + 'static)) -> Option<&'a GsmError> {
+        let mut next = Some(err);
+        while let Some(err) = next {
+            if let Some(found) = err.downcast_ref::<GsmError>() {
+                return Some(found);
+            }
+            next = err.source();
+        }
+        None
+    }
+
+    /// The kind of the error, as named by `#[gsm(retry(...))]` and the labels of the metrics: `credentials`, `not_found`,
+    /// `access_denied`, `throttled`, `service`, `transport`, `decode` or `validation`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GsmError::Credentials { .. } => "credentials",
+            GsmError::NotFound { .. } => "not_found",
+            GsmError::AccessDenied { .. } => "access_denied",
+            GsmError::Throttled { .. } => "throttled",
+            GsmError::Service { .. } => "service",
+            GsmError::Transport { .. } => "transport",
+            GsmError::Decode { .. } => "decode",
+            GsmError::Validation { .. } => "validation",
+        }
+    }
+
+    /// A [`GsmError::Decode`] of the secret `secret`, for the reason `reason`.
+    pub fn decode(secret: &str, reason: impl Into<String>) -> Self {
+        GsmError::Decode {
+            secret: secret.to_owned(),
+            reason: reason.into(),
+        }
+    }
+
+    /// The `GsmError` of the error `source` with the code `code` returned by another AWS service on the secret `secret`,
+    /// for the backends of the generated code, classified as those of Secrets Manager are.
+    pub fn service(secret: &str, code: Option<&str>, source: BoxError) -> Self {
+        service_error(secret.to_owned(), code, source)
+    }
+
+    /// A [`GsmError::Validation`] of the secret `secret`, for the reason `reason`.
+    pub fn validation(secret: &str, reason: impl Into<String>) -> Self {
+        GsmError::Validation {
+            secret: secret.to_owned(),
+            reason: reason.into(),
+        }
+    }
+
+    /// The name or ARN of the secret.
+    pub fn secret(&self) -> &str {
+        match self {
+            GsmError::Credentials { secret, .. }
+            | GsmError::NotFound { secret }
+            | GsmError::AccessDenied { secret, .. }
+            | GsmError::Throttled { secret, .. }
+            | GsmError::Service { secret, .. }
+            | GsmError::Transport { secret, .. }
+            | GsmError::Decode { secret, .. }
+            | GsmError::Validation { secret, .. } => secret,
+        }
+    }
+}
+
+/// How the fetches failing with one kind of error are retried.
+#[derive(Clone, Copy, Debug)]
+pub struct Backoff {
+    pub retries: u32,
+    /// Delay before the first retry, doubled before each next one.
+    pub delay: Duration,
+}
+
+/// The retries of the fetches failing with each kind of [`GsmError`], set by `#[gsm(retry(...))]`.
+///
+/// The fetches failing with other kinds of errors, or with errors that aren't `GsmError`s, fail at once.
+/// These retries come on top of those of the SDK, which retries throttling and transient errors a few times on its own.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub credentials: Option<Backoff>,
+    pub not_found: Option<Backoff>,
+    pub access_denied: Option<Backoff>,
+    pub throttled: Option<Backoff>,
+    pub service: Option<Backoff>,
+    pub transport: Option<Backoff>,
+}
+
+impl RetryPolicy {
+    /// No retries.
+    pub const NONE: RetryPolicy = RetryPolicy {
+        credentials: None,
+        not_found: None,
+        access_denied: None,
+        throttled: None,
+        service: None,
+        transport: None,
+    };
+
+    /// The delay before retrying a fetch that failed `failures` times, the last time with `err`, or `None` if it isn't retried.
+    pub fn delay(
+        &self,
+        err: &(dyn std::error::Error + 'static),
+        failures: u32,
+    ) -> Option<Duration> {
+        let backoff = match GsmError::find(err)? {
+            GsmError::Credentials { .. } => self.credentials,
+            GsmError::NotFound { .. } => self.not_found,
+            GsmError::AccessDenied { .. } => self.access_denied,
+            GsmError::Throttled { .. } => self.throttled,
+            GsmError::Service { .. } => self.service,
+            GsmError::Transport { .. } => self.transport,
+            GsmError::Decode { .. } | GsmError::Validation { .. } => None,
+        }?;
+        if failures == 0 || failures > backoff.retries {
+            return None;
+        }
+        Some(backoff.delay.saturating_mul(1 << (failures - 1).min(16)))
+    }
+}
+
+/// Classifies the error of code `code` returned by Secrets Manager on the secret `secret`, or by the service of another backend,
+/// whose codes differ for some kinds, such as the `NoSuchKey` of S3.
+pub(crate) fn service_error(secret: String, code: Option<&str>, source: BoxError) -> GsmError {
+    match code {
+        Some("ResourceNotFoundException" | "NoSuchKey" | "NoSuchBucket") => {
+            GsmError::NotFound { secret }
+        }
+        Some(
+            "UnrecognizedClientException"
+            | "InvalidClientTokenId"
+            | "InvalidSignatureException"
+            | "ExpiredTokenException",
+        ) => GsmError::Credentials { secret, source },
+        Some("AccessDeniedException" | "AccessDenied" | "DecryptionFailure") => {
+            GsmError::AccessDenied { secret, source }
+        }
+        Some("ThrottlingException" | "TooManyRequestsException" | "SlowDown") => {
+            GsmError::Throttled { secret, source }
+        }
+        _ => GsmError::Service { secret, source },
+    }
+}
+
+/// Deserializes a payload, leaked first so that borrowed fields can point into it.
+/// serde's messages may quote the values of the secret, so only the position of the error is kept.
+pub fn deserialize<T: serde::Deserialize<'static>>(
+    secret: &str,
+    rt_str: String,
+) -> Result<T, GsmError> {
+    let rt_bytes: &'static [u8] = Box::leak(rt_str.into_bytes().into_boxed_slice());
+    serde_json::from_slice(rt_bytes).map_err(|err| decode_error(secret, err))
+}
+
+/// Deserializes a payload into a type owning its values, so that nothing is leaked by the secrets read more than once.
+pub fn deserialize_owned<T: serde::de::DeserializeOwned>(
+    secret: &str,
+    rt_str: &str,
+) -> Result<T, GsmError> {
+    serde_json::from_str(rt_str).map_err(|err| decode_error(secret, err))
+}
+
+fn decode_error(secret: &str, err: serde_json::Error) -> GsmError {
+    let reason = match err.classify() {
+        serde_json::error::Category::Data => "a value doesn't have the type of its field",
+        _ => "it is not valid JSON",
+    };
+    GsmError::decode(
+        secret,
+        format!("{} (line {}, column {})", reason, err.line(), err.column()),
+    )
+}
+
+/// Whether the key of a secret matches a glob of `#[gsm(allow_extra(...))]`, where `*` stands for any characters and `?` for one.
+pub fn glob_matches(glob: &str, key: &str) -> bool {
+    let (glob, key): (Vec<char>, Vec<char>) = (glob.chars().collect(), key.chars().collect());
+    let (mut g, mut k) = (0, 0);
+    // The last `*`, and the character of the key it has been tried up to.
+    let mut star = None;
+    while k < key.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g, k));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == key[k] => {
+                g += 1;
+                k += 1;
+            }
+            _ => match star {
+                Some((star_g, star_k)) => {
+                    star = Some((star_g, star_k + 1));
+                    g = star_g + 1;
+                    k = star_k + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+/// Parses a payload as JSON. Only the position of a syntax error is kept, never the text around it.
+pub fn parse_json(secret: &str, rt_str: &str) -> Result<serde_json::Value, GsmError> {
+    serde_json::from_str(rt_str).map_err(|err| {
+        GsmError::decode(
+            secret,
+            format!(
+                "it is not valid JSON (line {}, column {})",
+                err.line(),
+                err.column()
+            ),
+        )
+    })
+}
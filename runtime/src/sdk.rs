@@ -0,0 +1,152 @@
+//! The SDK selected by the `aws-sdk-v1` and `aws-sdk-v0` features, and the calls whose API differs between its versions.
+
+use crate::GsmError;
+use aws_sdk_secretsmanager::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_secretsmanager::operation::get_secret_value::GetSecretValueError;
+
+#[cfg(feature = "aws-sdk-v1")]
+pub use {
+    aws_config_v1 as aws_config, aws_credential_types_v1 as aws_credential_types,
+    aws_sdk_secretsmanager_v1 as aws_sdk_secretsmanager,
+};
+
+#[cfg(all(feature = "aws-sdk-v0", not(feature = "aws-sdk-v1")))]
+pub use {
+    aws_config_v0 as aws_config, aws_credential_types_v0 as aws_credential_types,
+    aws_sdk_secretsmanager_v0 as aws_sdk_secretsmanager,
+};
+
+/// The loader of the default configuration, from the environment.
+#[cfg(feature = "aws-sdk-v1")]
+pub fn config_loader() -> aws_config::ConfigLoader {
+    aws_config::defaults(aws_config::BehaviorVersion::latest())
+}
+
+/// The loader of the default configuration, from the environment.
+#[cfg(all(feature = "aws-sdk-v0", not(feature = "aws-sdk-v1")))]
+pub fn config_loader() -> aws_config::ConfigLoader {
+    aws_config::from_env()
+}
+
+/// The configuration `config` with the credentials of the role `role_arn`, assumed with the credentials of `config`.
+/// The SDK caches the session credentials and refreshes them before they expire.
+#[cfg(feature = "aws-sdk-v1")]
+pub async fn assume_role(config: &aws_config::SdkConfig, role_arn: &str) -> aws_config::SdkConfig {
+    let provider = aws_config::sts::AssumeRoleProvider::builder(role_arn)
+        .session_name("global-secrets-manager")
+        .configure(config)
+        .build()
+        .await;
+    config
+        .to_builder()
+        .credentials_provider(
+            aws_credential_types::provider::SharedCredentialsProvider::new(provider),
+        )
+        .build()
+}
+
+/// The configuration `config` with the credentials of the role `role_arn`, assumed with the credentials of `config`.
+/// The SDK caches the session credentials and refreshes them before they expire.
+#[cfg(all(feature = "aws-sdk-v0", not(feature = "aws-sdk-v1")))]
+pub async fn assume_role(config: &aws_config::SdkConfig, role_arn: &str) -> aws_config::SdkConfig {
+    let mut builder = aws_config::sts::AssumeRoleProvider::builder(role_arn)
+        .session_name("global-secrets-manager");
+    if let Some(region) = config.region() {
+        builder = builder.region(region.clone());
+    }
+    let base = match config.credentials_provider() {
+        Some(base) => base.clone(),
+        None => aws_credential_types::provider::SharedCredentialsProvider::new(
+            aws_config::default_provider::credentials::default_provider().await,
+        ),
+    };
+    let provider = builder.build(base);
+    config
+        .to_builder()
+        .credentials_provider(
+            aws_credential_types::provider::SharedCredentialsProvider::new(provider),
+        )
+        .build()
+}
+
+/// The credentials of `config`, for the requests signed by [`sigv4`](crate::sigv4), such as the identities proven to other services.
+pub async fn credentials(
+    config: &aws_config::SdkConfig,
+) -> Result<crate::sigv4::Credentials, crate::BoxError> {
+    use aws_credential_types::provider::ProvideCredentials;
+    let provider = config
+        .credentials_provider()
+        .ok_or("the configuration has no credentials provider")?;
+    let credentials = provider.provide_credentials().await?;
+    Ok(crate::sigv4::Credentials {
+        access_key_id: credentials.access_key_id().to_owned(),
+        secret_access_key: credentials.secret_access_key().to_owned(),
+        session_token: credentials.session_token().map(str::to_owned),
+    })
+}
+
+/// The options of a password generated by Secrets Manager, where `None` leaves the default of `GetRandomPassword`:
+/// 32 characters, of every class but the space, with at least one of each.
+#[derive(Clone, Debug, Default)]
+pub struct PasswordOptions {
+    pub length: Option<i64>,
+    /// Characters the password never holds, such as the quotes that a connection string can't take.
+    pub exclude_characters: Option<String>,
+    pub exclude_numbers: Option<bool>,
+    pub exclude_punctuation: Option<bool>,
+    pub exclude_uppercase: Option<bool>,
+    pub exclude_lowercase: Option<bool>,
+    pub include_space: Option<bool>,
+    /// Whether the password holds at least one character of each class that isn't excluded.
+    pub require_each_included_type: Option<bool>,
+}
+
+/// A password generated by `GetRandomPassword` with `options`, for the credentials minted by the application.
+pub async fn random_password(
+    client: &aws_sdk_secretsmanager::Client,
+    options: &PasswordOptions,
+) -> Result<String, crate::BoxError> {
+    let resp = client
+        .get_random_password()
+        .set_password_length(options.length)
+        .set_exclude_characters(options.exclude_characters.clone())
+        .set_exclude_numbers(options.exclude_numbers)
+        .set_exclude_punctuation(options.exclude_punctuation)
+        .set_exclude_uppercase(options.exclude_uppercase)
+        .set_exclude_lowercase(options.exclude_lowercase)
+        .set_include_space(options.include_space)
+        .set_require_each_included_type(options.require_each_included_type)
+        .send()
+        .await?;
+    let password = resp
+        .random_password()
+        .ok_or("Secrets Manager generated no password")?;
+    Ok(password.to_owned())
+}
+
+/// Classifies the error of `GetSecretValue` on the secret `secret`.
+pub fn fetch_error(secret: &str, err: SdkError<GetSecretValueError>) -> GsmError {
+    let secret = secret.to_owned();
+    if !matches!(err, SdkError::ServiceError(_)) {
+        let mut next: Option<&(dyn std::error::Error + 'static)> = Some(&err);
+        while let Some(cause) = next {
+            if cause
+                .downcast_ref::<aws_credential_types::provider::error::CredentialsError>()
+                .is_some()
+            {
+                return GsmError::Credentials {
+                    secret,
+                    source: err.into(),
+                };
+            }
+            next = cause.source();
+        }
+        return GsmError::Transport {
+            secret,
+            source: err.into(),
+        };
+    }
+    let service = err.into_service_error();
+    let code = service.code().map(str::to_owned);
+    crate::service_error(secret, code.as_deref(), service.into())
+}
@@ -0,0 +1,159 @@
+//! Requests to AWS signed with SigV4 by this crate, for the fetches over HTTP of the `http` module and the identities proven to other services.
+//!
+//! A signed `GetCallerIdentity` request of STS proves the identity of its credentials to the service it is handed to, which sends it to STS itself,
+//! without the credentials leaving the application: the backends authenticating AWS roles, such as Akeyless, take one.
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The AWS credentials signing the requests.
+#[derive(Clone)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A signed request, to be sent as is.
+#[derive(Clone)]
+pub struct Request {
+    pub method: &'static str,
+    pub url: String,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+/// The `GetCallerIdentity` request of STS, signed at `now` with `credentials`, on the global endpoint.
+pub fn get_caller_identity(credentials: &Credentials, now: SystemTime) -> Request {
+    sign(
+        credentials,
+        "us-east-1",
+        "sts",
+        "https://sts.amazonaws.com/".to_owned(),
+        vec![(
+            "content-type",
+            "application/x-www-form-urlencoded; charset=utf-8".to_owned(),
+        )],
+        "Action=GetCallerIdentity&Version=2011-06-15".to_owned(),
+        now,
+    )
+}
+
+/// The POST request of `body` to `url`, for the service `service` in the region `region`, signed at `now` with `credentials`.
+/// `headers` are sent and signed along with those of SigV4, and must be named in lowercase.
+pub(crate) fn sign(
+    credentials: &Credentials,
+    region: &str,
+    service: &str,
+    url: String,
+    mut headers: Vec<(&'static str, String)>,
+    body: String,
+    now: SystemTime,
+) -> Request {
+    let authority = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+    let (host, path) = match authority.find('/') {
+        Some(slash) => (&authority[..slash], &authority[slash..]),
+        None => (authority, "/"),
+    };
+    let (date, timestamp) = timestamp(now);
+
+    headers.push(("host", host.to_owned()));
+    headers.push(("x-amz-date", timestamp.clone()));
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    // Sorted by name, as SigV4 signs them.
+    headers.sort_by_key(|(name, _)| *name);
+
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let canonical_request = format!(
+        "POST\n{}\n\n{}\n{}\n{}",
+        path,
+        canonical_headers,
+        signed_headers,
+        hex(&Sha256::digest(body.as_bytes()))
+    );
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        timestamp,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let mut key = hmac(
+        format!("AWS4{}", credentials.secret_access_key).as_bytes(),
+        date.as_bytes(),
+    );
+    for part in [region, service, "aws4_request"] {
+        key = hmac(&key, part.as_bytes());
+    }
+    let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+    headers.push((
+        "authorization",
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+
+    Request {
+        method: "POST",
+        url,
+        headers,
+        body,
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The date and the time of `now` in the formats of SigV4, such as `20240131` and `20240131T235959Z`.
+fn timestamp(now: SystemTime) -> (String, String) {
+    let seconds = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (days, time) = ((seconds / 86400) as i64, seconds % 86400);
+    // The civil date of the days since the epoch, after `civil_from_days` of Howard Hinnant's date algorithms.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let date = format!("{:04}{:02}{:02}", year, month, day);
+    let timestamp = format!(
+        "{}T{:02}{:02}{:02}Z",
+        date,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    );
+    (date, timestamp)
+}
@@ -0,0 +1,161 @@
+//! What the generated code reports of the loads of secrets and of the reads of the global constants: tracing spans and events
+//! with the `tracing` feature, metrics with the `metrics` feature, and OpenTelemetry spans with the `opentelemetry` feature.
+//!
+//! The generated code always calls these functions, and the features of this crate decide what they record,
+//! so that a crate expanding the macro doesn't need the crates of the features enabled by another one.
+//! Only the id of the secret is recorded, never the payload.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::BoxError;
+
+/// The number of attempts of a load, retries included, for its span.
+#[derive(Default)]
+pub struct Attempts(AtomicU32);
+
+/// When a global constant was last loaded or replaced, for the `gsm_refresh_staleness_seconds` gauge.
+pub type LoadedAt = Mutex<Option<Instant>>;
+
+/// Runs the load `load` of the secret `secret_id` from the backend `backend`, made of the attempts of [`fetch`] counted by `attempts`.
+///
+/// With the `tracing` feature, it runs in a `gsm.fetch` span recording how many attempts it took, and ends with an event with its duration, attempts and outcome.
+#[allow(unused_variables)]
+pub async fn load<F>(
+    secret_id: &str,
+    backend: &'static str,
+    attempts: &Attempts,
+    load: F,
+) -> Result<String, BoxError>
+where
+    F: Future<Output = Result<String, BoxError>>,
+{
+    // Boxed, so that the layouts of the futures of the SDK aren't nested in those of the callers, which would exceed the recursion limit.
+    let load = Box::pin(load);
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::info_span!(
+            "gsm.fetch",
+            secret = secret_id,
+            backend,
+            attempts = tracing::field::Empty
+        );
+        let start = Instant::now();
+        let result = tracing::Instrument::instrument(load, span.clone()).await;
+        let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+        let attempts = attempts.0.load(Ordering::Relaxed);
+        span.record("attempts", attempts);
+        span.in_scope(|| match &result {
+            Ok(_) => tracing::info!(duration_ms, attempts, outcome = "ok", "fetched secret"),
+            Err(err) => tracing::warn!(duration_ms, attempts, outcome = "error", error = %err, "failed to fetch secret"),
+        });
+        result
+    }
+    #[cfg(not(feature = "tracing"))]
+    load.await
+}
+
+/// Runs the attempt `fetch` of the secret `secret_id` from the backend `backend`, counted by `attempts`.
+///
+/// With the `metrics` feature, it counts the fetches and their failures by kind, and records their latency, labelled by the id of the secret.
+/// With the `opentelemetry` feature, it runs in a `gsm.fetch` span of the global tracer, child of the current context.
+#[allow(unused_variables)]
+pub async fn fetch<F>(
+    secret_id: &str,
+    backend: &'static str,
+    attempts: &Attempts,
+    fetch: F,
+) -> Result<String, BoxError>
+where
+    F: Future<Output = Result<String, BoxError>>,
+{
+    attempts.0.fetch_add(1, Ordering::Relaxed);
+    // Boxed for the wrappers as well.
+    #[cfg(any(feature = "metrics", feature = "opentelemetry"))]
+    let fetch = Box::pin(fetch);
+    #[cfg(feature = "metrics")]
+    let fetch = metered(secret_id, fetch);
+    #[cfg(feature = "opentelemetry")]
+    let fetch = traced(secret_id, backend, fetch);
+    fetch.await
+}
+
+#[cfg(feature = "metrics")]
+async fn metered<F>(secret_id: &str, fetch: F) -> Result<String, BoxError>
+where
+    F: Future<Output = Result<String, BoxError>>,
+{
+    metrics::counter!("gsm_fetch_attempts_total", "secret" => secret_id.to_owned()).increment(1);
+    let start = Instant::now();
+    let result = fetch.await;
+    metrics::histogram!("gsm_fetch_duration_seconds", "secret" => secret_id.to_owned())
+        .record(start.elapsed().as_secs_f64());
+    if let Err(err) = &result {
+        let kind = crate::GsmError::find(&**err).map_or("other", crate::GsmError::kind);
+        metrics::counter!("gsm_fetch_failures_total", "secret" => secret_id.to_owned(), "kind" => kind).increment(1);
+    }
+    result
+}
+
+/// The span is a child of the current context, so that a fetch caused by a request shows in its trace.
+/// The current context is kept by the blocking initialization of the constant too, since it runs on the thread dereferencing it.
+#[cfg(feature = "opentelemetry")]
+async fn traced<F>(secret_id: &str, backend: &'static str, fetch: F) -> Result<String, BoxError>
+where
+    F: Future<Output = Result<String, BoxError>>,
+{
+    use opentelemetry::trace::{FutureExt as _, Span as _, TraceContextExt as _, Tracer as _};
+    let tracer = opentelemetry::global::tracer("global-secrets-manager");
+    let mut span = tracer.start_with_context("gsm.fetch", &opentelemetry::Context::current());
+    span.set_attribute(opentelemetry::KeyValue::new("secret", secret_id.to_owned()));
+    span.set_attribute(opentelemetry::KeyValue::new("backend", backend));
+    let context = opentelemetry::Context::current_with_span(span);
+    let result = fetch.with_context(context.clone()).await;
+    if let Err(err) = &result {
+        context
+            .span()
+            .set_status(opentelemetry::trace::Status::error(err.to_string()));
+    }
+    context.span().end();
+    result
+}
+
+/// Records a read of the global constant of the type `type_name` finding it already loaded, at `loaded_at`.
+///
+/// With the `metrics` feature, it counts the read and sets how long ago the constant was loaded.
+#[allow(unused_variables)]
+pub fn cache_hit(type_name: &'static str, loaded_at: &LoadedAt) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!("gsm_cache_hits_total", "type" => type_name).increment(1);
+        if let Some(loaded_at) = *loaded_at
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+        {
+            metrics::gauge!("gsm_refresh_staleness_seconds", "type" => type_name)
+                .set(loaded_at.elapsed().as_secs_f64());
+        }
+    }
+}
+
+/// Records that the global constant of the type `type_name` was just loaded or replaced, at `loaded_at`.
+#[allow(unused_variables)]
+pub fn refreshed(type_name: &'static str, loaded_at: &LoadedAt) {
+    #[cfg(feature = "metrics")]
+    {
+        *loaded_at
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(Instant::now());
+        metrics::gauge!("gsm_refresh_staleness_seconds", "type" => type_name).set(0.0);
+    }
+}
+
+/// Reports the warning `args` of the generated code, as a tracing event with the `tracing` feature, or on the standard error.
+pub fn warning(args: std::fmt::Arguments<'_>) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!("{}", args);
+    #[cfg(not(feature = "tracing"))]
+    eprintln!("{}", args);
+}
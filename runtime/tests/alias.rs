@@ -0,0 +1,50 @@
+//! The keys read under the former names of `alias`, and the current names winning over them.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            ("alias/former", r#"{"db_password":"former"}"#),
+            (
+                "alias/both",
+                r#"{"db_password":"former","password":"current"}"#,
+            ),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[global_secret(name = "alias/former", no_static)]
+pub struct Former {
+    #[gsm(alias = "db_password", alias = "pg_password")]
+    password: String,
+}
+
+#[global_secret(name = "alias/both", no_static)]
+pub struct Both {
+    #[gsm(alias = "db_password")]
+    password: String,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn a_key_is_read_under_its_former_name() {
+    let secrets = block_on(Former::fetch()).unwrap();
+    assert_eq!(secrets.password, "former");
+}
+
+#[test]
+fn the_current_name_wins_over_the_former_one() {
+    let secrets = block_on(Both::fetch()).unwrap();
+    assert_eq!(secrets.password, "current");
+}
@@ -0,0 +1,64 @@
+//! The statuses of the rejections of the `Secrets` extractor.
+#![cfg(feature = "axum")]
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use global_secrets_manager_runtime::axum::SecretsRejection;
+use global_secrets_manager_runtime::{BoxError, GsmError};
+
+fn rejection(err: GsmError) -> SecretsRejection {
+    SecretsRejection(err.into())
+}
+
+#[test]
+fn passing_failures_are_unavailable() {
+    let source = || BoxError::from("source");
+    for err in [
+        GsmError::Throttled {
+            secret: "a".into(),
+            source: source(),
+        },
+        GsmError::Service {
+            secret: "a".into(),
+            source: source(),
+        },
+        GsmError::Transport {
+            secret: "a".into(),
+            source: source(),
+        },
+    ] {
+        assert_eq!(rejection(err).status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
+
+#[test]
+fn lasting_failures_are_internal_errors() {
+    let source = || BoxError::from("source");
+    for err in [
+        GsmError::Credentials {
+            secret: "a".into(),
+            source: source(),
+        },
+        GsmError::NotFound { secret: "a".into() },
+        GsmError::AccessDenied {
+            secret: "a".into(),
+            source: source(),
+        },
+        GsmError::decode("a", "invalid JSON"),
+    ] {
+        assert_eq!(rejection(err).status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    assert_eq!(
+        SecretsRejection("not a GsmError".into()).status(),
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+#[test]
+fn the_response_hides_the_error() {
+    let response = rejection(GsmError::NotFound {
+        secret: "prod/db".into(),
+    })
+    .into_response();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
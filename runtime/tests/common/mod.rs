@@ -0,0 +1,295 @@
+//! A Secrets Manager answering on a local port, for the tests of the generated code: it serves and stores the versions of the secrets,
+//! counts the reads of each secret in each region, and fails the requests it is told to fail.
+
+// Each test binary uses part of it.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+pub struct SecretsManager {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    /// The versions of each secret, the last one being `AWSCURRENT`, and the one it is rotated to, if any.
+    secrets: HashMap<String, Secret>,
+    /// The `GetSecretValue` requests made for each secret in each region.
+    reads: HashMap<(String, String), usize>,
+    /// The errors to answer, as the secret, the region, or any if `None`, the type of the error and how many times it is left to answer.
+    failures: Vec<(String, Option<String>, &'static str, usize)>,
+}
+
+#[derive(Default)]
+struct Secret {
+    versions: Vec<String>,
+    rotated: Option<String>,
+    /// The id of the version pending until it is described once, after a rotation.
+    pending: Option<String>,
+}
+
+impl SecretsManager {
+    /// Serves the payloads of `secrets`, by name, and points the SDK at them through the variables of the environment.
+    /// Each answer waits for `delay`, so that concurrent reads overlap. The SDK doesn't retry, so that the generated code alone does.
+    pub fn start(secrets: &[(&str, &str)], delay: Duration) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint_url = format!("http://{}", listener.local_addr().unwrap());
+        for (name, value) in [
+            ("AWS_ENDPOINT_URL", endpoint_url.as_str()),
+            ("AWS_REGION", "us-east-1"),
+            ("AWS_ACCESS_KEY_ID", "test"),
+            ("AWS_SECRET_ACCESS_KEY", "test"),
+            ("AWS_EC2_METADATA_DISABLED", "true"),
+            ("AWS_MAX_ATTEMPTS", "1"),
+        ] {
+            std::env::set_var(name, value);
+        }
+
+        let state = Arc::new(Mutex::new(State {
+            secrets: secrets
+                .iter()
+                .map(|(name, payload)| {
+                    let secret = Secret {
+                        versions: vec![payload.to_string()],
+                        ..Secret::default()
+                    };
+                    (name.to_string(), secret)
+                })
+                .collect(),
+            ..State::default()
+        }));
+        let served = state.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = served.clone();
+                std::thread::spawn(move || answer(stream, &state, delay));
+            }
+        });
+        SecretsManager { state }
+    }
+
+    /// The number of `GetSecretValue` requests made for the secret `name`, in every region.
+    pub fn requests(&self, name: &str) -> usize {
+        let state = self.state.lock().unwrap();
+        state
+            .reads
+            .iter()
+            .filter(|((secret, _), _)| secret == name)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// The number of `GetSecretValue` requests made for the secret `name` in `region`.
+    pub fn requests_in(&self, region: &str, name: &str) -> usize {
+        let state = self.state.lock().unwrap();
+        let key = (name.to_owned(), region.to_owned());
+        state.reads.get(&key).copied().unwrap_or_default()
+    }
+
+    /// The `AWSCURRENT` value of the secret `name`.
+    pub fn current(&self, name: &str) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        state.secrets.get(name)?.versions.last().cloned()
+    }
+
+    /// Answers the next `times` requests for the secret `name`, in every region, with the error of type `error`, such as `ThrottlingException`.
+    pub fn fail(&self, name: &str, error: &'static str, times: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.failures.push((name.to_owned(), None, error, times));
+    }
+
+    /// Answers the next `times` requests for the secret `name` in `region` with the error of type `error`.
+    pub fn fail_in(&self, region: &str, name: &str, error: &'static str, times: usize) {
+        let mut state = self.state.lock().unwrap();
+        let region = Some(region.to_owned());
+        state.failures.push((name.to_owned(), region, error, times));
+    }
+
+    /// Makes a rotation of the secret `name` put `payload` as its new version, which becomes `AWSCURRENT` once the secret is described,
+    /// as a rotation function would finish in the meantime.
+    pub fn rotates_to(&self, name: &str, payload: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.secrets.entry(name.to_owned()).or_default().rotated = Some(payload.to_owned());
+    }
+}
+
+/// Answers the request read from `stream`, then closes the connection.
+fn answer(mut stream: TcpStream, state: &Mutex<State>, delay: Duration) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let (mut content_length, mut operation, mut region) = (0, String::new(), String::new());
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap() == 0 {
+            return;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().unwrap();
+            } else if name.eq_ignore_ascii_case("x-amz-target") {
+                operation = value.trim_start_matches("secretsmanager.").to_owned();
+            } else if name.eq_ignore_ascii_case("authorization") {
+                // `Credential=<key>/<date>/<region>/secretsmanager/aws4_request`
+                region = value
+                    .split("Credential=")
+                    .nth(1)
+                    .and_then(|scope| scope.split('/').nth(2))
+                    .unwrap_or_default()
+                    .to_owned();
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+    let request: Value = serde_json::from_slice(&body).unwrap();
+    // The ARNs it answers with name the secrets without the suffix of real ones.
+    let secret_id = request["SecretId"].as_str().unwrap_or_default();
+    let name = match secret_id.split_once(":secret:") {
+        Some((_, name)) if secret_id.starts_with("arn:") => name.to_owned(),
+        _ => secret_id.to_owned(),
+    };
+    std::thread::sleep(delay);
+
+    let (status, body) = respond(
+        &mut state.lock().unwrap(),
+        &operation,
+        &region,
+        &name,
+        &request,
+    );
+    let body = body.to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/x-amz-json-1.1\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+}
+
+fn respond(
+    state: &mut State,
+    operation: &str,
+    region: &str,
+    name: &str,
+    request: &Value,
+) -> (&'static str, Value) {
+    if operation == "GetSecretValue" {
+        *state
+            .reads
+            .entry((name.to_owned(), region.to_owned()))
+            .or_default() += 1;
+    }
+    let failure = state
+        .failures
+        .iter_mut()
+        .find(|(secret, in_region, _, times)| {
+            secret == name
+                && in_region
+                    .as_deref()
+                    .is_none_or(|in_region| in_region == region)
+                && *times > 0
+        });
+    if let Some((_, _, error, times)) = failure {
+        *times -= 1;
+        let status = match *error {
+            "InternalServiceError" => "500 Internal Server Error",
+            _ => "400 Bad Request",
+        };
+        return (
+            status,
+            json!({ "__type": error, "message": "failed by the test" }),
+        );
+    }
+
+    let arn = format!(
+        "arn:aws:secretsmanager:{}:000000000000:secret:{}",
+        region, name
+    );
+    let secret = match state.secrets.get_mut(name) {
+        Some(secret) => secret,
+        None => {
+            return (
+                "400 Bad Request",
+                json!({
+                    "__type": "ResourceNotFoundException",
+                    "message": "Secrets Manager can't find the specified secret.",
+                }),
+            )
+        }
+    };
+    let version_id = |index: usize| format!("00000000-0000-0000-0000-{:012}", index + 1);
+    match operation {
+        "GetSecretValue" => {
+            let index = secret.versions.len() - 1;
+            (
+                "200 OK",
+                json!({
+                    "ARN": arn,
+                    "Name": name,
+                    "SecretString": secret.versions[index],
+                    "VersionId": version_id(index),
+                    "VersionStages": ["AWSCURRENT"],
+                }),
+            )
+        }
+        "DescribeSecret" => {
+            if let Some(pending) = secret.pending.take() {
+                secret.versions.push(pending);
+            }
+            let current = version_id(secret.versions.len() - 1);
+            (
+                "200 OK",
+                json!({
+                    "ARN": arn,
+                    "Name": name,
+                    "VersionIdsToStages": { current: ["AWSCURRENT"] },
+                }),
+            )
+        }
+        "PutSecretValue" | "UpdateSecret" => {
+            let payload = request["SecretString"].as_str().unwrap_or_default();
+            secret.versions.push(payload.to_owned());
+            (
+                "200 OK",
+                json!({
+                    "ARN": arn,
+                    "Name": name,
+                    "VersionId": version_id(secret.versions.len() - 1),
+                    "VersionStages": ["AWSCURRENT"],
+                }),
+            )
+        }
+        "RotateSecret" => match secret.rotated.take() {
+            Some(rotated) => {
+                let pending = version_id(secret.versions.len());
+                secret.pending = Some(rotated);
+                (
+                    "200 OK",
+                    json!({ "ARN": arn, "Name": name, "VersionId": pending }),
+                )
+            }
+            None => (
+                "400 Bad Request",
+                json!({
+                    "__type": "InvalidRequestException",
+                    "message": "the secret has no rotation function",
+                }),
+            ),
+        },
+        _ => (
+            "400 Bad Request",
+            json!({ "__type": "UnknownOperationException", "message": operation }),
+        ),
+    }
+}
@@ -0,0 +1,66 @@
+//! The common secrets embedded by `#[gsm(common)]` fields, and the errors of their payloads, which name the common secret.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+use global_secrets_manager_runtime::GsmError;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            ("common/base", r#"{"endpoint":"https://base"}"#),
+            ("common/service", r#"{"key":"service"}"#),
+            ("common/list", r#"["https://base"]"#),
+            ("common/listed", r#"{"key":"listed"}"#),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[global_secret(name = "common/base", no_static)]
+pub struct Base {
+    endpoint: String,
+}
+
+#[global_secret(name = "common/service", no_static)]
+pub struct Service {
+    #[gsm(common)]
+    base: Base,
+    key: String,
+}
+
+#[global_secret(name = "common/list", no_static)]
+pub struct List {
+    endpoint: String,
+}
+
+#[global_secret(name = "common/listed", no_static)]
+pub struct Listed {
+    #[gsm(common)]
+    base: List,
+    key: String,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn a_common_field_is_read_from_the_secret_of_its_struct() {
+    let secrets = block_on(Service::fetch()).unwrap();
+    assert_eq!(secrets.base.endpoint, "https://base");
+    assert_eq!(secrets.key, "service");
+    assert_eq!(SECRETS_MANAGER.requests("common/base"), 1);
+}
+
+#[test]
+fn an_error_of_the_common_payload_names_the_common_secret() {
+    let err = block_on(Listed::fetch()).err().unwrap();
+    let err = GsmError::find(&*err).unwrap();
+    assert!(matches!(err, GsmError::Decode { .. }));
+    assert_eq!(err.secret(), "common/list");
+}
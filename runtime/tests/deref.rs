@@ -0,0 +1,68 @@
+//! The values of `#[gsm(deref_secret)]` fields, read from the secrets they refer to.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+use global_secrets_manager_runtime::serde::Deserialize;
+use global_secrets_manager_runtime::GsmError;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            (
+                "deref/billing",
+                r#"{"db":"arn:aws:secretsmanager:us-east-1:000000000000:secret:deref/db","stripe_key":"deref/stripe"}"#,
+            ),
+            ("deref/db", r#"{"user":"billing","password":"hunter2"}"#),
+            ("deref/stripe", "sk_test"),
+            ("deref/dangling", r#"{"stripe_key":"deref/missing"}"#),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[derive(Deserialize)]
+#[serde(crate = "global_secrets_manager_runtime::serde")]
+pub struct Db {
+    user: String,
+    password: String,
+}
+
+#[global_secret(name = "deref/billing", no_static)]
+pub struct Billing {
+    #[gsm(deref_secret)]
+    db: Db,
+    #[gsm(deref_secret)]
+    stripe_key: String,
+}
+
+#[global_secret(name = "deref/dangling", no_static)]
+pub struct Dangling {
+    #[gsm(deref_secret)]
+    stripe_key: String,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn the_references_are_replaced_by_the_values_of_their_secrets() {
+    let secrets = block_on(Billing::fetch()).unwrap();
+    assert_eq!(secrets.db.user, "billing");
+    assert_eq!(secrets.db.password, "hunter2");
+    assert_eq!(secrets.stripe_key, "sk_test");
+    assert_eq!(SECRETS_MANAGER.requests("deref/db"), 1);
+}
+
+#[test]
+fn a_reference_to_a_missing_secret_fails() {
+    let err = block_on(Dangling::fetch()).err().unwrap();
+    let err = GsmError::find(&*err).unwrap();
+    assert!(matches!(err, GsmError::NotFound { .. }));
+    assert_eq!(err.secret(), "deref/missing");
+}
@@ -0,0 +1,74 @@
+//! The variants of an enum selected by the variable of `env_var`, each read from the secret of its environment.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::GlobalSecretsManager;
+use global_secrets_manager_runtime::aws_sdk_secretsmanager::Client;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+use global_secrets_manager_runtime::serde::Deserialize;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            ("DevSecrets", r#"{"key1":"dev"}"#),
+            ("enum/prod", r#"{"key1":"prod","key2":"prod"}"#),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[derive(Deserialize)]
+#[serde(crate = "global_secrets_manager_runtime::serde")]
+pub struct DevSecrets {
+    key1: String,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "global_secrets_manager_runtime::serde")]
+pub struct ProdSecrets {
+    key1: String,
+    key2: String,
+}
+
+#[derive(GlobalSecretsManager)]
+#[gsm(env_var = "ENUM_STAGE", no_static)]
+pub enum AppSecrets {
+    Dev(DevSecrets),
+    #[gsm(name = "enum/prod", rename = "production")]
+    Prod(ProdSecrets),
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+async fn load() -> Result<AppSecrets, Box<dyn std::error::Error + Send + Sync>> {
+    let client = Client::new(&global_secrets_manager_runtime::config_loader().load().await);
+    AppSecrets::get_with_client(&client).await
+}
+
+// The tests share the variable selecting the variant, so they run one after the other.
+#[test]
+fn the_variable_selects_the_variant_and_its_secret() {
+    std::env::set_var("ENUM_STAGE", "PRODUCTION");
+    match block_on(load()).unwrap() {
+        AppSecrets::Prod(secrets) => assert_eq!(
+            (secrets.key1, secrets.key2),
+            ("prod".to_owned(), "prod".to_owned())
+        ),
+        AppSecrets::Dev(_) => panic!("`production` selects `Prod`"),
+    }
+
+    std::env::set_var("ENUM_STAGE", "dev");
+    match block_on(load()).unwrap() {
+        AppSecrets::Dev(secrets) => assert_eq!(secrets.key1, "dev"),
+        AppSecrets::Prod(_) => panic!("`dev` selects `Dev`"),
+    }
+
+    std::env::set_var("ENUM_STAGE", "staging");
+    let err = block_on(load()).err().unwrap();
+    assert!(err.to_string().contains("staging"), "{}", err);
+}
@@ -0,0 +1,91 @@
+//! The precedence of the variables of `env` and `env_prefix` over the secret, and the deployments configured by the variables alone.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            (
+                "env/precedence",
+                r#"{"host":"secret","port":1,"user":"secret"}"#,
+            ),
+            ("env/fallback", r#"{"host":"secret"}"#),
+            ("env/replaced", r#"{"host":"secret","port":1}"#),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[global_secret(name = "env/precedence", env_prefix = "PRECEDENCE_", no_static)]
+pub struct Precedence {
+    #[gsm(env = "PRECEDENCE_OWN_HOST")]
+    host: String,
+    port: u16,
+    user: String,
+}
+
+#[global_secret(name = "env/fallback", env_prefix = "FALLBACK_", fallback, no_static)]
+pub struct Fallback {
+    host: String,
+    port: u16,
+}
+
+#[global_secret(name = "env/missing", env_prefix = "MISSING_", fallback, no_static)]
+pub struct Missing {
+    host: String,
+}
+
+#[global_secret(name = "env/replaced", env_prefix = "REPLACED_", no_static)]
+pub struct Replaced {
+    host: String,
+    port: u16,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn a_variable_of_its_own_comes_before_the_prefix_and_the_secret() {
+    std::env::set_var("PRECEDENCE_OWN_HOST", "own");
+    std::env::set_var("PRECEDENCE_HOST", "prefix");
+    std::env::set_var("PRECEDENCE_PORT", "2");
+    let secrets = block_on(Precedence::fetch()).unwrap();
+    assert_eq!(secrets.host, "own");
+    assert_eq!(secrets.port, 2);
+    assert_eq!(secrets.user, "secret");
+}
+
+#[test]
+fn fallback_variables_only_fill_in_the_missing_keys() {
+    std::env::set_var("FALLBACK_HOST", "prefix");
+    std::env::set_var("FALLBACK_PORT", "3");
+    let secrets = block_on(Fallback::fetch()).unwrap();
+    assert_eq!(secrets.host, "secret");
+    assert_eq!(secrets.port, 3);
+}
+
+#[test]
+fn a_missing_secret_is_read_as_empty() {
+    std::env::set_var("MISSING_HOST", "prefix");
+    let secrets = block_on(Missing::fetch()).unwrap();
+    assert_eq!(secrets.host, "prefix");
+    assert_eq!(SECRETS_MANAGER.requests("env/missing"), 1);
+}
+
+#[test]
+fn the_secret_isnt_fetched_when_the_variables_replace_every_key() {
+    SECRETS_MANAGER.fail("env/replaced", "AccessDeniedException", 1);
+    std::env::set_var("REPLACED_HOST", "prefix");
+    std::env::set_var("REPLACED_PORT", "4");
+    let secrets = block_on(Replaced::fetch()).unwrap();
+    assert_eq!(secrets.host, "prefix");
+    assert_eq!(secrets.port, 4);
+    assert_eq!(SECRETS_MANAGER.requests("env/replaced"), 0);
+}
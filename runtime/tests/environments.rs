@@ -0,0 +1,58 @@
+//! The blocks of `env(...)` selected by the variable of `env_var`, and the loads failing when it names none.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            ("environments/prod", r#"{"key1":"prod"}"#),
+            ("environments/dev", r#"{"key1":"dev"}"#),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[global_secret(
+    env_var = "SELECTED_STAGE",
+    env("prod", name = "environments/prod"),
+    env("dev", name = "environments/dev"),
+    no_static
+)]
+pub struct Selected {
+    key1: String,
+}
+
+#[global_secret(
+    env_var = "UNKNOWN_STAGE",
+    env("prod", name = "environments/prod"),
+    no_static
+)]
+pub struct Unknown {
+    key1: String,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn the_variable_selects_the_secret_of_its_block() {
+    std::env::set_var("SELECTED_STAGE", "dev");
+    let secrets = block_on(Selected::fetch()).unwrap();
+    assert_eq!(secrets.key1, "dev");
+    assert_eq!(SECRETS_MANAGER.requests("environments/dev"), 1);
+}
+
+#[test]
+fn a_variable_naming_no_block_fails_the_load() {
+    std::env::set_var("UNKNOWN_STAGE", "staging");
+    let err = block_on(Unknown::fetch()).err().unwrap();
+    assert!(err.to_string().contains("staging"), "{}", err);
+    assert_eq!(SECRETS_MANAGER.requests("environments/prod"), 0);
+}
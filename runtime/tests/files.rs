@@ -0,0 +1,37 @@
+//! `write_secret_to_path()` writes the value of the secret as it is stored to a new file, only readable by its owner.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::GlobalSecretsManager;
+use global_secrets_manager_runtime::aws_sdk_secretsmanager::Client;
+
+#[derive(GlobalSecretsManager)]
+#[gsm(name = "files/bundle")]
+pub struct Bundle;
+
+#[test]
+fn the_value_is_written_to_a_new_file() {
+    let _secrets_manager =
+        common::SecretsManager::start(&[("files/bundle", "not json")], Duration::ZERO);
+    let path = std::env::temp_dir().join(format!("gsm-bundle-{}", std::process::id()));
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let client = Client::new(&global_secrets_manager_runtime::config_loader().load().await);
+        assert_eq!(
+            Bundle::write_secret_to_path(&client, &path).await.unwrap(),
+            8
+        );
+        // An existing file isn't overwritten.
+        assert!(Bundle::write_secret_to_path(&client, &path).await.is_err());
+    });
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "not json");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,51 @@
+//! The instances read by `get()`, each from its own secret and cached separately.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            ("eu/payments", r#"{"api_key":"eu"}"#),
+            ("us/payments", r#"{"api_key":"us"}"#),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[global_secret(
+    name = "{PAYMENTS_REGION}/payments",
+    instances = "PAYMENTS_REGION",
+    no_static
+)]
+pub struct Payments {
+    api_key: String,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn each_instance_is_read_from_its_own_secret_once() {
+    block_on(async {
+        let eu = Payments::get("eu").await.unwrap();
+        let us = Payments::get("us").await.unwrap();
+        assert_eq!(eu.api_key, "eu");
+        assert_eq!(us.api_key, "us");
+        Payments::get("eu").await.unwrap();
+    });
+    assert_eq!(SECRETS_MANAGER.requests("eu/payments"), 1);
+    assert_eq!(SECRETS_MANAGER.requests("us/payments"), 1);
+}
+
+#[test]
+fn an_instance_naming_another_secret_is_rejected() {
+    let err = block_on(Payments::get("eu/../us")).err().unwrap();
+    assert!(err.to_string().contains("eu/../us"), "{}", err);
+}
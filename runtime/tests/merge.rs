@@ -0,0 +1,121 @@
+//! The order in which the secrets of `extends` are merged, and how the values of nested objects and arrays are merged.
+
+// The global constants are named after their structs.
+#![allow(non_upper_case_globals)]
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+use global_secrets_manager_runtime::serde::Deserialize;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            (
+                "merge/base",
+                r#"{"host":"base","port":5432,"pool":{"size":5,"timeout":30},"replicas":["a","b"]}"#,
+            ),
+            ("merge/db", r#"{"host":"db","user":"db"}"#),
+            (
+                "merge/app",
+                r#"{"host":"app","pool":{"size":10},"replicas":["c"]}"#,
+            ),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(crate = "global_secrets_manager_runtime::serde")]
+pub struct Pool {
+    size: u32,
+    timeout: Option<u32>,
+}
+
+#[global_secret(name = "merge/app", extends = "merge/base", extends = "merge/db")]
+pub struct LastWins {
+    host: String,
+    port: u16,
+    user: String,
+    pool: Pool,
+    replicas: Vec<String>,
+}
+
+#[global_secret(
+    name = "merge/app",
+    extends = "merge/base",
+    extends = "merge/db",
+    merge = "first_wins"
+)]
+pub struct FirstWins {
+    host: String,
+    port: u16,
+    user: String,
+    pool: Pool,
+    replicas: Vec<String>,
+}
+
+#[global_secret(name = "merge/app", extends = "merge/base", extends = "merge/db")]
+pub struct PerField {
+    #[gsm(merge = "first_wins")]
+    host: String,
+    pool: Pool,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn later_secrets_override_earlier_ones() {
+    let secrets = block_on(LastWins::try_preload()).unwrap();
+    assert_eq!(secrets.host, "app");
+    assert_eq!(secrets.user, "db");
+    assert_eq!(secrets.port, 5432);
+}
+
+#[test]
+fn first_wins_keeps_the_earliest_value() {
+    let secrets = block_on(FirstWins::try_preload()).unwrap();
+    assert_eq!(secrets.host, "base");
+    assert_eq!(secrets.user, "db");
+    assert_eq!(
+        secrets.pool,
+        Pool {
+            size: 5,
+            timeout: Some(30)
+        }
+    );
+    assert_eq!(secrets.replicas, ["a", "b"]);
+}
+
+#[test]
+fn a_field_overrides_the_order_of_the_struct() {
+    let secrets = block_on(PerField::try_preload()).unwrap();
+    assert_eq!(secrets.host, "base");
+    assert_eq!(
+        secrets.pool,
+        Pool {
+            size: 10,
+            timeout: None
+        }
+    );
+}
+
+#[test]
+fn nested_objects_and_arrays_are_taken_whole() {
+    let secrets = block_on(LastWins::try_preload()).unwrap();
+    // `timeout` of the base isn't merged into the pool of the app.
+    assert_eq!(
+        secrets.pool,
+        Pool {
+            size: 10,
+            timeout: None
+        }
+    );
+    assert_eq!(secrets.replicas, ["c"]);
+}
@@ -0,0 +1,54 @@
+//! The parts of a secret read by `pointer`, for the struct and for its fields.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+use global_secrets_manager_runtime::GsmError;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[(
+            "pointer/shared",
+            r#"{"services":{"billing":{"api_key":"billing","credentials":{"db":{"password":"hunter2"}}}}}"#,
+        )],
+        Duration::ZERO,
+    )
+});
+
+#[global_secret(name = "pointer/shared", pointer = "/services/billing", no_static)]
+pub struct Billing {
+    #[gsm(pointer = "/credentials/db/password")]
+    db_password: String,
+    #[gsm(pointer = "/credentials/db/user")]
+    db_user: Option<String>,
+    api_key: String,
+}
+
+#[global_secret(name = "pointer/shared", pointer = "/services/payroll", no_static)]
+pub struct Payroll {
+    api_key: String,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn the_pointers_of_the_fields_are_relative_to_the_struct() {
+    let secrets = block_on(Billing::fetch()).unwrap();
+    assert_eq!(secrets.db_password, "hunter2");
+    assert_eq!(secrets.db_user, None);
+    assert_eq!(secrets.api_key, "billing");
+}
+
+#[test]
+fn a_struct_pointer_that_does_not_match_fails() {
+    let err = block_on(Payroll::fetch()).err().unwrap();
+    let err = GsmError::find(&*err).unwrap();
+    assert!(matches!(err, GsmError::Decode { .. }));
+    assert_eq!(err.secret(), "pointer/shared");
+}
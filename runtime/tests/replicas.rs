@@ -0,0 +1,80 @@
+//! The reads of `#[gsm(replicas(...))]` move on to the next region on the failures of a region, and only on those.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+use global_secrets_manager_runtime::GsmError;
+
+const PRIMARY: &str = "us-east-1";
+const REPLICA: &str = "us-west-2";
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            ("replicas/failover", r#"{"key":"value"}"#),
+            ("replicas/unhealthy", r#"{"key":"value"}"#),
+            ("replicas/denied", r#"{"key":"value"}"#),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[global_secret(name = "replicas/failover", no_static, replicas("us-west-2"))]
+pub struct Failover {
+    key: String,
+}
+
+#[global_secret(name = "replicas/unhealthy", no_static, replicas("us-west-2"))]
+pub struct Unhealthy {
+    key: String,
+}
+
+#[global_secret(name = "replicas/denied", no_static, replicas("us-west-2"))]
+pub struct Denied {
+    key: String,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn a_failed_read_moves_on_to_the_replica() {
+    SECRETS_MANAGER.fail_in(PRIMARY, "replicas/failover", "InternalServiceError", 1);
+    let secrets = block_on(Failover::fetch()).unwrap();
+    assert_eq!(secrets.key, "value");
+    assert_eq!(SECRETS_MANAGER.requests_in(PRIMARY, "replicas/failover"), 1);
+    assert_eq!(SECRETS_MANAGER.requests_in(REPLICA, "replicas/failover"), 1);
+}
+
+#[test]
+fn an_unhealthy_region_is_read_last() {
+    SECRETS_MANAGER.fail_in(PRIMARY, "replicas/unhealthy", "InternalServiceError", 3);
+    for _ in 0..4 {
+        block_on(Unhealthy::fetch()).unwrap();
+    }
+    // The third failure out of three reads made the primary unhealthy, so the fourth read went to the replica alone.
+    assert_eq!(
+        SECRETS_MANAGER.requests_in(PRIMARY, "replicas/unhealthy"),
+        3
+    );
+    assert_eq!(
+        SECRETS_MANAGER.requests_in(REPLICA, "replicas/unhealthy"),
+        4
+    );
+}
+
+#[test]
+fn errors_of_every_region_are_returned_at_once() {
+    SECRETS_MANAGER.fail_in(PRIMARY, "replicas/denied", "AccessDeniedException", 1);
+    let err = block_on(Denied::fetch()).err().unwrap();
+    assert!(matches!(
+        GsmError::find(&*err),
+        Some(GsmError::AccessDenied { .. })
+    ));
+    assert_eq!(SECRETS_MANAGER.requests_in(REPLICA, "replicas/denied"), 0);
+}
@@ -0,0 +1,81 @@
+//! The fetches failing with the kinds of errors of `#[gsm(retry(...))]` are retried up to their number of retries, and the others fail at once.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+use global_secrets_manager_runtime::GsmError;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            ("retry/recovered", r#"{"key":"value"}"#),
+            ("retry/exhausted", r#"{"key":"value"}"#),
+            ("retry/denied", r#"{"key":"value"}"#),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[global_secret(
+    name = "retry/recovered",
+    no_static,
+    retry(throttled(retries = 2, backoff = "10ms"))
+)]
+pub struct Recovered {
+    key: String,
+}
+
+#[global_secret(
+    name = "retry/exhausted",
+    no_static,
+    retry(throttled(retries = 2, backoff = "10ms"))
+)]
+pub struct Exhausted {
+    key: String,
+}
+
+#[global_secret(
+    name = "retry/denied",
+    no_static,
+    retry(throttled(retries = 2, backoff = "10ms"))
+)]
+pub struct Denied {
+    key: String,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+/// The kind of the `GsmError` of `err`.
+fn kind(err: &global_secrets_manager_runtime::BoxError) -> &'static str {
+    GsmError::find(&**err).map_or("other", GsmError::kind)
+}
+
+#[test]
+fn throttled_fetches_are_retried() {
+    SECRETS_MANAGER.fail("retry/recovered", "ThrottlingException", 2);
+    let secrets = block_on(Recovered::fetch()).unwrap();
+    assert_eq!(secrets.key, "value");
+    assert_eq!(SECRETS_MANAGER.requests("retry/recovered"), 3);
+}
+
+#[test]
+fn the_last_error_is_returned_after_the_retries() {
+    SECRETS_MANAGER.fail("retry/exhausted", "ThrottlingException", 3);
+    let err = block_on(Exhausted::fetch()).err().unwrap();
+    assert_eq!(kind(&err), "throttled");
+    assert_eq!(SECRETS_MANAGER.requests("retry/exhausted"), 3);
+}
+
+#[test]
+fn other_kinds_of_errors_are_not_retried() {
+    SECRETS_MANAGER.fail("retry/denied", "AccessDeniedException", 3);
+    let err = block_on(Denied::fetch()).err().unwrap();
+    assert_eq!(kind(&err), "access_denied");
+    assert_eq!(SECRETS_MANAGER.requests("retry/denied"), 1);
+}
@@ -0,0 +1,78 @@
+//! `rotate_now()` waits for the rotated version to become `AWSCURRENT`, and replaces the global constant stored in a lock.
+
+// The global constants are named after their structs.
+#![allow(non_upper_case_globals)]
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::aws_sdk_secretsmanager::Client;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            ("rotate/immutable", r#"{"password":"old"}"#),
+            ("rotate/locked", r#"{"password":"old"}"#),
+            ("rotate/manual", r#"{"password":"old"}"#),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[global_secret(name = "rotate/immutable")]
+pub struct Immutable {
+    password: String,
+}
+
+#[global_secret(name = "rotate/locked", storage = "rwlock")]
+pub struct Locked {
+    password: String,
+}
+
+#[global_secret(name = "rotate/manual", no_static)]
+pub struct Manual {
+    password: String,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+async fn client() -> Client {
+    Client::new(&global_secrets_manager_runtime::config_loader().load().await)
+}
+
+#[test]
+fn the_immutable_constant_keeps_its_value() {
+    SECRETS_MANAGER.rotates_to("rotate/immutable", r#"{"password":"new"}"#);
+    let rotated = block_on(async {
+        Immutable::try_preload().await.unwrap();
+        Immutable::rotate_now(&client().await, Some(Duration::from_secs(5))).await
+    });
+    assert_eq!(rotated.unwrap().password, "new");
+    assert_eq!(Immutable.password, "old");
+    // The stages are described, so the value is only read by the load and the rotated value.
+    assert_eq!(SECRETS_MANAGER.requests("rotate/immutable"), 2);
+}
+
+#[test]
+fn the_constant_in_a_lock_is_replaced() {
+    SECRETS_MANAGER.rotates_to("rotate/locked", r#"{"password":"new"}"#);
+    let rotated = block_on(async {
+        Locked::try_preload().await.unwrap();
+        Locked::rotate_now(&client().await, Some(Duration::from_secs(5))).await
+    });
+    assert_eq!(rotated.unwrap().password, "new");
+    assert_eq!(Locked::read().password, "new");
+}
+
+#[test]
+fn a_secret_without_rotation_function_fails() {
+    let rotated = block_on(async { Manual::rotate_now(&client().await, None).await });
+    assert!(rotated.is_err());
+    assert_eq!(SECRETS_MANAGER.requests("rotate/manual"), 0);
+}
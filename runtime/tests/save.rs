@@ -0,0 +1,46 @@
+//! `save()` writes the value of a writable type as the new version of the secret, which the next reads get.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::aws_sdk_secretsmanager::Client;
+use global_secrets_manager_runtime::serde::{Deserialize, Serialize};
+
+#[global_secret(name = "save/webhooks", writable, no_static)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(crate = "global_secrets_manager_runtime::serde")]
+pub struct WebhookKeys {
+    signing_key: String,
+    previous_key: Option<String>,
+}
+
+#[test]
+fn saved_values_are_read_back() {
+    let secrets_manager = common::SecretsManager::start(
+        &[(
+            "save/webhooks",
+            r#"{"signing_key":"old","previous_key":null}"#,
+        )],
+        Duration::ZERO,
+    );
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let client = Client::new(&global_secrets_manager_runtime::config_loader().load().await);
+        let keys = WebhookKeys {
+            signing_key: "new".to_owned(),
+            previous_key: Some("old".to_owned()),
+        };
+        assert_eq!(
+            keys.save_dry_run(&client).await.unwrap(),
+            ["changed `previous_key`", "changed `signing_key`"]
+        );
+        keys.save(&client).await.unwrap();
+        assert_eq!(WebhookKeys::fetch().await.unwrap(), keys);
+    });
+    assert_eq!(
+        secrets_manager.current("save/webhooks").unwrap(),
+        r#"{"signing_key":"new","previous_key":"old"}"#
+    );
+}
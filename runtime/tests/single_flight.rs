@@ -0,0 +1,50 @@
+//! Concurrent first reads of a global constant, through `try_preload()`, `current()` and dereferencing, fetch the secret once.
+
+// The global constant is named after its struct.
+#![allow(non_upper_case_globals)]
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+
+#[global_secret(name = "single-flight")]
+pub struct SingleFlight {
+    key: String,
+}
+
+#[test]
+fn concurrent_first_reads_fetch_the_secret_once() {
+    let secrets_manager = common::SecretsManager::start(
+        &[("single-flight", r#"{"key":"value"}"#)],
+        Duration::from_millis(200),
+    );
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let preloads: Vec<_> = (0..8)
+        .map(|_| {
+            runtime.spawn(async {
+                SingleFlight::try_preload()
+                    .await
+                    .map(|secrets| secrets.key.clone())
+            })
+        })
+        .collect();
+    let reads: Vec<_> = (0..8)
+        .map(|i| {
+            std::thread::spawn(move || match i % 2 {
+                0 => SingleFlight::current().unwrap().key.clone(),
+                _ => SingleFlight.key.clone(),
+            })
+        })
+        .collect();
+
+    for preload in preloads {
+        assert_eq!(runtime.block_on(preload).unwrap().unwrap(), "value");
+    }
+    for read in reads {
+        assert_eq!(read.join().unwrap(), "value");
+    }
+    assert_eq!(secrets_manager.requests("single-flight"), 1);
+}
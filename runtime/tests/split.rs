@@ -0,0 +1,47 @@
+//! The lists kept as single strings, split by `split`, and the values already arrays, left as they are.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[(
+            "split/api",
+            r#"{"origins":"https://app.example.com, ,https://admin.example.com","ports":"8080;8443","hosts":["a","b"],"none":""}"#,
+        )],
+        Duration::ZERO,
+    )
+});
+
+#[global_secret(name = "split/api", no_static)]
+pub struct Api {
+    #[gsm(split)]
+    origins: Vec<String>,
+    #[gsm(split = ";")]
+    ports: Vec<u16>,
+    #[gsm(split)]
+    hosts: Vec<String>,
+    #[gsm(split)]
+    none: Vec<String>,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn strings_are_split_into_the_items_of_their_lists() {
+    let secrets = block_on(Api::fetch()).unwrap();
+    assert_eq!(
+        secrets.origins,
+        ["https://app.example.com", "https://admin.example.com"]
+    );
+    assert_eq!(secrets.ports, [8080, 8443]);
+    assert_eq!(secrets.hosts, ["a", "b"]);
+    assert!(secrets.none.is_empty());
+}
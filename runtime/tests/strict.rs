@@ -0,0 +1,56 @@
+//! The loads of `strict` structs failing on the keys they don't model, except those of `allow_extra`.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+use global_secrets_manager_runtime::GsmError;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            (
+                "strict/allowed",
+                r#"{"api_key":"key","legacy_token":"old","old_api_key":"old"}"#,
+            ),
+            ("strict/extra", r#"{"api_key":"key","stray_key":"stray"}"#),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[global_secret(
+    name = "strict/allowed",
+    strict,
+    allow_extra("legacy_*", "old_api_key"),
+    no_static
+)]
+pub struct Allowed {
+    api_key: String,
+}
+
+#[global_secret(name = "strict/extra", strict, allow_extra("legacy_*"), no_static)]
+pub struct Extra {
+    api_key: String,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn the_keys_of_allow_extra_are_accepted() {
+    let secrets = block_on(Allowed::fetch()).unwrap();
+    assert_eq!(secrets.api_key, "key");
+}
+
+#[test]
+fn the_other_keys_fail_the_load_by_name() {
+    let err = block_on(Extra::fetch()).err().unwrap();
+    assert!(err.to_string().contains("stray_key"), "{}", err);
+    let err = GsmError::find(&*err).unwrap();
+    assert_eq!(err.secret(), "strict/extra");
+}
@@ -0,0 +1,49 @@
+//! The secrets of the tenants read by `for_tenant()`, cached for each tenant, and the ids that can't name a secret.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            ("tenants/acme/db", r#"{"username":"acme"}"#),
+            ("tenants/globex/db", r#"{"username":"globex"}"#),
+        ],
+        Duration::ZERO,
+    )
+});
+
+#[global_secret(tenant(name = "tenants/{tenant}/db"), no_static)]
+pub struct TenantDb {
+    username: String,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn each_tenant_reads_its_own_secret_once() {
+    block_on(async {
+        let acme = TenantDb::for_tenant("acme").await.unwrap();
+        let globex = TenantDb::for_tenant("globex").await.unwrap();
+        assert_eq!(acme.username, "acme");
+        assert_eq!(globex.username, "globex");
+        TenantDb::for_tenant("acme").await.unwrap();
+    });
+    assert_eq!(SECRETS_MANAGER.requests("tenants/acme/db"), 1);
+    assert_eq!(SECRETS_MANAGER.requests("tenants/globex/db"), 1);
+}
+
+#[test]
+fn an_id_naming_another_secret_is_rejected() {
+    let err = block_on(TenantDb::for_tenant("acme/../globex"))
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("acme/../globex"), "{}", err);
+}
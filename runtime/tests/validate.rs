@@ -0,0 +1,87 @@
+//! The loads failing the checks of `validate` and of the fields, as validation errors of the secret.
+
+mod common;
+
+use std::time::Duration;
+
+use global_secrets_manager::global_secret;
+use global_secrets_manager_runtime::once_cell::sync::Lazy;
+use global_secrets_manager_runtime::GsmError;
+
+static SECRETS_MANAGER: Lazy<common::SecretsManager> = Lazy::new(|| {
+    common::SecretsManager::start(
+        &[
+            (
+                "validate/valid",
+                r#"{"role_arn":"arn:aws:iam::0:role/app"}"#,
+            ),
+            ("validate/function", r#"{"role_arn":"role/app"}"#),
+            ("validate/field", r#"{"api_key":""}"#),
+        ],
+        Duration::ZERO,
+    )
+});
+
+fn check_valid(secrets: &Valid) -> Result<(), String> {
+    check(&secrets.role_arn)
+}
+
+fn check_function(secrets: &Function) -> Result<(), String> {
+    check(&secrets.role_arn)
+}
+
+fn check(role_arn: &str) -> Result<(), String> {
+    if role_arn.starts_with("arn:") {
+        Ok(())
+    } else {
+        Err("`role_arn` is not an ARN".to_owned())
+    }
+}
+
+#[global_secret(name = "validate/valid", validate = "check_valid", no_static)]
+pub struct Valid {
+    role_arn: String,
+}
+
+#[global_secret(name = "validate/function", validate = "check_function", no_static)]
+pub struct Function {
+    role_arn: String,
+}
+
+#[global_secret(name = "validate/field", no_static)]
+pub struct Field {
+    #[gsm(non_empty)]
+    api_key: String,
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    Lazy::force(&SECRETS_MANAGER);
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+#[test]
+fn a_secret_passing_the_checks_is_loaded() {
+    let secrets = block_on(Valid::fetch()).unwrap();
+    assert_eq!(secrets.role_arn, "arn:aws:iam::0:role/app");
+}
+
+#[test]
+fn the_error_of_the_function_fails_the_load() {
+    let err = block_on(Function::fetch()).err().unwrap();
+    let err = GsmError::find(&*err).unwrap();
+    assert!(matches!(err, GsmError::Validation { .. }));
+    assert_eq!(err.secret(), "validate/function");
+    assert!(
+        err.to_string().contains("`role_arn` is not an ARN"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn a_check_of_a_field_fails_the_load() {
+    let err = block_on(Field::fetch()).err().unwrap();
+    let err = GsmError::find(&*err).unwrap();
+    assert!(matches!(err, GsmError::Validation { .. }));
+    assert!(err.to_string().contains("api_key"), "{}", err);
+}
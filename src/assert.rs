@@ -0,0 +1,57 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, Result, Token, TypePath};
+
+/// Input of `assert_secret_schema!(Type)` or `assert_secret_schema!(Type, test_name)`.
+pub struct AssertSchema {
+    ty: TypePath,
+    name: Option<Ident>,
+}
+
+impl Parse for AssertSchema {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ty = input.parse()?;
+        let name = if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        input.parse::<Option<Token![,]>>()?;
+        Ok(Self { ty, name })
+    }
+}
+
+/// A test fetching the secret of the type with the credentials of the environment, checking its keys and then deserializing it.
+pub fn expand(input: AssertSchema) -> TokenStream {
+    let AssertSchema { ty, name } = input;
+    let type_name = ty.path.segments.last().unwrap().ident.to_string();
+    let name =
+        name.unwrap_or_else(|| format_ident!("{}_matches_its_secret", snake_case(&type_name)));
+    quote! {
+        #[test]
+        fn #name() {
+            global_secrets_manager_runtime::dotenvy::dotenv().ok();
+            global_secrets_manager_runtime::executor::block_on(async {
+                let shared_config = global_secrets_manager_runtime::config_loader().load().await;
+                let client = global_secrets_manager_runtime::aws_sdk_secretsmanager::Client::new(&shared_config);
+                let diff = <#ty>::verify_schema(&client).await.unwrap();
+                assert!(diff.is_empty(), "the secret of `{}` doesn't match its fields: {:?}", #type_name, diff);
+                if let Err(err) = <#ty>::get_with_client(&client).await {
+                    panic!("the secret of `{}` doesn't deserialize: {}", #type_name, err);
+                }
+            }).unwrap();
+        }
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.char_indices() {
+        if c.is_uppercase() && i > 0 {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
@@ -0,0 +1,1323 @@
+use syn::spanned::Spanned;
+use syn::{Attribute, Error, Field, Ident, Lit, LitStr, Meta, MetaList, NestedMeta, Path, Result};
+
+/// Options given to the container, either by `#[gsm(...)]` on the type or by the arguments of `#[global_secret(...)]`.
+#[derive(Default)]
+pub struct ContainerAttrs {
+    /// Name of the secret in Secrets Manager. Defaults to the name of the type.
+    pub name: Option<LitStr>,
+    /// Environment variable selecting the variant of an enum. Defaults to `APP_ENV`.
+    pub env_var: Option<LitStr>,
+    /// Name of the accessor trait. Defaults to the name of the type followed by `Provider`.
+    pub provider: Option<Ident>,
+    /// What the secret holds, for the helpers generated for well-known kinds of credentials.
+    pub kind: Option<Kind>,
+    /// Set by `#[gsm(writable)]` to generate the methods writing the secret.
+    pub writable: Option<Path>,
+    /// Set by `#[gsm(redact_keys)]` to keep the names of the keys out of the error messages.
+    pub redact_keys: Option<Path>,
+    /// Set by `#[gsm(strict)]` to fail the load when the secret has keys that no field reads.
+    pub strict: Option<Path>,
+    /// Globs of the keys `strict` lets through unread, set by `#[gsm(allow_extra("legacy_*"))]`.
+    pub allow_extra: Vec<LitStr>,
+    /// Set by `#[gsm(test_default)]` to implement `Default` where the builders are generated, for placeholder secrets in tests.
+    pub test_default: Option<Path>,
+    /// Set by `#[gsm(no_static)]` to generate `fetch()` and `load()` without the global constant, for libraries that must not hold process-wide state.
+    pub no_static: Option<Path>,
+    /// Tags identifying the secret in place of its name, set by `#[gsm(tags = "key=value, ...")]`.
+    pub tags: Option<Vec<(String, String)>>,
+    /// Function called with the reads of the secret, set by `#[gsm(audit = "path::to::hook")]`.
+    pub audit: Option<Path>,
+    /// Age in seconds past which a secret not rotated is reported, set by `#[gsm(max_age = "90d")]`.
+    pub max_age: Option<u64>,
+    /// Function called with the age of a secret older than `max_age`, set by `#[gsm(on_stale = "path::to::hook")]`.
+    pub on_stale: Option<Path>,
+    /// Prefix of the variables returned by `as_env_vars()`, generated when set by `#[gsm(export)]` or `#[gsm(export = "APP_")]`.
+    pub export: Option<LitStr>,
+    /// Prefix of the environment variables overriding the keys, named after the fields in uppercase, set by `#[gsm(env_prefix = "APP_")]`.
+    pub env_prefix: Option<LitStr>,
+    /// Set by `#[gsm(fallback)]` for the variables of `env_prefix` to only fill in the keys that the secret lacks.
+    pub fallback: Option<Path>,
+    /// Function checking the values of the loaded secret, set by `#[gsm(validate = "path::to::check")]`.
+    pub validate: Option<Path>,
+    /// KMS key encrypting the versions written by a writable type, set by `#[gsm(kms_key_id = "alias/app-secrets")]`.
+    pub kms_key_id: Option<LitStr>,
+    /// JSON pointer to the part of the secret holding the struct, set by `#[gsm(pointer = "/app")]`.
+    pub pointer: Option<LitStr>,
+    /// Secrets whose keys are merged under those of the struct's secret, set by repeating `#[gsm(extends = "shared/base")]`.
+    pub extends: Vec<LitStr>,
+    /// Which of the merged secrets wins a key, set by `#[gsm(merge = "first_wins")]`. Defaults to the last one.
+    pub merge: Option<LitStr>,
+    /// Wiring of each environment, selected at load time by `env_var`, set by repeating `#[gsm(env("prod", name = "prod/App"))]`.
+    pub environments: Vec<Environment>,
+    /// Roles assumed in turn to reach the secret of another account, set by `#[gsm(role_chain("arn:a", "arn:b"))]`.
+    pub role_chain: Vec<LitStr>,
+    /// Regions of the replicas the reads switch to while the primary region fails, set by `#[gsm(replicas("us-west-2", "eu-west-1"))]`.
+    pub replicas: Vec<LitStr>,
+    /// Function called when the reads switch to another region, set by `#[gsm(on_region_switch = "path::to::hook")]`.
+    pub on_region_switch: Option<Path>,
+    /// How the global constant is stored, set by `#[gsm(storage = "rwlock")]`. Defaults to the immutable `lazy`.
+    pub storage: Option<LitStr>,
+    /// Longest time the reference of `with_secret()` may be held without a warning, in milliseconds, set by `#[gsm(max_hold = "100ms")]`.
+    pub max_hold: Option<u64>,
+    /// Function called with the time each reference of `with_secret()` was held, set by `#[gsm(on_hold = "path::to::hook")]`.
+    pub on_hold: Option<Path>,
+    /// Name of the application in the user agent of the SDK calls, set by `#[gsm(app_name = "billing-api")]`.
+    pub app_name: Option<LitStr>,
+    /// Retries of the fetches failing with each kind of error, set by `#[gsm(retry(throttled(retries = 5, backoff = "200ms")))]`.
+    pub retry: Vec<Retry>,
+    /// Secrets of the tenants read by `for_tenant()`, set by `#[gsm(tenant(name = "tenants/{tenant}/db"))]`.
+    pub tenant: Option<Tenant>,
+    /// Variable selecting the instance of the secret, and its placeholder in the name, set by `#[gsm(instances = "REGION")]`.
+    /// Each instance is read by `get("eu")`, the global constant reading the one of the variable.
+    pub instances: Option<LitStr>,
+    /// Service the payload is read from instead of Secrets Manager, set by the option naming it, such as `#[gsm(appconfig(...))]`.
+    pub backend: Option<Backend>,
+    /// Bucket of the object read by `#[gsm(backend = "s3")]`, set by `#[gsm(bucket = "app-config")]`.
+    pub bucket: Option<LitStr>,
+    /// Key of the object read by `#[gsm(backend = "s3")]`, set by `#[gsm(key = "prod/app.json")]`.
+    pub key: Option<LitStr>,
+    /// Path of the static secret read by `#[gsm(backend = "akeyless")]`, set by `#[gsm(path = "/prod/app")]`.
+    pub path: Option<LitStr>,
+}
+
+/// A service a struct reads its payload from instead of Secrets Manager.
+pub enum Backend {
+    /// AWS AppConfig, set by `#[gsm(appconfig(application = "billing", environment = "prod", profile = "flags"))]`.
+    AppConfig(AppConfig),
+    /// An object of S3, set by `#[gsm(backend = "s3", bucket = "app-config", key = "prod/app.json")]`.
+    S3(Path),
+    /// Bitwarden Secrets Manager, set by `#[gsm(bitwarden(project = "..."))]` or `#[gsm(bitwarden(secret = "..."))]`.
+    Bitwarden(Bitwarden),
+    /// CyberArk Conjur, set by `#[gsm(conjur(policy = "prod/billing"))]`.
+    Conjur(Conjur),
+    /// A static secret of Akeyless, set by `#[gsm(backend = "akeyless", path = "/prod/app")]`.
+    Akeyless(Path),
+    /// A record of Keeper Secrets Manager, set by `#[gsm(keeper(record = "..."))]`.
+    Keeper(Keeper),
+    /// A local file encrypted with a key of Google Cloud KMS, set by `#[gsm(gcp_kms_file(file = "...", key = "..."))]`.
+    GcpKmsFile(GcpKmsFile),
+    /// A JSON document served over HTTPS, such as by a broker of secrets, set by `#[gsm(http_json(url = "https://..."))]`.
+    HttpJson(HttpJson),
+    /// A service of the protocol of `proto/secret_service.proto`, set by `#[gsm(grpc(endpoint = "http://127.0.0.1:50051"))]`.
+    Grpc(Grpc),
+}
+
+impl Backend {
+    /// The option setting the backend, for the error messages.
+    pub fn option(&self) -> &'static str {
+        match self {
+            Backend::AppConfig(_) => "appconfig(...)",
+            Backend::S3(_) => "backend = \"s3\"",
+            Backend::Bitwarden(_) => "bitwarden(...)",
+            Backend::Conjur(_) => "conjur(...)",
+            Backend::Akeyless(_) => "backend = \"akeyless\"",
+            Backend::Keeper(_) => "keeper(...)",
+            Backend::GcpKmsFile(_) => "gcp_kms_file(...)",
+            Backend::HttpJson(_) => "http_json(...)",
+            Backend::Grpc(_) => "grpc(...)",
+        }
+    }
+
+    /// The feature of global-secrets-manager generating the code of the backend.
+    pub fn feature(&self) -> &'static str {
+        match self {
+            Backend::AppConfig(_) => "appconfig",
+            Backend::S3(_) => "s3",
+            Backend::Bitwarden(_) => "bitwarden",
+            Backend::Conjur(_) => "conjur",
+            Backend::Akeyless(_) => "akeyless",
+            Backend::Keeper(_) => "keeper",
+            Backend::GcpKmsFile(_) => "gcp-kms-file",
+            Backend::HttpJson(_) => "http-json",
+            Backend::Grpc(_) => "grpc",
+        }
+    }
+
+    /// The name of the backend recorded by the spans of the fetches.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::AppConfig(_) => "aws-appconfig",
+            Backend::S3(_) => "aws-s3",
+            Backend::Bitwarden(_) => "bitwarden-secrets-manager",
+            Backend::Conjur(_) => "cyberark-conjur",
+            Backend::Akeyless(_) => "akeyless",
+            Backend::Keeper(_) => "keeper-secrets-manager",
+            Backend::GcpKmsFile(_) => "gcp-kms-file",
+            Backend::HttpJson(_) => "http-json",
+            Backend::Grpc(_) => "grpc",
+        }
+    }
+
+    /// The option setting the backend, for the spans of the errors.
+    pub fn path(&self) -> &Path {
+        match self {
+            Backend::AppConfig(appconfig) => &appconfig.path,
+            Backend::S3(path) => path,
+            Backend::Bitwarden(bitwarden) => &bitwarden.path,
+            Backend::Conjur(conjur) => &conjur.path,
+            Backend::Akeyless(path) => path,
+            Backend::Keeper(keeper) => &keeper.path,
+            Backend::GcpKmsFile(file) => &file.path,
+            Backend::HttpJson(http) => &http.path,
+            Backend::Grpc(grpc) => &grpc.path,
+        }
+    }
+
+    /// Whether the backend holds each key in a value of its own, as a string, rather than the payload in one document.
+    pub fn reads_strings(&self) -> bool {
+        match self {
+            Backend::Bitwarden(bitwarden) => bitwarden.project.is_some(),
+            Backend::Conjur(_) | Backend::Keeper(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// The secrets of Bitwarden Secrets Manager read by a struct, with the access token of a machine account.
+#[cfg_attr(not(feature = "bitwarden"), allow(dead_code))]
+pub struct Bitwarden {
+    pub path: Path,
+    /// Id of the project whose secrets hold the keys, each named by its key in Bitwarden.
+    pub project: Option<LitStr>,
+    /// Id of the secret whose value is the payload, in place of `project`.
+    pub secret: Option<LitStr>,
+    /// Variable holding the access token. Defaults to `BWS_ACCESS_TOKEN`, as for the `bws` command line.
+    pub access_token_env: Option<LitStr>,
+    /// Server of a region or a self-hosted instance, such as `https://vault.bitwarden.eu`. Defaults to the US cloud.
+    pub server_url: Option<LitStr>,
+}
+
+impl Bitwarden {
+    /// The project or the secret, as `project/<id>` or `secret/<id>`.
+    pub fn source(&self) -> String {
+        match (&self.project, &self.secret) {
+            (Some(project), _) => format!("project/{}", project.value()),
+            (None, secret) => format!(
+                "secret/{}",
+                secret.as_ref().map(LitStr::value).unwrap_or_default()
+            ),
+        }
+    }
+
+    fn from_list(list: &MetaList) -> Result<Self> {
+        let [project, secret, access_token_env, server_url] = string_options(
+            list,
+            ["project", "secret", "access_token_env", "server_url"],
+        )?;
+        if project.is_some() == secret.is_some() {
+            return Err(Error::new_spanned(
+                list,
+                "expected either the `project` holding the keys or the `secret` holding the payload",
+            ));
+        }
+        Ok(Bitwarden {
+            path: list.path.clone(),
+            project,
+            secret,
+            access_token_env,
+            server_url,
+        })
+    }
+}
+
+/// The variables of Conjur read by a struct, one for each key, authenticated as a host with its API key.
+///
+/// The options left out are taken from the variables of the Conjur clients: `CONJUR_APPLIANCE_URL`, `CONJUR_ACCOUNT` and `CONJUR_AUTHN_LOGIN`.
+#[cfg_attr(not(feature = "conjur"), allow(dead_code))]
+pub struct Conjur {
+    pub path: Path,
+    /// Policy holding the variables, named `{policy}/{key}`.
+    pub policy: LitStr,
+    /// URL of the Conjur server.
+    pub url: Option<LitStr>,
+    /// Account of the policy.
+    pub account: Option<LitStr>,
+    /// Identity authenticated, such as `host/prod/billing/api`.
+    pub login: Option<LitStr>,
+}
+
+impl Conjur {
+    fn from_list(list: &MetaList) -> Result<Self> {
+        let [policy, url, account, login] =
+            string_options(list, ["policy", "url", "account", "login"])?;
+        Ok(Conjur {
+            path: list.path.clone(),
+            policy: required(list, policy, "policy")?,
+            url,
+            account,
+            login,
+        })
+    }
+}
+
+/// The record of Keeper Secrets Manager read by a struct, with the keys of a device bound to a KSM application.
+///
+/// On the first start, the one-time access token of the variable `token_env` binds the device, and the keys it is given are
+/// saved to the `config` file, which the later starts read without the token.
+#[cfg_attr(not(feature = "keeper"), allow(dead_code))]
+pub struct Keeper {
+    pub path: Path,
+    /// UID of the record whose custom fields hold the keys, each named by its label.
+    pub record: LitStr,
+    /// File holding the configuration of the device. Defaults to `ksm-config.json`, as for the `ksm` command line.
+    pub config: Option<LitStr>,
+    /// Variable holding the one-time access token. Defaults to `KSM_TOKEN`.
+    pub token_env: Option<LitStr>,
+}
+
+impl Keeper {
+    fn from_list(list: &MetaList) -> Result<Self> {
+        let [record, config, token_env] = string_options(list, ["record", "config", "token_env"])?;
+        Ok(Keeper {
+            path: list.path.clone(),
+            record: required(list, record, "record")?,
+            config,
+            token_env,
+        })
+    }
+}
+
+/// The local file read by a struct, a ciphertext of Google Cloud KMS decrypted with the credentials of the environment.
+#[cfg_attr(not(feature = "gcp-kms-file"), allow(dead_code))]
+pub struct GcpKmsFile {
+    pub path: Path,
+    /// Path of the file, relative to the working directory.
+    pub file: LitStr,
+    /// Resource name of the key, as `projects/{project}/locations/{location}/keyRings/{ring}/cryptoKeys/{key}`.
+    pub key: LitStr,
+}
+
+impl GcpKmsFile {
+    fn from_list(list: &MetaList) -> Result<Self> {
+        let [file, key] = string_options(list, ["file", "key"])?;
+        Ok(GcpKmsFile {
+            path: list.path.clone(),
+            file: required(list, file, "file")?,
+            key: required(list, key, "key")?,
+        })
+    }
+}
+
+/// The JSON document read by a struct from an HTTPS endpoint, with a bearer token, a client certificate, or both.
+#[cfg_attr(not(feature = "http-json"), allow(dead_code))]
+pub struct HttpJson {
+    pub path: Path,
+    /// URL of the document, which must be `https://`.
+    pub url: LitStr,
+    /// Variable holding the bearer token sent in the `Authorization` header.
+    pub token_env: Option<LitStr>,
+    /// PEM file holding the client certificate and its private key, for mutual TLS.
+    pub identity: Option<LitStr>,
+    /// PEM file holding the certificate of the authority the server certificate is checked against, in addition to the system ones.
+    pub ca: Option<LitStr>,
+}
+
+impl HttpJson {
+    fn from_list(list: &MetaList) -> Result<Self> {
+        let [url, token_env, identity, ca] =
+            string_options(list, ["url", "token_env", "identity", "ca"])?;
+        let url = required(list, url, "url")?;
+        if !url.value().starts_with("https://") {
+            return Err(Error::new_spanned(
+                url,
+                "expected an `https://` URL, as the document holds secrets",
+            ));
+        }
+        Ok(HttpJson {
+            path: list.path.clone(),
+            url,
+            token_env,
+            identity,
+            ca,
+        })
+    }
+}
+
+/// The secret service read by a struct, which answers the `GetSecret` of `proto/secret_service.proto` with the payload of the name of the struct.
+#[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+pub struct Grpc {
+    pub path: Path,
+    /// Endpoint of the service, such as `http://127.0.0.1:50051` for a sidecar. Defaults to the one of `GSM_GRPC_ENDPOINT`.
+    pub endpoint: Option<LitStr>,
+}
+
+impl Grpc {
+    fn from_list(list: &MetaList) -> Result<Self> {
+        let [endpoint] = string_options(list, ["endpoint"])?;
+        Ok(Grpc {
+            path: list.path.clone(),
+            endpoint,
+        })
+    }
+}
+
+/// The configuration profile of AppConfig read by a struct.
+#[cfg_attr(not(feature = "appconfig"), allow(dead_code))]
+pub struct AppConfig {
+    pub path: Path,
+    /// Name or id of the application.
+    pub application: LitStr,
+    /// Name or id of the environment.
+    pub environment: LitStr,
+    /// Name or id of the configuration profile.
+    pub profile: LitStr,
+    /// Shortest time between two polls of the configuration, in seconds, set by `poll_interval = "60s"`. Defaults to the one of AppConfig, 60 seconds.
+    pub poll_interval: Option<u64>,
+}
+
+impl AppConfig {
+    /// The profile, as `application/environment/profile`.
+    pub fn source(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            self.application.value(),
+            self.environment.value(),
+            self.profile.value()
+        )
+    }
+
+    fn from_list(list: &MetaList) -> Result<Self> {
+        let [application, environment, profile, poll_interval] = string_options(
+            list,
+            ["application", "environment", "profile", "poll_interval"],
+        )?;
+        let poll_interval = match &poll_interval {
+            Some(lit) => match seconds(lit)? {
+                seconds @ 15..=86400 => Some(seconds),
+                _ => {
+                    return Err(Error::new_spanned(
+                        lit,
+                        "expected an interval between 15s and 1d, the ones AppConfig accepts",
+                    ))
+                }
+            },
+            None => None,
+        };
+        Ok(AppConfig {
+            path: list.path.clone(),
+            application: required(list, application, "application")?,
+            environment: required(list, environment, "environment")?,
+            profile: required(list, profile, "profile")?,
+            poll_interval,
+        })
+    }
+}
+
+/// The secrets of the tenants of a multi-tenant application, one for each tenant, read by `for_tenant()` and cached for each of them.
+pub struct Tenant {
+    pub path: Path,
+    /// Name of the secrets, with `{tenant}` replaced by the id of the tenant.
+    pub name: LitStr,
+    /// Most tenants cached at once, the least recently read ones being dropped first. Defaults to 1000.
+    pub capacity: usize,
+    /// Time the secret of a tenant is cached for before it is fetched again, in seconds, set by `ttl = "5m"`. Defaults to 5 minutes.
+    pub ttl: u64,
+}
+
+impl Tenant {
+    fn from_list(list: &MetaList) -> Result<Self> {
+        let (mut name, mut capacity, mut ttl) = (None, None, None);
+        for nested in &list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                    let lit = lit_str(&nv.lit)?;
+                    if !lit.value().contains("{tenant}") {
+                        return Err(Error::new_spanned(
+                            lit,
+                            "expected `{tenant}` in the name, where the id of the tenant goes",
+                        ));
+                    }
+                    set_once(&mut name, lit, nv)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("capacity") => {
+                    let count = match &nv.lit {
+                        Lit::Int(int) if int.base10_parse::<usize>()? > 0 => int.base10_parse()?,
+                        other => {
+                            return Err(Error::new_spanned(
+                                other,
+                                "expected the number of tenants cached at once",
+                            ))
+                        }
+                    };
+                    set_once(&mut capacity, count, nv)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("ttl") => {
+                    set_once(&mut ttl, seconds(&lit_str(&nv.lit)?)?, nv)?
+                }
+                other => {
+                    return Err(Error::new_spanned(
+                        other,
+                        "expected `name`, `capacity` or `ttl`",
+                    ))
+                }
+            }
+        }
+        Ok(Tenant {
+            path: list.path.clone(),
+            name: required(list, name, "name")?,
+            capacity: capacity.unwrap_or(1000),
+            ttl: ttl.unwrap_or(5 * 60),
+        })
+    }
+}
+
+/// How the fetches failing with one kind of error are retried, set by `throttled(retries = 5, backoff = "200ms")` in `#[gsm(retry(...))]`.
+pub struct Retry {
+    /// The kind of error, named as the field of `RetryPolicy` in global-secrets-manager-runtime.
+    pub kind: Ident,
+    pub retries: u32,
+    /// Delay before the first retry, in milliseconds, doubled before each next one. Defaults to 100ms.
+    pub backoff: u64,
+}
+
+impl Retry {
+    const KINDS: &'static [&'static str] = &[
+        "credentials",
+        "not_found",
+        "access_denied",
+        "throttled",
+        "service",
+        "transport",
+    ];
+
+    fn from_list(list: &MetaList) -> Result<Vec<Self>> {
+        let mut kinds: Vec<Self> = Vec::new();
+        for nested in &list.nested {
+            let kind = match nested {
+                NestedMeta::Meta(Meta::List(kind)) => kind,
+                other => {
+                    return Err(Error::new_spanned(
+                        other,
+                        "expected a kind of error, as in `retry(throttled(retries = 5, backoff = \"200ms\"))`",
+                    ))
+                }
+            };
+            let ident = match kind.path.get_ident() {
+                Some(ident) if Self::KINDS.iter().any(|name| ident == name) => ident.clone(),
+                _ => {
+                    return Err(Error::new_spanned(
+                        &kind.path,
+                        format!(
+                            "expected one of the kinds of errors: {}",
+                            Self::KINDS.join(", ")
+                        ),
+                    ))
+                }
+            };
+            if kinds.iter().any(|retry| retry.kind == ident) {
+                return Err(Error::new_spanned(ident, "duplicate kind of error"));
+            }
+            let (mut retries, mut backoff) = (None, None);
+            for nested in &kind.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("retries") => {
+                        let count = match &nv.lit {
+                            Lit::Int(int) => int.base10_parse::<u32>()?,
+                            other => {
+                                return Err(Error::new_spanned(
+                                    other,
+                                    "expected a number of retries",
+                                ))
+                            }
+                        };
+                        set_once(&mut retries, count, nv)?
+                    }
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("backoff") => {
+                        set_once(&mut backoff, milliseconds(&lit_str(&nv.lit)?)?, nv)?
+                    }
+                    other => {
+                        return Err(Error::new_spanned(other, "expected `retries` or `backoff`"))
+                    }
+                }
+            }
+            let retries = retries.ok_or_else(|| {
+                Error::new_spanned(kind, "expected the number of retries, as in `retries = 3`")
+            })?;
+            kinds.push(Retry {
+                kind: ident,
+                retries,
+                backoff: backoff.unwrap_or(100),
+            });
+        }
+        if kinds.is_empty() {
+            return Err(Error::new_spanned(
+                list,
+                "expected the kinds of errors to retry",
+            ));
+        }
+        Ok(kinds)
+    }
+}
+
+/// Wiring of one environment, set by `#[gsm(env("dev", name = "dev/App", endpoint_url = "http://localhost:4566"))]`.
+pub struct Environment {
+    /// Value of `env_var` selecting the environment, compared case-insensitively.
+    pub value: LitStr,
+    /// Name of the secret in this environment. Defaults to the name of the struct's secret.
+    pub name: Option<LitStr>,
+    /// Endpoint of Secrets Manager in this environment, such as LocalStack.
+    pub endpoint_url: Option<LitStr>,
+    /// Region of the secret in this environment.
+    pub region: Option<LitStr>,
+}
+
+impl Environment {
+    fn from_list(list: &MetaList) -> Result<Self> {
+        let mut nested = list.nested.iter();
+        let value =
+            match nested.next() {
+                Some(NestedMeta::Lit(Lit::Str(value))) => value.clone(),
+                _ => return Err(Error::new_spanned(
+                    list,
+                    "expected the environment first, as in `env(\"prod\", name = \"prod/App\")`",
+                )),
+            };
+        let mut environment = Environment {
+            value,
+            name: None,
+            endpoint_url: None,
+            region: None,
+        };
+        for nested in nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                    set_once(&mut environment.name, lit_str(&nv.lit)?, nv)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("endpoint_url") => {
+                    set_once(&mut environment.endpoint_url, lit_str(&nv.lit)?, nv)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("region") => {
+                    set_once(&mut environment.region, lit_str(&nv.lit)?, nv)?
+                }
+                other => {
+                    return Err(Error::new_spanned(
+                        other,
+                        "expected `name`, `endpoint_url` or `region`",
+                    ))
+                }
+            }
+        }
+        Ok(environment)
+    }
+}
+
+/// Well-known kinds of credentials, set by `#[gsm(kind = "...")]`.
+pub enum Kind {
+    /// Redis or ElastiCache, with an auth token.
+    Redis,
+    /// SASL credentials of Kafka brokers, such as those of MSK.
+    Kafka,
+}
+
+impl ContainerAttrs {
+    pub fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+        let mut container = Self::default();
+        for nested in gsm_nested(attrs)? {
+            container.parse_nested(nested)?;
+        }
+        Ok(container)
+    }
+
+    /// What the backend of the struct reads, such as `billing/prod/flags` for a profile of AppConfig,
+    /// for the listings of the `gsm` binary and the errors naming the payload.
+    pub fn backend_source(&self) -> Option<String> {
+        let value = |lit: &Option<LitStr>| lit.as_ref().map(LitStr::value).unwrap_or_default();
+        match self.backend.as_ref()? {
+            Backend::AppConfig(appconfig) => Some(appconfig.source()),
+            Backend::S3(_) => Some(format!("s3://{}/{}", value(&self.bucket), value(&self.key))),
+            Backend::Bitwarden(bitwarden) => Some(bitwarden.source()),
+            Backend::Conjur(conjur) => Some(conjur.policy.value()),
+            Backend::Akeyless(_) => Some(value(&self.path)),
+            Backend::Keeper(keeper) => Some(format!("record/{}", keeper.record.value())),
+            Backend::GcpKmsFile(file) => Some(file.file.value()),
+            Backend::HttpJson(http) => Some(http.url.value()),
+            Backend::Grpc(grpc) => Some(
+                grpc.endpoint
+                    .as_ref()
+                    .map_or_else(|| "$GSM_GRPC_ENDPOINT".to_owned(), LitStr::value),
+            ),
+        }
+    }
+
+    /// Whether the global constant is stored in a `RwLock`, by `#[gsm(storage = "rwlock")]`.
+    pub fn rwlock(&self) -> bool {
+        matches!(&self.storage, Some(storage) if storage.value() == "rwlock")
+    }
+
+    fn set_backend(&mut self, backend: Backend, spanned: impl quote::ToTokens) -> Result<()> {
+        if let Some(other) = &self.backend {
+            return Err(Error::new_spanned(
+                spanned,
+                format!(
+                    "a struct reads a single backend, and this one already reads from `{}`",
+                    other.option()
+                ),
+            ));
+        }
+        self.backend = Some(backend);
+        Ok(())
+    }
+
+    pub fn set_name(&mut self, name: LitStr) -> Result<()> {
+        set_once(&mut self.name, name.clone(), name)
+    }
+
+    pub fn parse_nested(&mut self, nested: NestedMeta) -> Result<()> {
+        match nested {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                self.set_name(lit_str(&nv.lit)?)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("env_var") => {
+                set_once(&mut self.env_var, lit_str(&nv.lit)?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("provider") => {
+                set_once(&mut self.provider, lit_str(&nv.lit)?.parse()?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("kind") => {
+                let kind = match lit_str(&nv.lit)?.value().as_str() {
+                    "redis" => Kind::Redis,
+                    "kafka" => Kind::Kafka,
+                    _ => return Err(Error::new_spanned(&nv.lit, "expected `redis` or `kafka`")),
+                };
+                set_once(&mut self.kind, kind, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("tags") => {
+                let lit = lit_str(&nv.lit)?;
+                let tags = lit
+                    .value()
+                    .split(',')
+                    .map(|pair| match pair.split_once('=') {
+                        Some((key, value)) if !key.trim().is_empty() => {
+                            Ok((key.trim().to_owned(), value.trim().to_owned()))
+                        }
+                        _ => Err(Error::new_spanned(
+                            &lit,
+                            "expected `key=value` pairs separated by commas",
+                        )),
+                    })
+                    .collect::<Result<_>>()?;
+                set_once(&mut self.tags, tags, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("audit") => {
+                set_once(&mut self.audit, lit_str(&nv.lit)?.parse()?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("validate") => {
+                set_once(&mut self.validate, lit_str(&nv.lit)?.parse()?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("kms_key_id") => {
+                set_once(&mut self.kms_key_id, lit_str(&nv.lit)?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("pointer") => {
+                set_once(&mut self.pointer, pointer(&nv.lit)?, &nv)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("env") => {
+                let environment = Environment::from_list(&list)?;
+                let value = environment.value.value();
+                if self
+                    .environments
+                    .iter()
+                    .any(|other| other.value.value().eq_ignore_ascii_case(&value))
+                {
+                    return Err(Error::new_spanned(
+                        &environment.value,
+                        "duplicate environment",
+                    ));
+                }
+                self.environments.push(environment);
+                Ok(())
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("role_chain") => {
+                let roles = lit_strs(&list, "the ARNs of the roles to assume, in order")?;
+                set_once_vec(&mut self.role_chain, roles, &list)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("app_name") => {
+                set_once(&mut self.app_name, app_name(&nv.lit)?, &nv)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("tenant") => {
+                set_once(&mut self.tenant, Tenant::from_list(&list)?, &list)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("instances") => {
+                let lit = lit_str(&nv.lit)?;
+                let var = lit.value();
+                if var.is_empty() || !var.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+                    return Err(Error::new_spanned(
+                        &lit,
+                        "expected the variable selecting the instance, such as `REGION`",
+                    ));
+                }
+                set_once(&mut self.instances, lit, &nv)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("retry") => {
+                set_once_vec(&mut self.retry, Retry::from_list(&list)?, &list)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("backend") => {
+                let backend = match lit_str(&nv.lit)?.value().as_str() {
+                    "s3" => Backend::S3(nv.path.clone()),
+                    "akeyless" => Backend::Akeyless(nv.path.clone()),
+                    _ => {
+                        return Err(Error::new_spanned(
+                            &nv.lit,
+                            "expected `s3` or `akeyless`; the other backends are set by options of their own, such as `appconfig(...)`",
+                        ))
+                    }
+                };
+                self.set_backend(backend, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("bucket") => {
+                set_once(&mut self.bucket, lit_str(&nv.lit)?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("key") => {
+                set_once(&mut self.key, lit_str(&nv.lit)?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("path") => {
+                set_once(&mut self.path, lit_str(&nv.lit)?, &nv)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("bitwarden") => {
+                let bitwarden = Bitwarden::from_list(&list)?;
+                self.set_backend(Backend::Bitwarden(bitwarden), &list)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("conjur") => {
+                let conjur = Conjur::from_list(&list)?;
+                self.set_backend(Backend::Conjur(conjur), &list)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("keeper") => {
+                let keeper = Keeper::from_list(&list)?;
+                self.set_backend(Backend::Keeper(keeper), &list)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("gcp_kms_file") => {
+                let file = GcpKmsFile::from_list(&list)?;
+                self.set_backend(Backend::GcpKmsFile(file), &list)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("http_json") => {
+                let http = HttpJson::from_list(&list)?;
+                self.set_backend(Backend::HttpJson(http), &list)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("grpc") => {
+                let grpc = Grpc::from_list(&list)?;
+                self.set_backend(Backend::Grpc(grpc), &list)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("appconfig") => {
+                let appconfig = AppConfig::from_list(&list)?;
+                self.set_backend(Backend::AppConfig(appconfig), &list)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("replicas") => {
+                let regions =
+                    lit_strs(&list, "the regions of the replicas, in order of preference")?;
+                set_once_vec(&mut self.replicas, regions, &list)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("on_region_switch") => {
+                set_once(&mut self.on_region_switch, lit_str(&nv.lit)?.parse()?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("extends") => {
+                self.extends.push(lit_str(&nv.lit)?);
+                Ok(())
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("merge") => {
+                set_once(&mut self.merge, merge(&nv.lit)?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("storage") => {
+                let storage = lit_str(&nv.lit)?;
+                if !matches!(storage.value().as_str(), "lazy" | "rwlock") {
+                    return Err(Error::new_spanned(storage, "expected `lazy` or `rwlock`"));
+                }
+                set_once(&mut self.storage, storage, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("max_hold") => {
+                set_once(&mut self.max_hold, milliseconds(&lit_str(&nv.lit)?)?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("on_hold") => {
+                set_once(&mut self.on_hold, lit_str(&nv.lit)?.parse()?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("max_age") => {
+                set_once(&mut self.max_age, seconds(&lit_str(&nv.lit)?)?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("on_stale") => {
+                set_once(&mut self.on_stale, lit_str(&nv.lit)?.parse()?, &nv)
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("export") => {
+                set_once(&mut self.export, LitStr::new("", path.span()), &path)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("export") => {
+                set_once(&mut self.export, lit_str(&nv.lit)?, &nv)
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("env_prefix") => {
+                set_once(&mut self.env_prefix, lit_str(&nv.lit)?, &nv)
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("fallback") => {
+                set_once(&mut self.fallback, path.clone(), path)
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("writable") => {
+                set_once(&mut self.writable, path.clone(), path)
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("strict") => {
+                set_once(&mut self.strict, path.clone(), path)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("allow_extra") => {
+                let globs = lit_strs(
+                    &list,
+                    "the globs of the keys to let through, such as `legacy_*`",
+                )?;
+                set_once_vec(&mut self.allow_extra, globs, &list)
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("redact_keys") => {
+                set_once(&mut self.redact_keys, path.clone(), path)
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("test_default") => {
+                set_once(&mut self.test_default, path.clone(), path)
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("no_static") => {
+                set_once(&mut self.no_static, path.clone(), path)
+            }
+            other => Err(Error::new_spanned(
+                other,
+                "unknown global-secrets-manager option",
+            )),
+        }
+    }
+}
+
+/// Options given to an enum variant by `#[gsm(...)]`.
+#[derive(Default)]
+pub struct VariantAttrs {
+    /// Name of the secret backing the variant. Defaults to the name of the type it holds.
+    pub name: Option<LitStr>,
+    /// Value of the environment variable selecting the variant. Defaults to the name of the variant.
+    pub rename: Option<LitStr>,
+}
+
+impl VariantAttrs {
+    pub fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+        let mut variant = Self::default();
+        for nested in gsm_nested(attrs)? {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                    set_once(&mut variant.name, lit_str(&nv.lit)?, &nv)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => {
+                    set_once(&mut variant.rename, lit_str(&nv.lit)?, &nv)?
+                }
+                other => {
+                    return Err(Error::new_spanned(
+                        other,
+                        "unknown global-secrets-manager option",
+                    ))
+                }
+            }
+        }
+        Ok(variant)
+    }
+}
+
+/// Options given to a field by `#[gsm(...)]`.
+#[derive(Default)]
+pub struct FieldAttrs {
+    /// Id of the clap argument defaulting to the field, set by `#[gsm(clap)]` or `#[gsm(clap = "id")]`.
+    pub clap: Option<LitStr>,
+    /// Header set from the field by the reqwest middleware, by `#[gsm(bearer)]` or `#[gsm(header = "name")]`.
+    pub header: Option<Header>,
+    /// Name of the variable returned for the field by `as_env_vars()`, set by `#[gsm(export = "NAME")]`.
+    pub export: Option<LitStr>,
+    /// Set by `#[gsm(skip_export)]` to leave the field out of `as_env_vars()`.
+    pub skip_export: Option<Path>,
+    /// Environment variable overriding the key of the field, set by `#[gsm(env = "NAME")]`.
+    pub env: Option<LitStr>,
+    /// Set by `#[gsm(fallback)]` for the variable of `env` to only fill in the key when the secret lacks it.
+    pub fallback: Option<Path>,
+    /// Set by `#[gsm(non_empty)]` to reject an empty value at load.
+    pub non_empty: Option<Path>,
+    /// Regular expression the value must match, set by `#[gsm(matches = "^sk_live_")]`.
+    pub matches: Option<LitStr>,
+    /// Bounds of the length of the value, set by `#[gsm(len = "32..=64")]`.
+    pub len: Option<Length>,
+    /// Former names of the key, set by `#[gsm(alias = "old_name")]`, which may be repeated.
+    pub aliases: Vec<LitStr>,
+    /// Set by `#[gsm(kms_decrypt)]` for the value to be decrypted by KMS from a base64 ciphertext.
+    pub kms_decrypt: Option<Path>,
+    /// Set by `#[gsm(lazy_decode)]` for the base64 value to be decoded, or decrypted with `kms_decrypt`, at its first access.
+    pub lazy_decode: Option<Path>,
+    /// JSON pointer to the value of the field, set by `#[gsm(pointer = "/credentials/db/password")]`.
+    pub pointer: Option<LitStr>,
+    /// Which of the secrets of `extends` wins the key of the field, set by `#[gsm(merge = "...")]`.
+    pub merge: Option<LitStr>,
+    /// Set by `#[gsm(common)]` for the field to hold the struct of a secret shared by several structs, read from that secret.
+    pub common: Option<Path>,
+    /// Separator by which a string value is split into the items of the field, set by `#[gsm(split)]` for commas or `#[gsm(split = ";")]`.
+    pub split: Option<LitStr>,
+    /// Set by `#[gsm(deref_secret)]` for the field to hold the secret whose ARN or name the key holds, read from that secret.
+    pub deref_secret: Option<Path>,
+}
+
+/// The bounds of a length, as a range of `#[gsm(len = "...")]`.
+pub struct Length {
+    pub min: usize,
+    /// The inclusive upper bound, if any.
+    pub max: Option<usize>,
+    /// The range as written, for the messages.
+    pub range: String,
+}
+
+/// A request header carrying the value of a field.
+#[allow(dead_code)]
+pub struct Header {
+    /// The lowercase name of the header.
+    pub name: LitStr,
+    /// Whether the value is sent as `Bearer <value>`.
+    pub bearer: bool,
+}
+
+impl FieldAttrs {
+    pub fn from_field(field: &Field) -> Result<Self> {
+        let mut attrs = Self::default();
+        for nested in gsm_nested(&field.attrs)? {
+            match nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("clap") => {
+                    let ident = field.ident.as_ref().unwrap();
+                    let id = LitStr::new(&ident.to_string(), ident.span());
+                    feature(&path, "clap", cfg!(feature = "clap"))?;
+                    set_once(&mut attrs.clap, id, &path)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("clap") => {
+                    feature(&nv, "clap", cfg!(feature = "clap"))?;
+                    set_once(&mut attrs.clap, lit_str(&nv.lit)?, &nv)?
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("bearer") => {
+                    feature(
+                        &path,
+                        "reqwest-middleware",
+                        cfg!(feature = "reqwest-middleware"),
+                    )?;
+                    let name = LitStr::new("authorization", path.span());
+                    let header = Header { name, bearer: true };
+                    set_once(&mut attrs.header, header, &path)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("header") => {
+                    feature(
+                        &nv,
+                        "reqwest-middleware",
+                        cfg!(feature = "reqwest-middleware"),
+                    )?;
+                    let name = lit_str(&nv.lit)?;
+                    if name.value().is_empty()
+                        || !name
+                            .value()
+                            .bytes()
+                            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+                    {
+                        return Err(Error::new_spanned(name, "invalid header name"));
+                    }
+                    let name = LitStr::new(&name.value().to_ascii_lowercase(), name.span());
+                    let header = Header {
+                        name,
+                        bearer: false,
+                    };
+                    set_once(&mut attrs.header, header, &nv)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("export") => {
+                    set_once(&mut attrs.export, lit_str(&nv.lit)?, &nv)?
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip_export") => {
+                    set_once(&mut attrs.skip_export, path.clone(), path)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("env") => {
+                    set_once(&mut attrs.env, lit_str(&nv.lit)?, &nv)?
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("fallback") => {
+                    set_once(&mut attrs.fallback, path.clone(), path)?
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("non_empty") => {
+                    set_once(&mut attrs.non_empty, path.clone(), path)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("matches") => {
+                    feature(&nv, "regex", cfg!(feature = "regex"))?;
+                    set_once(&mut attrs.matches, lit_str(&nv.lit)?, &nv)?
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("lazy_decode") => {
+                    set_once(&mut attrs.lazy_decode, path.clone(), path)?
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("kms_decrypt") => {
+                    feature(&path, "kms", cfg!(feature = "kms"))?;
+                    set_once(&mut attrs.kms_decrypt, path.clone(), path)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("pointer") => {
+                    set_once(&mut attrs.pointer, pointer(&nv.lit)?, &nv)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("merge") => {
+                    set_once(&mut attrs.merge, merge(&nv.lit)?, &nv)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("alias") => {
+                    attrs.aliases.push(lit_str(&nv.lit)?)
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("common") => {
+                    set_once(&mut attrs.common, path.clone(), path)?
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("split") => {
+                    let separator = LitStr::new(",", path.span());
+                    set_once(&mut attrs.split, separator, &path)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("split") => {
+                    let separator = lit_str(&nv.lit)?;
+                    if separator.value().is_empty() {
+                        return Err(Error::new_spanned(
+                            separator,
+                            "expected the separator of the items",
+                        ));
+                    }
+                    set_once(&mut attrs.split, separator, &nv)?
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("deref_secret") => {
+                    set_once(&mut attrs.deref_secret, path.clone(), path)?
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("len") => {
+                    set_once(&mut attrs.len, length(&nv.lit)?, &nv)?
+                }
+                other => {
+                    return Err(Error::new_spanned(
+                        other,
+                        "unknown global-secrets-manager option",
+                    ))
+                }
+            }
+        }
+        if let (Some(export), Some(_)) = (&attrs.export, &attrs.skip_export) {
+            return Err(Error::new_spanned(
+                export,
+                "`export` and `skip_export` can't be combined",
+            ));
+        }
+        if let (None, Some(fallback)) = (&attrs.env, &attrs.fallback) {
+            return Err(Error::new_spanned(
+                fallback,
+                "`fallback` needs the variable of `env = \"...\"`",
+            ));
+        }
+        Ok(attrs)
+    }
+}
+
+/// Fails unless the feature an option relies on is enabled.
+fn feature(spanned: impl quote::ToTokens, name: &str, enabled: bool) -> Result<()> {
+    if enabled {
+        return Ok(());
+    }
+    Err(Error::new_spanned(
+        spanned,
+        format!(
+            "this option needs the `{}` feature of global-secrets-manager",
+            name
+        ),
+    ))
+}
+
+/// Collects the items of every `#[gsm(...)]` attribute.
+fn gsm_nested(attrs: &[Attribute]) -> Result<Vec<NestedMeta>> {
+    let mut nested = Vec::new();
+    for attr in attrs.iter().filter(|attr| is_gsm(attr)) {
+        match attr.parse_meta()? {
+            Meta::List(list) => nested.extend(list.nested),
+            other => return Err(Error::new_spanned(other, "expected `#[gsm(...)]`")),
+        }
+    }
+    Ok(nested)
+}
+
+pub fn is_gsm(attr: &Attribute) -> bool {
+    attr.path.is_ident("gsm")
+}
+
+fn lit_str(lit: &Lit) -> Result<LitStr> {
+    match lit {
+        Lit::Str(lit) => Ok(lit.clone()),
+        other => Err(Error::new_spanned(other, "expected a string literal")),
+    }
+}
+
+/// The string literals of a list, such as `role_chain("arn:a", "arn:b")`, which must not be empty.
+fn lit_strs(list: &MetaList, expected: &str) -> Result<Vec<LitStr>> {
+    let lits = list
+        .nested
+        .iter()
+        .map(|nested| match nested {
+            NestedMeta::Lit(Lit::Str(lit)) => Ok(lit.clone()),
+            other => Err(Error::new_spanned(other, "expected a string literal")),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if lits.is_empty() {
+        return Err(Error::new_spanned(list, format!("expected {}", expected)));
+    }
+    Ok(lits)
+}
+
+/// The string options of a list such as `appconfig(application = "billing", profile = "flags")`, in the order of `names`.
+/// Each may be given once, and the options not in `names` are rejected.
+fn string_options<const N: usize>(
+    list: &MetaList,
+    names: [&str; N],
+) -> Result<[Option<LitStr>; N]> {
+    let mut values: [Option<LitStr>; N] = std::array::from_fn(|_| None);
+    for nested in &list.nested {
+        let position = match nested {
+            NestedMeta::Meta(Meta::NameValue(nv)) => names
+                .iter()
+                .position(|name| nv.path.is_ident(name))
+                .map(|i| (i, nv)),
+            _ => None,
+        };
+        match position {
+            Some((i, nv)) => set_once(&mut values[i], lit_str(&nv.lit)?, nv)?,
+            None => {
+                let expected: Vec<_> = names.iter().map(|name| format!("`{}`", name)).collect();
+                return Err(Error::new_spanned(
+                    nested,
+                    format!("expected one of {}", expected.join(", ")),
+                ));
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// The option `name` of the list, which must be given.
+fn required(list: &MetaList, value: Option<LitStr>, name: &str) -> Result<LitStr> {
+    value.ok_or_else(|| {
+        Error::new_spanned(
+            list,
+            format!(
+                "expected `{} = \"...\"` in `{}(...)`",
+                name,
+                quote::ToTokens::to_token_stream(&list.path)
+            ),
+        )
+    })
+}
+
+fn set_once_vec<T>(slot: &mut Vec<T>, values: Vec<T>, spanned: impl quote::ToTokens) -> Result<()> {
+    if !slot.is_empty() {
+        return Err(Error::new_spanned(
+            spanned,
+            "duplicate global-secrets-manager option",
+        ));
+    }
+    *slot = values;
+    Ok(())
+}
+
+fn set_once<T>(slot: &mut Option<T>, value: T, spanned: impl quote::ToTokens) -> Result<()> {
+    if slot.is_some() {
+        return Err(Error::new_spanned(
+            spanned,
+            "duplicate global-secrets-manager option",
+        ));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+/// A duration such as `"90d"`, in seconds: a number followed by `s`, `m`, `h` or `d`.
+pub fn seconds(lit: &LitStr) -> Result<u64> {
+    let value = lit.value();
+    let (number, unit) =
+        value.split_at(value.len() - value.chars().last().map_or(0, char::len_utf8));
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => 0,
+    };
+    match number.parse::<u64>() {
+        Ok(n) if unit > 0 => Ok(n * unit),
+        _ => Err(Error::new_spanned(
+            lit,
+            "expected a number followed by `s`, `m`, `h` or `d`, such as \"90d\"",
+        )),
+    }
+}
+
+/// A duration such as `"100ms"`, in milliseconds: a number followed by `ms`, or a duration accepted by `seconds`.
+fn milliseconds(lit: &LitStr) -> Result<u64> {
+    let parsed = match lit.value().strip_suffix("ms") {
+        Some(number) => number.parse::<u64>().ok(),
+        None => seconds(lit).ok().map(|seconds| seconds * 1000),
+    };
+    parsed.ok_or_else(|| {
+        Error::new_spanned(
+            lit,
+            "expected a number followed by `ms`, `s`, `m`, `h` or `d`, such as \"100ms\"",
+        )
+    })
+}
+
+/// An order of merging, `last_wins` or `first_wins`.
+fn merge(lit: &Lit) -> Result<LitStr> {
+    let merge = lit_str(lit)?;
+    match merge.value().as_str() {
+        "last_wins" | "first_wins" => Ok(merge),
+        _ => Err(Error::new_spanned(
+            merge,
+            "expected `last_wins` or `first_wins`",
+        )),
+    }
+}
+
+/// A name of application for the user agent, made of the characters that `aws_config::AppName` accepts.
+fn app_name(lit: &Lit) -> Result<LitStr> {
+    let app_name = lit_str(lit)?;
+    let value = app_name.value();
+    let valid = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+    if value.is_empty() || !value.chars().all(valid) {
+        return Err(Error::new_spanned(
+            app_name,
+            "expected a name of letters, digits and `!#$%&'*+-.^_`|~`, such as \"billing-api\"",
+        ));
+    }
+    Ok(app_name)
+}
+
+/// A JSON pointer, such as `"/credentials/db/password"`.
+fn pointer(lit: &Lit) -> Result<LitStr> {
+    let pointer = lit_str(lit)?;
+    if !pointer.value().starts_with('/') {
+        return Err(Error::new_spanned(
+            pointer,
+            "expected a JSON pointer starting with `/`, such as \"/credentials/db/password\"",
+        ));
+    }
+    Ok(pointer)
+}
+
+/// A length such as `32`, or a range such as `"32..=64"`, `"8.."` or `"..64"`.
+fn length(lit: &Lit) -> Result<Length> {
+    let expected = || {
+        Error::new_spanned(
+            lit,
+            "expected a length or a range of lengths, such as \"32..=64\"",
+        )
+    };
+    let range = match lit {
+        Lit::Int(int) => int.base10_digits().to_owned(),
+        Lit::Str(s) => s.value(),
+        _ => return Err(expected()),
+    };
+    let bound = |bound: &str| bound.trim().parse::<usize>().map_err(|_| expected());
+    let (min, max) = match range.split_once("..") {
+        None => (bound(&range)?, Some(bound(&range)?)),
+        Some((min, max)) => {
+            let min = if min.trim().is_empty() {
+                0
+            } else {
+                bound(min)?
+            };
+            let max = match max.strip_prefix('=') {
+                Some(max) => Some(bound(max)?),
+                None if max.trim().is_empty() => None,
+                None => Some(bound(max)?.checked_sub(1).ok_or_else(expected)?),
+            };
+            (min, max)
+        }
+    };
+    if max.is_some_and(|max| max < min) {
+        return Err(Error::new_spanned(lit, "the range of lengths is empty"));
+    }
+    Ok(Length { min, max, range })
+}
@@ -0,0 +1,63 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::Path;
+
+/// The type of the global constant of structs marked `#[gsm(audit = "...")]`, or whose reads are counted by the `metrics` feature of the runtime:
+/// a `Lazy` whose dereferences call the hook, once per call site, or count the reads.
+pub fn wrapper(ident: &Ident) -> Ident {
+    format_ident!("{}Static", ident)
+}
+
+/// The wrapper of the global constant `ident`, whose dereferences run `access` before lending the value.
+pub fn declare(ident: &Ident, ty: &TokenStream, audited: bool, access: TokenStream) -> TokenStream {
+    let wrapper = wrapper(ident);
+    let (doc, track_caller) = if audited {
+        (
+            format!(
+                "The global constant [`{}`], reporting which code reads its secret.",
+                ident
+            ),
+            quote!(#[track_caller]),
+        )
+    } else {
+        (
+            format!("The global constant [`{}`], counting its reads.", ident),
+            TokenStream::new(),
+        )
+    };
+    quote! {
+        #[doc = #doc]
+        pub struct #wrapper(global_secrets_manager_runtime::once_cell::sync::Lazy<#ty>);
+
+        impl std::ops::Deref for #wrapper {
+            type Target = #ty;
+
+            #track_caller
+            fn deref(&self) -> &#ty {
+                #access
+                &self.0
+            }
+        }
+    }
+}
+
+/// `audit()` of the wrapper, calling the hook with the reads of the secret `secret_name`.
+pub fn expand(ident: &Ident, secret_name: &str, hook: &Path) -> TokenStream {
+    let wrapper = wrapper(ident);
+    quote! {
+        impl #wrapper {
+            /// Calls the hook with the key read, if any, unless it was already called for that read at that location.
+            fn audit(key: Option<&'static str>, location: &'static std::panic::Location<'static>) {
+                type Site = (Option<&'static str>, &'static str, u32, u32);
+                static SEEN: std::sync::Mutex<Vec<Site>> = std::sync::Mutex::new(Vec::new());
+                let site = (key, location.file(), location.line(), location.column());
+                let mut seen = SEEN.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+                if !seen.contains(&site) {
+                    seen.push(site);
+                    drop(seen);
+                    #hook(#secret_name, key, location);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,409 @@
+//! Prints a struct matching the keys of an existing secret, with the types of its values, ready to be pasted into the application.
+//!
+//! ```text
+//! gsm-codegen <secret name> [type name]
+//! gsm-codegen --stdin <secret name> [type name] < secret.json
+//! ```
+//!
+//! The secret is fetched with the `aws` command line, so the credentials and region of the environment are used.
+//! Only the keys and the kinds of the values are read; the values are never printed.
+//! Nested objects get structs of their own, named after their keys and numbered when two would get the same name,
+//! and objects whose values are objects with the same keys are read as maps of them.
+
+#[path = "../json.rs"]
+mod json;
+
+use std::io::Read;
+use std::process::{Command, ExitCode};
+
+use json::Kind;
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use",
+    "where", "while", "yield",
+];
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let stdin = args.first().is_some_and(|arg| arg == "--stdin");
+    if stdin {
+        args.remove(0);
+    }
+    let (secret_name, type_name) = match args.as_slice() {
+        [secret_name] => (secret_name.clone(), pascal_case(secret_name)),
+        [secret_name, type_name] => (secret_name.clone(), type_name.clone()),
+        _ => {
+            eprintln!("usage: gsm-codegen [--stdin] <secret name> [type name]");
+            return ExitCode::FAILURE;
+        }
+    };
+    let secret = if stdin {
+        let mut secret = String::new();
+        if let Err(err) = std::io::stdin().read_to_string(&mut secret) {
+            eprintln!("could not read the secret: {}", err);
+            return ExitCode::FAILURE;
+        }
+        secret
+    } else {
+        match fetch(&secret_name) {
+            Ok(secret) => secret,
+            Err(err) => {
+                eprintln!("{}", err);
+                return ExitCode::FAILURE;
+            }
+        }
+    };
+    match json::object(&secret) {
+        Some(entries) => {
+            print!("{}", generate(&secret_name, &type_name, &entries));
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!("secret `{}` is not a JSON object", secret_name);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn fetch(secret_name: &str) -> Result<String, String> {
+    let output = Command::new("aws")
+        .args([
+            "secretsmanager",
+            "get-secret-value",
+            "--secret-id",
+            secret_name,
+        ])
+        .args(["--query", "SecretString", "--output", "text"])
+        .output()
+        .map_err(|err| format!("could not run the `aws` command line: {}", err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "could not fetch secret `{}`: {}",
+            secret_name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|err| err.to_string())
+}
+
+fn generate(secret_name: &str, type_name: &str, entries: &[(String, Kind)]) -> String {
+    let mut code =
+        String::from("#[derive(GlobalSecretsManager)]\n#[derive(Debug, serde::Deserialize)]\n");
+    if secret_name != type_name {
+        code += &format!("#[gsm(name = {:?})]\n", secret_name);
+    }
+    let fields = entries
+        .iter()
+        .map(|(key, kind)| (key.clone(), shape(kind)))
+        .collect();
+    let mut structs = Vec::new();
+    rust_type(type_name, &Shape::Struct(fields), &mut structs);
+    for (i, (_, definition)) in structs.iter().enumerate() {
+        if i > 0 {
+            code += "\n#[derive(Debug, serde::Deserialize)]\n";
+        }
+        code += definition;
+    }
+    code
+}
+
+/// The type a value is read into, merged over the items of arrays.
+#[derive(Clone, PartialEq)]
+enum Shape {
+    Scalar(&'static str),
+    /// `null`, whose type isn't known.
+    Null,
+    /// The items of an empty array, or values of different kinds.
+    Value,
+    Optional(Box<Shape>),
+    List(Box<Shape>),
+    /// An object whose values are objects with the same keys, such as the settings of each service.
+    Map(Box<Shape>),
+    Struct(Vec<(String, Shape)>),
+}
+
+fn shape(kind: &Kind) -> Shape {
+    match kind {
+        Kind::String => Shape::Scalar("String"),
+        Kind::Integer => Shape::Scalar("i64"),
+        Kind::Float => Shape::Scalar("f64"),
+        Kind::Bool => Shape::Scalar("bool"),
+        Kind::Null => Shape::Null,
+        Kind::Array(items) => {
+            let item = items.iter().map(shape).reduce(merge);
+            Shape::List(Box::new(item.unwrap_or(Shape::Value)))
+        }
+        Kind::Object(entries) if entries.is_empty() => Shape::Map(Box::new(Shape::Value)),
+        Kind::Object(entries) => {
+            let fields: Vec<(String, Shape)> = entries
+                .iter()
+                .map(|(key, kind)| (key.clone(), shape(kind)))
+                .collect();
+            fn keys(shape: &Shape) -> Option<Vec<&str>> {
+                match shape {
+                    Shape::Struct(fields) => {
+                        let mut keys: Vec<&str> =
+                            fields.iter().map(|(key, _)| key.as_str()).collect();
+                        keys.sort_unstable();
+                        Some(keys)
+                    }
+                    _ => None,
+                }
+            }
+            let first = keys(&fields[0].1);
+            if fields.len() > 1
+                && first.is_some()
+                && fields.iter().all(|(_, shape)| keys(shape) == first)
+            {
+                let value = fields.into_iter().map(|(_, shape)| shape).reduce(merge);
+                Shape::Map(Box::new(value.unwrap_or(Shape::Value)))
+            } else {
+                Shape::Struct(fields)
+            }
+        }
+    }
+}
+
+/// The shape reading both values: a number of either kind is an `f64`, `null` makes a value optional,
+/// and the keys absent from some of the objects are optional.
+fn merge(a: Shape, b: Shape) -> Shape {
+    match (a, b) {
+        (a, b) if a == b => a,
+        (Shape::Scalar("i64"), Shape::Scalar("f64"))
+        | (Shape::Scalar("f64"), Shape::Scalar("i64")) => Shape::Scalar("f64"),
+        (Shape::Null, other) | (other, Shape::Null) => match other {
+            Shape::Optional(_) => other,
+            other => Shape::Optional(Box::new(other)),
+        },
+        (Shape::Optional(a), Shape::Optional(b)) => Shape::Optional(Box::new(merge(*a, *b))),
+        (Shape::Optional(a), b) | (b, Shape::Optional(a)) => {
+            Shape::Optional(Box::new(merge(*a, b)))
+        }
+        (Shape::List(a), Shape::List(b)) => Shape::List(Box::new(merge_items(*a, *b))),
+        (Shape::Map(a), Shape::Map(b)) => Shape::Map(Box::new(merge_items(*a, *b))),
+        (Shape::Struct(a), Shape::Struct(b)) => {
+            let mut fields: Vec<(String, Shape)> = Vec::new();
+            for (key, shape) in &a {
+                let merged = match b.iter().find(|(other, _)| other == key) {
+                    Some((_, other)) => merge(shape.clone(), other.clone()),
+                    None => merge(shape.clone(), Shape::Null),
+                };
+                fields.push((key.clone(), merged));
+            }
+            for (key, shape) in b {
+                if !a.iter().any(|(other, _)| *other == key) {
+                    fields.push((key, merge(shape, Shape::Null)));
+                }
+            }
+            Shape::Struct(fields)
+        }
+        _ => Shape::Value,
+    }
+}
+
+/// The items of an empty array or object take the shape of the other's.
+fn merge_items(a: Shape, b: Shape) -> Shape {
+    match (a, b) {
+        (Shape::Value, other) | (other, Shape::Value) => other,
+        (a, b) => merge(a, b),
+    }
+}
+
+/// The fields of a struct, with the structs of the nested objects appended to `structs`, named after their keys.
+fn body(
+    type_name: &str,
+    fields: &[(String, Shape)],
+    structs: &mut Vec<(String, String)>,
+) -> String {
+    let mut code = String::new();
+    let mut names: Vec<String> = Vec::new();
+    for (i, (key, shape)) in fields.iter().enumerate() {
+        let mut field = field_name(key, i);
+        // Keys such as `db-host` and `db_host` would get the same field.
+        if names.contains(&field) {
+            field = format!("{}_{}", field.trim_start_matches("r#"), i);
+        }
+        if field.trim_start_matches("r#") != key {
+            code += &format!("    #[serde(rename = {:?})]\n", key);
+        }
+        let name = format!("{}{}", type_name, pascal_case(key));
+        code += &format!("    pub {}: {},\n", field, rust_type(&name, shape, structs));
+        names.push(field);
+    }
+    code
+}
+
+/// The type of `shape`, where a struct is named `name`, or `name` followed by a number when another struct already is.
+fn rust_type(name: &str, shape: &Shape, structs: &mut Vec<(String, String)>) -> String {
+    match shape {
+        Shape::Scalar(ty) => (*ty).to_owned(),
+        Shape::Null => "Option<serde_json::Value>".to_owned(),
+        Shape::Value => "serde_json::Value".to_owned(),
+        Shape::Optional(shape) => format!("Option<{}>", rust_type(name, shape, structs)),
+        Shape::List(shape) => format!("Vec<{}>", rust_type(name, shape, structs)),
+        Shape::Map(shape) => format!(
+            "std::collections::HashMap<String, {}>",
+            rust_type(name, shape, structs)
+        ),
+        Shape::Struct(fields) => {
+            let taken = |name: &str| structs.iter().any(|(other, _)| other == name);
+            let name = match taken(name) {
+                false => name.to_owned(),
+                true => (2..)
+                    .map(|n| format!("{}{}", name, n))
+                    .find(|name| !taken(name))
+                    .unwrap(),
+            };
+            // Reserved before the fields, so that the structs are printed from the outermost.
+            let index = structs.len();
+            structs.push((name.clone(), String::new()));
+            let mut definition = format!("pub struct {} {{\n", name);
+            definition += &body(&name, fields, structs);
+            definition += "}\n";
+            structs[index].1 = definition;
+            name
+        }
+    }
+}
+
+/// The key in snake_case, or `field{i}` when nothing of it can be kept.
+fn field_name(key: &str, i: usize) -> String {
+    let mut name = String::new();
+    let mut previous_lowercase = false;
+    for c in key.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && previous_lowercase {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+            previous_lowercase = c.is_ascii_lowercase() || c.is_ascii_digit();
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+            previous_lowercase = false;
+        }
+    }
+    let name = name.trim_end_matches('_').to_owned();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("field{}", i)
+    } else if KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name)
+    } else {
+        name
+    }
+}
+
+/// `prod/app-db` becomes `ProdAppDb`.
+fn pascal_case(secret_name: &str) -> String {
+    let name: String = secret_name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word[..1].to_ascii_uppercase() + &word[1..])
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) || name.is_empty() {
+        format!("Secret{}", name)
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codegen(secret: &str) -> String {
+        generate("prod/app", "App", &json::object(secret).unwrap())
+    }
+
+    #[test]
+    fn nested_objects_get_structs_of_their_own() {
+        assert_eq!(
+            codegen(r#"{"db": {"host": "h", "port": 5432, "tls": {"verify": true}}}"#),
+            "#[derive(GlobalSecretsManager)]\n\
+             #[derive(Debug, serde::Deserialize)]\n\
+             #[gsm(name = \"prod/app\")]\n\
+             pub struct App {\n    pub db: AppDb,\n}\n\
+             \n#[derive(Debug, serde::Deserialize)]\n\
+             pub struct AppDb {\n    pub host: String,\n    pub port: i64,\n    pub tls: AppDbTls,\n}\n\
+             \n#[derive(Debug, serde::Deserialize)]\n\
+             pub struct AppDbTls {\n    pub verify: bool,\n}\n",
+        );
+    }
+
+    #[test]
+    fn arrays_of_objects_merge_their_items() {
+        let code = codegen(
+            r#"{"servers": [{"host": "a", "port": 1}, {"host": "b", "weight": 0.5}], "tags": []}"#,
+        );
+        assert!(
+            code.contains("    pub servers: Vec<AppServers>,\n"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("    pub tags: Vec<serde_json::Value>,\n"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("pub struct AppServers {\n    pub host: String,\n    pub port: Option<i64>,\n    pub weight: Option<f64>,\n}\n"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn objects_of_objects_with_the_same_keys_are_maps() {
+        let code = codegen(r#"{"services": {"billing": {"url": "a"}, "search": {"url": "b"}}}"#);
+        assert!(
+            code.contains("    pub services: std::collections::HashMap<String, AppServices>,\n"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("pub struct AppServices {\n    pub url: String,\n}\n"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn colliding_names_are_numbered() {
+        let code = codegen(
+            r#"{"db_config": {"a": 1}, "db": {"config": {"b": true}}, "db-host": "a", "db_host": "b"}"#,
+        );
+        assert!(
+            code.contains("    pub db_config: AppDbConfig,\n"),
+            "{}",
+            code
+        );
+        assert!(code.contains("    pub config: AppDbConfig2,\n"), "{}", code);
+        assert!(
+            code.contains("pub struct AppDbConfig2 {\n    pub b: bool,\n}\n"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("    #[serde(rename = \"db-host\")]\n    pub db_host: String,\n    #[serde(rename = \"db_host\")]\n    pub db_host_3: String,\n"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn keys_that_are_not_identifiers_are_renamed() {
+        let code = codegen(r#"{"type": "a", "apiKey": "b", "2fa": true}"#);
+        assert!(code.contains("    pub r#type: String,\n"), "{}", code);
+        assert!(
+            code.contains("    #[serde(rename = \"apiKey\")]\n    pub api_key: String,\n"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("    #[serde(rename = \"2fa\")]\n    pub field2: bool,\n"),
+            "{}",
+            code
+        );
+    }
+}
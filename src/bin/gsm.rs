@@ -0,0 +1,416 @@
+//! Lists the secrets that the structs of an application read, and checks that they can be fetched and match their fields, as a gate before deploying.
+//!
+//! ```text
+//! gsm list [source directory]
+//! gsm check [--profile <name>] [source directory]
+//! ```
+//!
+//! The structs are found in the sources, `src` by default, and read as the derive reads them: `#[derive(GlobalSecretsManager)]`,
+//! `#[global_secret]` and `secrets! { ... }`, with their names and the keys of their fields.
+//! The secrets are fetched with the `aws` command line, with the profile given or the credentials and region of the environment.
+//! Only the keys are compared, and the report never prints the values, nor the keys of the structs marked `#[gsm(redact_keys)]`.
+//!
+//! Built with the `cli` feature: `cargo install global-secrets-manager --features cli --bin gsm`.
+
+#[path = "../attr.rs"]
+#[allow(dead_code)]
+mod attr;
+#[path = "../check.rs"]
+#[allow(dead_code)]
+mod check;
+#[path = "../json.rs"]
+mod json;
+#[path = "../schema.rs"]
+#[allow(dead_code)]
+mod schema;
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    parenthesized, Attribute, Data, DeriveInput, Fields, FieldsNamed, Ident, Item, LitStr, Meta,
+    NestedMeta, Visibility,
+};
+
+use attr::{ContainerAttrs, VariantAttrs};
+use schema::Schema;
+
+/// A secret read by a struct of the application.
+struct Secret {
+    /// The name of the secret, its tags when it is looked up by them, or what its backend reads.
+    name: String,
+    /// The struct or the variant reading it.
+    ty: String,
+    /// Why the check doesn't fetch it: it is looked up by its tags, read from another backend, or named for each tenant or instance.
+    skipped: Option<String>,
+    /// The keys of the fields, unless the derive can't know them, as for the variants of an enum.
+    schema: Option<Schema>,
+    redact_keys: bool,
+    /// Where the struct is declared.
+    file: PathBuf,
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let command = if args.is_empty() {
+        String::new()
+    } else {
+        args.remove(0)
+    };
+    let mut profile = None;
+    if let Some(i) = args.iter().position(|arg| arg == "--profile") {
+        args.remove(i);
+        if i < args.len() {
+            profile = Some(args.remove(i));
+        }
+    }
+    let dir = match args.as_slice() {
+        [] => PathBuf::from("src"),
+        [dir] => PathBuf::from(dir),
+        _ => return usage(),
+    };
+    if !matches!(command.as_str(), "list" | "check") || (command == "list" && profile.is_some()) {
+        return usage();
+    }
+
+    let mut secrets = Vec::new();
+    if let Err(err) = scan_dir(&dir, &mut secrets) {
+        eprintln!("{}", err);
+        return ExitCode::FAILURE;
+    }
+    if command == "list" {
+        for secret in &secrets {
+            println!("{}\t{}\t{}", secret.name, secret.ty, secret.file.display());
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let mut failed = 0;
+    for secret in &secrets {
+        let (status, detail) = check(secret, profile.as_deref());
+        if status != "ok" && status != "skipped" {
+            failed += 1;
+        }
+        match detail {
+            Some(detail) => println!("{:<8} {} ({}): {}", status, secret.name, secret.ty, detail),
+            None => println!("{:<8} {} ({})", status, secret.name, secret.ty),
+        }
+    }
+    println!("{} secrets checked, {} failed", secrets.len(), failed);
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn usage() -> ExitCode {
+    eprintln!("usage: gsm list [source directory]\n       gsm check [--profile <name>] [source directory]");
+    ExitCode::FAILURE
+}
+
+/// The status of the secret, `ok`, `skipped`, `error` or `drift`, with what it is about.
+fn check(secret: &Secret, profile: Option<&str>) -> (&'static str, Option<String>) {
+    if let Some(reason) = &secret.skipped {
+        return ("skipped", Some(reason.clone()));
+    }
+    let payload = match fetch(&secret.name, profile) {
+        Ok(payload) => payload,
+        Err(err) => return ("error", Some(err)),
+    };
+    let schema = match &secret.schema {
+        Some(schema) => schema,
+        None => return ("ok", None),
+    };
+    let keys = match json::object(&payload) {
+        Some(entries) => entries.into_iter().map(|(key, _)| key).collect::<Vec<_>>(),
+        None => return ("error", Some("it is not a JSON object".to_owned())),
+    };
+    let (missing, extra) = schema.diff(&keys);
+    if missing.is_empty() && extra.is_empty() {
+        return ("ok", None);
+    }
+    let detail = if secret.redact_keys {
+        format!(
+            "{} missing keys, {} unexpected keys",
+            missing.len(),
+            extra.len()
+        )
+    } else {
+        format!(
+            "missing keys [{}], unexpected keys [{}]",
+            missing.join(", "),
+            extra.join(", ")
+        )
+    };
+    // Unexpected keys only fail the load under `#[serde(deny_unknown_fields)]` or `#[gsm(strict)]`, but are reported all the same.
+    if missing.is_empty() && !schema.deny_unknown && !schema.strict {
+        ("ok", Some(detail))
+    } else {
+        ("drift", Some(detail))
+    }
+}
+
+/// The string value of the secret, fetched with the `aws` command line.
+fn fetch(secret_name: &str, profile: Option<&str>) -> Result<String, String> {
+    let mut command = Command::new("aws");
+    command
+        .args([
+            "secretsmanager",
+            "get-secret-value",
+            "--secret-id",
+            secret_name,
+        ])
+        .args(["--query", "SecretString", "--output", "text"]);
+    if let Some(profile) = profile {
+        command.args(["--profile", profile]);
+    }
+    let output = command
+        .output()
+        .map_err(|err| format!("could not run the `aws` command line: {}", err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "could not fetch it: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|err| err.to_string())
+}
+
+fn scan_dir(dir: &Path, secrets: &mut Vec<Secret>) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|err| format!("could not read `{}`: {}", dir.display(), err))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            scan_dir(&path, secrets)?;
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            let source = std::fs::read_to_string(&path)
+                .map_err(|err| format!("could not read `{}`: {}", path.display(), err))?;
+            let file = syn::parse_file(&source)
+                .map_err(|err| format!("could not parse `{}`: {}", path.display(), err))?;
+            scan_items(&file.items, &path, secrets)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+        }
+    }
+    Ok(())
+}
+
+fn scan_items(items: &[Item], file: &Path, secrets: &mut Vec<Secret>) -> syn::Result<()> {
+    for item in items {
+        match item {
+            Item::Struct(item) => {
+                let attribute = item
+                    .attrs
+                    .iter()
+                    .find(|attr| is_named(attr, "global_secret"));
+                if attribute.is_none() && !derives(&item.attrs) {
+                    continue;
+                }
+                let input = DeriveInput::from(item.clone());
+                let mut container = ContainerAttrs::from_attrs(&input.attrs)?;
+                if let Some(Meta::List(list)) = attribute.map(Attribute::parse_meta).transpose()? {
+                    for nested in list.nested {
+                        container.parse_nested(nested)?;
+                    }
+                }
+                secrets.extend(secret(&input, &container, file)?);
+            }
+            Item::Enum(item) if derives(&item.attrs) => {
+                for variant in &item.variants {
+                    let name = VariantAttrs::from_attrs(&variant.attrs)?
+                        .name
+                        .map(|name| name.value());
+                    let inner = match &variant.fields {
+                        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                            &fields.unnamed[0].ty
+                        }
+                        _ => continue,
+                    };
+                    let name = match (name, inner) {
+                        (Some(name), _) => name,
+                        (None, syn::Type::Path(path)) => {
+                            path.path.segments.last().unwrap().ident.to_string()
+                        }
+                        _ => continue,
+                    };
+                    secrets.push(Secret {
+                        name,
+                        ty: format!("{}::{}", item.ident, variant.ident),
+                        skipped: None,
+                        schema: None,
+                        redact_keys: false,
+                        file: file.to_owned(),
+                    });
+                }
+            }
+            Item::Macro(item)
+                if item
+                    .mac
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|s| s.ident == "secrets") =>
+            {
+                for Declaration {
+                    attrs,
+                    vis,
+                    ident,
+                    name,
+                    fields,
+                } in item.mac.parse_body::<Declarations>()?.0
+                {
+                    let input: DeriveInput = syn::parse_quote! {
+                        #(#attrs)*
+                        #vis struct #ident #fields
+                    };
+                    let mut container = ContainerAttrs::from_attrs(&input.attrs)?;
+                    if let Some(name) = name {
+                        container.set_name(name)?;
+                    }
+                    secrets.extend(secret(&input, &container, file)?);
+                }
+            }
+            Item::Mod(item) => {
+                if let Some((_, items)) = &item.content {
+                    scan_items(items, file, secrets)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The secrets read by the struct: its own, or the one of each of its environments.
+fn secret(
+    input: &DeriveInput,
+    container: &ContainerAttrs,
+    file: &Path,
+) -> syn::Result<Vec<Secret>> {
+    let secret_name = container
+        .name
+        .as_ref()
+        .map_or_else(|| input.ident.to_string(), LitStr::value);
+    let schema = || -> syn::Result<Option<Schema>> {
+        match &input.data {
+            Data::Struct(data) => match &data.fields {
+                Fields::Named(fields) => Ok(Some(schema::schema(input, fields)?)),
+                _ => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    };
+    let mut names = Vec::new();
+    let mut skipped = None;
+    if let Some(backend) = &container.backend {
+        names.push((
+            format!(
+                "{} {}",
+                backend.name(),
+                container.backend_source().unwrap_or_default()
+            ),
+            input.ident.to_string(),
+        ));
+        skipped = Some(format!("read from {}", backend.name()));
+    } else if let Some(tags) = &container.tags {
+        let tags: Vec<_> = tags
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        names.push((
+            format!("tagged {}", tags.join(", ")),
+            input.ident.to_string(),
+        ));
+        skipped = Some("looked up by tags".to_owned());
+    } else if let Some(tenant) = &container.tenant {
+        names.push((tenant.name.value(), input.ident.to_string()));
+        skipped = Some("one for each tenant".to_owned());
+    } else if container.instances.is_some() {
+        names.push((secret_name, input.ident.to_string()));
+        skipped = Some("one for each instance".to_owned());
+    } else if container.environments.is_empty() {
+        names.push((secret_name, input.ident.to_string()));
+    } else {
+        for environment in &container.environments {
+            let name = environment
+                .name
+                .as_ref()
+                .map_or_else(|| secret_name.clone(), LitStr::value);
+            names.push((
+                name,
+                format!("{} in {}", input.ident, environment.value.value()),
+            ));
+        }
+    }
+    names
+        .into_iter()
+        .map(|(name, ty)| {
+            Ok(Secret {
+                name,
+                ty,
+                skipped: skipped.clone(),
+                schema: schema()?,
+                redact_keys: container.redact_keys.is_some(),
+                file: file.to_owned(),
+            })
+        })
+        .collect()
+}
+
+fn derives(attrs: &[Attribute]) -> bool {
+    attrs.iter().filter(|attr| is_named(attr, "derive")).any(|attr| {
+        matches!(attr.parse_meta(), Ok(Meta::List(list)) if list.nested.iter().any(|nested| {
+            matches!(nested, NestedMeta::Meta(Meta::Path(path)) if path.segments.last().is_some_and(|s| s.ident == "GlobalSecretsManager"))
+        }))
+    })
+}
+
+fn is_named(attr: &Attribute, name: &str) -> bool {
+    attr.path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == name)
+}
+
+/// The declarations of `secrets! { ... }`, as the macro parses them.
+struct Declarations(Vec<Declaration>);
+
+struct Declaration {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    ident: Ident,
+    name: Option<LitStr>,
+    fields: FieldsNamed,
+}
+
+impl Parse for Declarations {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut declarations = Vec::new();
+        while !input.is_empty() {
+            let attrs = input.call(Attribute::parse_outer)?;
+            let vis = input.parse()?;
+            let ident = input.parse()?;
+            let name = if input.peek(syn::token::Paren) {
+                let content;
+                parenthesized!(content in input);
+                Some(content.parse()?)
+            } else {
+                None
+            };
+            let fields = input.parse()?;
+            declarations.push(Declaration {
+                attrs,
+                vis,
+                ident,
+                name,
+                fields,
+            });
+        }
+        Ok(Self(declarations))
+    }
+}
@@ -0,0 +1,131 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DeriveInput, FieldsNamed, Result};
+
+use crate::attr::ContainerAttrs;
+use crate::schema;
+
+/// `builder()`, in the tests of the application and with the `test-util` feature of the runtime crate, building the struct field by field without fetching the secret,
+/// as its fields are often private, `fake(seed)`, filling them with fake values derived from the seed,
+/// and with `#[gsm(test_default)]`, `Default`, leaving every field to its own default.
+pub fn expand(
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    ty: &TokenStream,
+    secret_name: &str,
+    container: &ContainerAttrs,
+) -> Result<TokenStream> {
+    let DeriveInput {
+        vis,
+        ident,
+        generics,
+        ..
+    } = input;
+    let builder = format_ident!("{}Builder", ident);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let names: Vec<_> = fields.named.iter().map(|f| &f.ident).collect();
+    let types: Vec<_> = fields.named.iter().map(|f| &f.ty).collect();
+    let docs = names.iter().map(|name| {
+        format!(
+            "Sets the value of the key `{}` of the secret.",
+            name.as_ref().unwrap()
+        )
+    });
+    let values = fields.named.iter().map(|field| {
+        let name = field.ident.as_ref().unwrap();
+        if schema::is_option(&field.ty) {
+            quote!(self.#name.unwrap_or_default())
+        } else {
+            let message = format!("`{}` must be set to build `{}`", name, ident);
+            quote!(self.#name.expect(#message))
+        }
+    });
+
+    let default = container.test_default.as_ref().map(|_| {
+        quote! {
+            impl #impl_generics Default for #ident #ty_generics #where_clause {
+                fn default() -> Self {
+                    #ident {
+                        #(#names: Default::default(),)*
+                    }
+                }
+            }
+        }
+    });
+
+    let fakes = schema::schema(input, fields)?.keys.into_iter().map(|key| {
+        let name = &key.names[0];
+        let accepts = match &key.ty {
+            Some(ty) => schema::accepts(ty, quote!(*value)),
+            None => quote!(true),
+        };
+        quote! {
+            let values = global_secrets_manager_runtime::fake::values(seed, #secret_name, #name);
+            let value = values.iter().map(String::as_str).find(|value| #accepts).unwrap_or("null");
+            fields.push(format!("{}:{}", global_secrets_manager_runtime::serde_json::Value::String(#name.to_owned()), value));
+        }
+    });
+
+    let deserialize = schema::deserialize(
+        input,
+        secret_name,
+        quote!(&format!("{{{}}}", fields.join(","))),
+    );
+
+    Ok(quote! {
+        global_secrets_manager_runtime::__test_util! {
+            #[doc = concat!("Builds [`", stringify!(#ident), "`] in tests, without fetching the secret.")]
+            #vis struct #builder #generics #where_clause {
+                #(#names: Option<#types>,)*
+            }
+
+            impl #impl_generics #ident #ty_generics #where_clause {
+                #[doc = concat!("A [`", stringify!(#builder), "`] with no field set.")]
+                pub fn builder() -> #builder #ty_generics {
+                    #builder {
+                        #(#names: None,)*
+                    }
+                }
+            }
+
+            impl #impl_generics #builder #ty_generics #where_clause {
+                #(
+                    #[doc = #docs]
+                    pub fn #names(mut self, value: #types) -> Self {
+                        self.#names = Some(value);
+                        self
+                    }
+                )*
+
+                /// The struct of the values set, where the `Option` fields not set are `None`.
+                ///
+                /// # Panics
+                ///
+                /// Panics if another field isn't set.
+                pub fn build(self) -> #ident #ty_generics {
+                    #ident {
+                        #(#names: #values,)*
+                    }
+                }
+            }
+
+            impl #ty {
+                /// The struct read from a payload of fake values derived from `seed` alone, shaped after the names of the keys,
+                /// such as a URL for `api_url`, so that snapshot tests are reproducible on any machine.
+                ///
+                /// # Panics
+                ///
+                /// Panics if a field accepts none of the values, such as a string of a given format.
+                pub fn fake(seed: u64) -> Self {
+                    let mut fields: Vec<String> = Vec::new();
+                    #(#fakes)*
+                    #deserialize
+                        .expect("the fake values must be accepted by the fields")
+                }
+            }
+
+            #default
+        }
+    })
+}
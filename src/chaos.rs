@@ -0,0 +1,39 @@
+//! Faults injected into fetches with the `chaos` feature, so that applications can test how they handle slow or failing loads.
+//! They are configured when the application runs, by environment variables:
+//!
+//! - `GSM_CHAOS_LATENCY_MS`: delay added before every fetch
+//! - `GSM_CHAOS_ERROR_RATE`: share of fetches failing as throttled, from `0` to `1`
+//! - `GSM_CHAOS_MALFORMED_RATE`: share of fetches returning a payload that isn't JSON
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// A future faulting the fetch `fetch`, innermost so that the integrations observing fetches see the faults.
+pub fn fetch(fetch: TokenStream) -> TokenStream {
+    quote! {
+        async {
+            fn env<T: std::str::FromStr>(name: &str) -> Option<T> {
+                std::env::var(name).ok()?.parse().ok()
+            }
+            fn roll(rate: Option<f64>) -> bool {
+                use std::hash::{BuildHasher, Hasher};
+                let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+                matches!(rate, Some(rate) if (random as f64 / u64::MAX as f64) < rate)
+            }
+            if let Some(latency) = env::<u64>("GSM_CHAOS_LATENCY_MS") {
+                global_secrets_manager_runtime::executor::sleep(std::time::Duration::from_millis(latency)).await;
+            }
+            if roll(env("GSM_CHAOS_ERROR_RATE")) {
+                return Err(global_secrets_manager_runtime::GsmError::Throttled {
+                    secret: secret_id.to_owned(),
+                    source: "ThrottlingException: rate exceeded (injected by GSM_CHAOS_ERROR_RATE)".into(),
+                }
+                .into());
+            }
+            if roll(env("GSM_CHAOS_MALFORMED_RATE")) {
+                return Ok("{\"injected by GSM_CHAOS_MALFORMED_RATE\"".to_owned());
+            }
+            #fetch.await
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Checking the keys of the secret while compiling, with the `aws` command line, when `GSM_COMPILE_TIME_CHECK=1` is set.
+//! The macro can't depend on the SDK, and only the keys are read from its output, never the values.
+//! The keys are compared as `gsm check` compares them.
+
+use std::process::Command;
+
+use syn::{DeriveInput, Error, FieldsNamed, Result};
+
+use crate::json;
+use crate::schema::{self, Schema};
+
+const ENV_VAR: &str = "GSM_COMPILE_TIME_CHECK";
+
+/// Fails the expansion when the secret lacks keys the fields need or has keys no field reads.
+pub fn secret(input: &DeriveInput, fields: &FieldsNamed, secret_name: &str) -> Result<()> {
+    if !matches!(std::env::var(ENV_VAR).as_deref(), Ok("1" | "true")) {
+        return Ok(());
+    }
+    let fail = |message: String| Err(Error::new_spanned(&input.ident, message));
+    let output = Command::new("aws")
+        .args([
+            "secretsmanager",
+            "get-secret-value",
+            "--secret-id",
+            secret_name,
+        ])
+        .args(["--query", "SecretString", "--output", "text"])
+        .output();
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            return fail(format!(
+                "could not fetch secret `{}` to check it ({} is set): {}",
+                secret_name,
+                ENV_VAR,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+        Err(err) => {
+            return fail(format!(
+                "could not run the `aws` command line to check secret `{}` ({} is set): {}",
+                secret_name, ENV_VAR, err
+            ))
+        }
+    };
+    let keys = match json::object(&String::from_utf8_lossy(&output.stdout)) {
+        Some(entries) => entries.into_iter().map(|(key, _)| key).collect::<Vec<_>>(),
+        None => return fail(format!("secret `{}` is not a JSON object", secret_name)),
+    };
+    let (missing, extra) = schema::schema(input, fields)?.diff(&keys);
+    if missing.is_empty() && extra.is_empty() {
+        return Ok(());
+    }
+    fail(format!(
+        "secret `{}` doesn't match the fields: missing keys {:?}, unexpected keys {:?}",
+        secret_name, missing, extra
+    ))
+}
+
+impl Schema {
+    /// The required keys absent from `keys`, and the keys of `keys` that no field reads, except those of `allow_extra`.
+    pub fn diff(&self, keys: &[String]) -> (Vec<String>, Vec<String>) {
+        let missing = self
+            .keys
+            .iter()
+            .filter(|key| key.required && !key.names.iter().any(|name| keys.contains(name)))
+            .map(|key| key.names[0].clone())
+            .collect();
+        let extra = keys
+            .iter()
+            .filter(|key| !self.open && !self.keys.iter().any(|k| k.names.contains(key)))
+            .filter(|key| {
+                !self
+                    .allow_extra
+                    .iter()
+                    .any(|glob| global_secrets_manager_runtime::glob_matches(glob, key))
+            })
+            .cloned()
+            .collect();
+        (missing, extra)
+    }
+}
@@ -0,0 +1,80 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Error, FieldsNamed, Result};
+
+use crate::attr::FieldAttrs;
+use crate::schema;
+
+/// `with_common()`, embedding the payloads of the secrets of the `#[gsm(common)]` fields into the struct's payload before it is read.
+///
+/// Each common field holds a struct deriving `GlobalSecretsManager` itself, whose secret is read as its own type reads it,
+/// and whose payload is put under the key of the field, so that several structs share one secret, such as the org-wide endpoints.
+pub fn expand(
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    field_attrs: &[FieldAttrs],
+    ty: &TokenStream,
+    secret_name: &str,
+) -> Result<TokenStream> {
+    if field_attrs.iter().all(|attrs| attrs.common.is_none()) {
+        return Ok(quote! {
+            impl #ty {
+                async fn with_common(_client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, rt_str: String) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                    Ok(rt_str)
+                }
+            }
+        });
+    }
+
+    let keys = schema::schema(input, fields)?.keys;
+    let mut inserts = Vec::new();
+    for (field, attrs) in fields.named.iter().zip(field_attrs) {
+        let common = match &attrs.common {
+            Some(common) => common,
+            None => continue,
+        };
+        let (name, field_ty) = match keys
+            .iter()
+            .find(|key| Some(&key.field) == field.ident.as_ref())
+        {
+            Some(key) => match &key.ty {
+                Some(field_ty) => (&key.names[0], field_ty),
+                None => {
+                    return Err(Error::new_spanned(
+                        common,
+                        "`common` can't be set on a field that serde deserializes with a function of its own",
+                    ))
+                }
+            },
+            None => {
+                return Err(Error::new_spanned(
+                    common,
+                    "`common` can't be set on a field that serde doesn't read from the secret",
+                ))
+            }
+        };
+        inserts.push(quote! {
+            // Errors about the payload of the common secret name that secret rather than the struct's.
+            let (common_id, payload) = <#field_ty>::common_payload(client).await?;
+            let value = match global_secrets_manager_runtime::parse_json(&common_id, &payload)? {
+                global_secrets_manager_runtime::serde_json::Value::Object(value) => value,
+                _ => return Err(global_secrets_manager_runtime::GsmError::decode(&common_id, "it must be a JSON object to be embedded as a common secret").into()),
+            };
+            object.insert(#name.to_owned(), global_secrets_manager_runtime::serde_json::Value::Object(value));
+        });
+    }
+
+    Ok(quote! {
+        impl #ty {
+            /// Puts the payloads of the secrets of the `#[gsm(common)]` fields under their keys, replacing the values of the struct's secret.
+            async fn with_common(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, rt_str: String) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                let mut object = match global_secrets_manager_runtime::serde_json::from_str(&rt_str) {
+                    Ok(global_secrets_manager_runtime::serde_json::Value::Object(object)) => object,
+                    _ => return Err(global_secrets_manager_runtime::GsmError::decode(#secret_name, "it must be a JSON object to embed its common secrets").into()),
+                };
+                #(#inserts)*
+                Ok(global_secrets_manager_runtime::serde_json::Value::Object(object).to_string())
+            }
+        }
+    })
+}
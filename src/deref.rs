@@ -0,0 +1,125 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Error, FieldsNamed, GenericArgument, PathArguments, Result, Type};
+
+use crate::attr::{ContainerAttrs, FieldAttrs};
+use crate::schema;
+
+/// `with_deref()`, replacing the values of the `#[gsm(deref_secret)]` fields, ARNs or names of other secrets, with the payloads of these secrets.
+///
+/// The payload is put as is into the fields holding strings, and parsed as JSON for the other fields, such as the structs of its keys.
+pub fn expand(
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    field_attrs: &[FieldAttrs],
+    ty: &TokenStream,
+    secret_name: &str,
+    container: &ContainerAttrs,
+) -> Result<TokenStream> {
+    if field_attrs.iter().all(|attrs| attrs.deref_secret.is_none()) {
+        return Ok(quote! {
+            impl #ty {
+                async fn with_deref(_client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, rt_str: String) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                    Ok(rt_str)
+                }
+            }
+        });
+    }
+
+    let keys = schema::schema(input, fields)?.keys;
+    let mut replaces = Vec::new();
+    for (field, attrs) in fields.named.iter().zip(field_attrs) {
+        let deref_secret = match &attrs.deref_secret {
+            Some(deref_secret) => deref_secret,
+            None => continue,
+        };
+        if let Some(backend) = &container.backend {
+            return Err(Error::new_spanned(
+                deref_secret,
+                format!(
+                    "`deref_secret` follows references to secrets of Secrets Manager, and can't be combined with `{}`",
+                    backend.option()
+                ),
+            ));
+        }
+        if attrs.common.is_some() {
+            return Err(Error::new_spanned(
+                deref_secret,
+                "`deref_secret` and `common` both read the field from another secret; set one of them",
+            ));
+        }
+        let name = match keys
+            .iter()
+            .find(|key| Some(&key.field) == field.ident.as_ref())
+        {
+            Some(key) => &key.names[0],
+            None => return Err(Error::new_spanned(
+                deref_secret,
+                "`deref_secret` can't be set on a field that serde doesn't read from the secret",
+            )),
+        };
+        let value = if holds_string(&field.ty) {
+            quote! { global_secrets_manager_runtime::serde_json::Value::String(payload) }
+        } else {
+            quote! { global_secrets_manager_runtime::parse_json(reference, &payload)? }
+        };
+        replaces.push(quote! {
+            match object.get(#name) {
+                None | Some(global_secrets_manager_runtime::serde_json::Value::Null) => {}
+                Some(global_secrets_manager_runtime::serde_json::Value::String(reference)) => {
+                    let reference = &Self::conventional_name(reference)?;
+                    let payload = Self::get_secret(client, reference).await?;
+                    object.insert(#name.to_owned(), #value);
+                }
+                Some(_) => {
+                    return Err(global_secrets_manager_runtime::GsmError::decode(
+                        #secret_name,
+                        concat!("`", #name, "` must hold the ARN or the name of the secret it refers to"),
+                    )
+                    .into())
+                }
+            }
+        });
+    }
+
+    Ok(quote! {
+        impl #ty {
+            /// Replaces the references of the `#[gsm(deref_secret)]` fields with the payloads of the secrets they refer to.
+            async fn with_deref(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, rt_str: String) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                let mut object = match global_secrets_manager_runtime::serde_json::from_str(&rt_str) {
+                    Ok(global_secrets_manager_runtime::serde_json::Value::Object(object)) => object,
+                    _ => return Err(global_secrets_manager_runtime::GsmError::decode(#secret_name, "it must be a JSON object to follow its references to secrets").into()),
+                };
+                #(#replaces)*
+                Ok(global_secrets_manager_runtime::serde_json::Value::Object(object).to_string())
+            }
+        }
+    })
+}
+
+/// Whether the type holds a string, such as `String`, `&str` or `Cow<str>`, optionally in an `Option`.
+fn holds_string(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) => holds_string(&reference.elem),
+        Type::Path(path) => {
+            let segment = match path.path.segments.last() {
+                Some(segment) => segment,
+                None => return false,
+            };
+            match &segment.arguments {
+                PathArguments::None => segment.ident == "String" || segment.ident == "str",
+                PathArguments::AngleBracketed(args)
+                    if ["Option", "Cow", "Box", "Rc", "Arc"]
+                        .iter()
+                        .any(|wrapper| segment.ident == wrapper) =>
+                {
+                    args.args
+                        .iter()
+                        .any(|arg| matches!(arg, GenericArgument::Type(ty) if holds_string(ty)))
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
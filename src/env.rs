@@ -0,0 +1,208 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{DeriveInput, Error, FieldsNamed, Ident, Result};
+
+use crate::attr::{ContainerAttrs, FieldAttrs};
+use crate::audit;
+use crate::schema;
+
+/// `as_env_vars()` when `#[gsm(export)]` is set, returning the fields as environment variables for child processes,
+/// named after the fields in uppercase unless `#[gsm(export = "...")]` names them.
+///
+/// It is opt-in since every exported field must implement `Display`.
+pub fn expand(
+    ident: &Ident,
+    fields: &FieldsNamed,
+    field_attrs: &[FieldAttrs],
+    ty: &TokenStream,
+    container: &ContainerAttrs,
+) -> TokenStream {
+    let prefix = match &container.export {
+        Some(prefix) => prefix.value(),
+        None => return TokenStream::new(),
+    };
+    let vars = fields
+        .named
+        .iter()
+        .zip(field_attrs)
+        .filter(|(_, attrs)| attrs.skip_export.is_none())
+        .map(|(field, attrs)| {
+            let member = field.ident.as_ref().unwrap();
+            let name = attrs.export.as_ref().map_or_else(
+                || member.unraw().to_string().to_ascii_uppercase(),
+                |export| export.value(),
+            );
+            let name = format!("{}{}", prefix, name);
+            // Spanned on the field, so that a type without `Display` is reported there.
+            if schema::is_option(&field.ty) {
+                quote_spanned! {field.ty.span()=>
+                    if let Some(value) = &self.#member {
+                        vars.push((#name.to_owned(), std::string::ToString::to_string(value)));
+                    }
+                }
+            } else {
+                quote_spanned! {field.ty.span()=>
+                    vars.push((#name.to_owned(), std::string::ToString::to_string(&self.#member)));
+                }
+            }
+        });
+    let audit = container.audit.as_ref().map(|_| {
+        let wrapper = audit::wrapper(ident);
+        quote! {
+            #wrapper::audit(None, std::panic::Location::caller());
+        }
+    });
+    let track_caller = container.audit.as_ref().map(|_| quote!(#[track_caller]));
+
+    quote! {
+        impl #ty {
+            /// The fields as environment variables, to pass the secret to a child process such as a migration or a sidecar.
+            /// Fields left empty by `None` are omitted.
+            #track_caller
+            pub fn as_env_vars(&self) -> Vec<(String, String)> {
+                #audit
+                let mut vars = Vec::new();
+                #(#vars)*
+                vars
+            }
+        }
+    }
+}
+
+/// `with_env()`, applying the variables of `#[gsm(env = "...")]` and `#[gsm(env_prefix = "...")]` to the payload before it is deserialized.
+///
+/// A variable is inserted as a string when the field accepts one, and as JSON otherwise, so that `PORT=5432` sets a number.
+pub fn overrides(
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    field_attrs: &[FieldAttrs],
+    ty: &TokenStream,
+    container: &ContainerAttrs,
+) -> Result<TokenStream> {
+    if let (None, Some(fallback)) = (&container.env_prefix, &container.fallback) {
+        return Err(Error::new_spanned(
+            fallback,
+            "`fallback` needs the prefix of `env_prefix = \"...\"`",
+        ));
+    }
+    let schema = schema::schema(input, fields)?;
+    let keys = schema.keys;
+    let mut applied = Vec::new();
+    // The variables replacing every key, which make the secret unneeded once all are set.
+    let mut replacing = Vec::new();
+    for (field, attrs) in fields.named.iter().zip(field_attrs) {
+        let key = keys
+            .iter()
+            .find(|key| Some(&key.field) == field.ident.as_ref());
+        // A variable of its own takes precedence over the prefix, with its own `fallback`.
+        let (var, fallback, key) = match (&attrs.env, &container.env_prefix, key) {
+            (Some(_), _, None) => {
+                return Err(Error::new_spanned(
+                    &attrs.env,
+                    "`env` can't be set on a field that serde doesn't read from the secret",
+                ))
+            }
+            (Some(var), _, Some(key)) => (var.value(), attrs.fallback.is_some(), key),
+            (None, Some(prefix), Some(key)) => {
+                let name = field.ident.as_ref().unwrap().unraw().to_string();
+                let var = format!("{}{}", prefix.value(), name.to_ascii_uppercase());
+                (var, container.fallback.is_some(), key)
+            }
+            (None, _, _) => continue,
+        };
+        if !fallback {
+            replacing.push(var.clone());
+        }
+        let names = &key.names;
+        let accepts_string = match &key.ty {
+            Some(ty) => schema::accepts(ty, quote!(&string.to_string())),
+            None => quote!(true),
+        };
+        applied.push(quote! {
+            if let Ok(text) = std::env::var(#var) {
+                let names: &[&str] = &[#(#names),*];
+                let present = names.iter().any(|name| matches!(object.get(*name), Some(value) if !value.is_null()));
+                if !#fallback || !present {
+                    let string = global_secrets_manager_runtime::serde_json::Value::String(text.clone());
+                    let value = if #accepts_string {
+                        string
+                    } else {
+                        global_secrets_manager_runtime::serde_json::from_str(&text).unwrap_or(string)
+                    };
+                    for name in names {
+                        object.remove(*name);
+                    }
+                    object.insert(names[0].to_owned(), value);
+                }
+            }
+        });
+    }
+    let get_secret = match &container.env_prefix {
+        // A flattened field reads keys that no variable replaces.
+        Some(_) if !schema.open && replacing.len() == keys.len() => quote! {
+            if [#(#replacing),*].iter().all(|var| std::env::var_os(var).is_some()) {
+                return Ok("{}".to_owned());
+            }
+        },
+        _ => TokenStream::new(),
+    };
+    let get_secret = match &container.env_prefix {
+        Some(_) => quote! {
+            global_secrets_manager_runtime::__sdk! {
+                impl #ty {
+                    /// Fetches the secret, unless the variables of `env_prefix` replace every key, and reads it as empty when it doesn't exist,
+                    /// so that a deployment configured by the variables alone needs no secret.
+                    async fn get_secret_or_env(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, secret_id: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                        #get_secret
+                        match Self::get_secret(client, secret_id).await {
+                            Err(err) if matches!(global_secrets_manager_runtime::GsmError::find(&*err), Some(global_secrets_manager_runtime::GsmError::NotFound { .. })) => Ok("{}".to_owned()),
+                            read => read,
+                        }
+                    }
+                }
+            }
+        },
+        None => quote! {
+            global_secrets_manager_runtime::__sdk! {
+                impl #ty {
+                    async fn get_secret_or_env(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, secret_id: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                        Self::get_secret(client, secret_id).await
+                    }
+                }
+            }
+        },
+    };
+    if applied.is_empty() {
+        return Ok(quote! {
+            impl #ty {
+                fn with_env(rt_str: String) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                    Ok(rt_str)
+                }
+            }
+
+            #get_secret
+        });
+    }
+    Ok(quote! {
+        #get_secret
+
+        impl #ty {
+            /// Replaces the keys of the secret with the variables of `#[gsm(env = "...")]` and `#[gsm(env_prefix = "...")]` that are set, or only fills them in with `#[gsm(fallback)]`.
+            /// Payloads that aren't JSON objects are left to serde.
+            fn with_env(rt_str: String) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                let mut payload: global_secrets_manager_runtime::serde_json::Value = match global_secrets_manager_runtime::serde_json::from_str(&rt_str) {
+                    Ok(payload) => payload,
+                    Err(_) => return Ok(rt_str),
+                };
+                let object = match payload.as_object_mut() {
+                    Some(object) => object,
+                    None => return Ok(rt_str),
+                };
+                #(#applied)*
+                Ok(global_secrets_manager_runtime::serde_json::to_string(&payload)?)
+            }
+        }
+    })
+}
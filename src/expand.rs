@@ -0,0 +1,1530 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse_quote, AttributeArgs, Data, DataEnum, DataStruct, DataUnion, DeriveInput, Error, Fields,
+    FieldsNamed, GenericParam, Generics, Ident, LitStr, Meta, NestedMeta, Path, Result, Type,
+};
+
+use crate::attr::{self, ContainerAttrs, FieldAttrs, Retry, VariantAttrs};
+use crate::audit;
+use crate::builder;
+use crate::common;
+use crate::deref;
+use crate::env;
+use crate::instance;
+use crate::integrations::{self, Target};
+use crate::kind;
+use crate::lazy;
+use crate::manage;
+use crate::merge;
+use crate::provider;
+use crate::registry;
+use crate::render;
+use crate::replica;
+use crate::schema;
+use crate::tenant;
+use crate::validate;
+
+/// Expansion of `#[derive(GlobalSecretsManager)]`.
+pub fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    let container = ContainerAttrs::from_attrs(&input.attrs)?;
+    expand(input, &container)
+}
+
+/// Expansion of `#[global_secret(...)]`.
+pub fn attribute(args: AttributeArgs, mut input: DeriveInput) -> Result<TokenStream> {
+    // The arguments are kept as a `#[gsm(...)]` of the declaration, so that the parts of the expansion reading its attributes, such as the schema, see them too.
+    if !args.is_empty() {
+        input.attrs.push(parse_quote!(#[gsm(#(#args),*)]));
+    }
+    let container = ContainerAttrs::from_attrs(&input.attrs)?;
+    declare(container, input)
+}
+
+/// Re-emits a declaration with the serde derive it needs and without our helper attributes, followed by its expansion.
+pub fn declare(container: ContainerAttrs, mut input: DeriveInput) -> Result<TokenStream> {
+    let expanded = expand(&input, &container)?;
+
+    let deserialized = matches!(
+        input.data,
+        Data::Struct(DataStruct {
+            fields: Fields::Named(_),
+            ..
+        })
+    );
+    if deserialized && !derives(&input, "Deserialize") {
+        input
+            .attrs
+            .push(parse_quote!(#[derive(global_secrets_manager_runtime::serde::Deserialize)]));
+        // The derive finds serde through the runtime crate, so that the application doesn't need it.
+        input
+            .attrs
+            .push(parse_quote!(#[serde(crate = "global_secrets_manager_runtime::serde")]));
+    }
+    input.attrs.retain(|a| !attr::is_gsm(a));
+    match &mut input.data {
+        Data::Struct(data) => {
+            for field in &mut data.fields {
+                field.attrs.retain(|a| !attr::is_gsm(a));
+            }
+        }
+        Data::Enum(data) => {
+            for variant in &mut data.variants {
+                variant.attrs.retain(|a| !attr::is_gsm(a));
+            }
+        }
+        Data::Union(_) => {}
+    }
+
+    Ok(quote! {
+        #input
+        #expanded
+    })
+}
+
+/// Whether the input already derives a trait whose path ends with `name`.
+fn derives(input: &DeriveInput, name: &str) -> bool {
+    input
+        .attrs
+        .iter()
+        .filter(|a| a.path.is_ident("derive"))
+        .filter_map(|a| match a.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .any(|nested| match nested {
+            NestedMeta::Meta(meta) => meta.path().segments.last().is_some_and(|s| s.ident == name),
+            NestedMeta::Lit(_) => false,
+        })
+}
+
+fn expand(input: &DeriveInput, container: &ContainerAttrs) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let ty = concrete_type(input)?;
+    let secret_name = container
+        .name
+        .as_ref()
+        .map_or_else(|| ident.to_string(), |name| name.value());
+
+    let named = matches!(
+        input.data,
+        Data::Struct(DataStruct {
+            fields: Fields::Named(_),
+            ..
+        })
+    );
+    let struct_only = [
+        ("tags", container.tags.is_some()),
+        ("audit", container.audit.is_some()),
+        ("export", container.export.is_some()),
+        ("env_prefix", container.env_prefix.is_some()),
+        ("fallback", container.fallback.is_some()),
+        ("validate", container.validate.is_some()),
+        ("pointer", container.pointer.is_some()),
+        ("extends", !container.extends.is_empty()),
+        ("merge", container.merge.is_some()),
+        ("env", !container.environments.is_empty()),
+        ("redact_keys", container.redact_keys.is_some()),
+        ("strict", container.strict.is_some()),
+        ("allow_extra", !container.allow_extra.is_empty()),
+        ("test_default", container.test_default.is_some()),
+        ("tenant", container.tenant.is_some()),
+        ("instances", container.instances.is_some()),
+        (
+            container
+                .backend
+                .as_ref()
+                .map_or("", |backend| backend.option()),
+            container.backend.is_some(),
+        ),
+    ];
+    if let Some((option, _)) = struct_only.iter().find(|(_, set)| *set && !named) {
+        return Err(Error::new_spanned(
+            ident,
+            format!(
+                "`{}` is only supported on structs with named fields",
+                option
+            ),
+        ));
+    }
+
+    if let (Some(_), Some(environment)) = (&container.tags, container.environments.first()) {
+        return Err(Error::new_spanned(
+            &environment.value,
+            "the secret of an environment is named by `name`, and can't be looked up by `tags`",
+        ));
+    }
+
+    if let (Some(_), true) = (&container.audit, container.rwlock()) {
+        return Err(Error::new_spanned(
+            &container.storage,
+            "`audit` reports the dereferences of the immutable constant, and can't be combined with `storage = \"rwlock\"`",
+        ));
+    }
+
+    if let Some(no_static) = &container.no_static {
+        // These options are about the global constant, which isn't declared.
+        let global_only = [
+            ("storage", container.storage.is_some()),
+            ("audit", container.audit.is_some()),
+            ("max_hold", container.max_hold.is_some()),
+            ("on_hold", container.on_hold.is_some()),
+        ];
+        if let Some((option, _)) = global_only.iter().find(|(_, set)| *set) {
+            return Err(Error::new_spanned(
+                no_static,
+                format!(
+                    "`{}` applies to the global constant, which `no_static` doesn't declare",
+                    option
+                ),
+            ));
+        }
+        if schema::borrows(input) {
+            return Err(Error::new_spanned(
+                no_static,
+                "`no_static` fetches the secret at each call, so the fields can't borrow from the payload; use owned types such as `String`",
+            ));
+        }
+    }
+
+    if let (Some(hook), true) = (&container.on_region_switch, container.replicas.is_empty()) {
+        return Err(Error::new_spanned(
+            hook,
+            "`on_region_switch` needs the regions of `replicas(...)`",
+        ));
+    }
+
+    if let Some(backend) = &container.backend {
+        // These options are about the secret in Secrets Manager, which the backend replaces.
+        let secrets_manager_only = [
+            ("tags", container.tags.is_some()),
+            ("extends", !container.extends.is_empty()),
+            ("env", !container.environments.is_empty()),
+            ("role_chain", !container.role_chain.is_empty()),
+            ("replicas", !container.replicas.is_empty()),
+            ("writable", container.writable.is_some()),
+            ("max_age", container.max_age.is_some()),
+            ("tenant", container.tenant.is_some()),
+            ("instances", container.instances.is_some()),
+        ];
+        if let Some((option, _)) = secrets_manager_only.iter().find(|(_, set)| *set) {
+            return Err(Error::new_spanned(
+                backend.path(),
+                format!(
+                    "`{}` applies to secrets of Secrets Manager, and can't be combined with `{}`",
+                    option,
+                    backend.option()
+                ),
+            ));
+        }
+    }
+
+    if let Some(tenant) = &container.tenant {
+        // The secrets of the tenants are named by `tenant`, in place of the ones these options look up.
+        let named = [
+            ("tags", container.tags.is_some()),
+            ("env", !container.environments.is_empty()),
+        ];
+        if let Some((option, _)) = named.iter().find(|(_, set)| *set) {
+            return Err(Error::new_spanned(
+                &tenant.path,
+                format!(
+                    "`tenant` names the secrets of the tenants, and can't be combined with `{}`",
+                    option
+                ),
+            ));
+        }
+    }
+
+    if let Some(instances) = &container.instances {
+        let placeholder = format!("{{{}}}", instances.value());
+        if !secret_name.contains(&placeholder) {
+            return Err(Error::new_spanned(
+                instances,
+                format!(
+                    "expected `{}` in the `name` of the secret, where the instance goes",
+                    placeholder
+                ),
+            ));
+        }
+        // The secrets of the instances are named by `instances`, in place of the ones these options look up.
+        let named = [
+            ("tags", container.tags.is_some()),
+            ("env", !container.environments.is_empty()),
+            ("tenant", container.tenant.is_some()),
+        ];
+        if let Some((option, _)) = named.iter().find(|(_, set)| *set) {
+            return Err(Error::new_spanned(
+                instances,
+                format!(
+                    "`instances` names the secrets of the instances, and can't be combined with `{}`",
+                    option
+                ),
+            ));
+        }
+    }
+
+    match (&container.backend, &container.bucket, &container.key) {
+        (Some(attr::Backend::S3(_)), Some(_), Some(_)) => {}
+        (Some(attr::Backend::S3(path)), _, _) => {
+            return Err(Error::new_spanned(
+                path,
+                "`backend = \"s3\"` needs the `bucket` and the `key` of the object",
+            ))
+        }
+        (_, Some(lit), _) | (_, _, Some(lit)) => {
+            return Err(Error::new_spanned(
+                lit,
+                "`bucket` and `key` name the object of `backend = \"s3\"`",
+            ))
+        }
+        _ => {}
+    }
+
+    match (&container.backend, &container.path) {
+        (Some(attr::Backend::Akeyless(_)), Some(_)) => {}
+        (Some(attr::Backend::Akeyless(path)), None) => {
+            return Err(Error::new_spanned(
+                path,
+                "`backend = \"akeyless\"` needs the `path` of the static secret",
+            ))
+        }
+        (_, Some(lit)) => {
+            return Err(Error::new_spanned(
+                lit,
+                "`path` names the static secret of `backend = \"akeyless\"`",
+            ))
+        }
+        _ => {}
+    }
+
+    if let (Some(kms_key_id), None) = (&container.kms_key_id, &container.writable) {
+        return Err(Error::new_spanned(
+            kms_key_id,
+            "`kms_key_id` only applies to the writes of `writable` types",
+        ));
+    }
+
+    match &input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => expand_struct(input, fields, &ty, &secret_name, container),
+        Data::Struct(DataStruct {
+            fields: Fields::Unit,
+            ..
+        }) => match &container.storage {
+            Some(storage) => Err(Error::new_spanned(
+                storage,
+                "a unit struct has no global constant to store",
+            )),
+            None => Ok(expand_unit(&ty, &secret_name, container)),
+        },
+        Data::Struct(DataStruct {
+            fields: Fields::Unnamed(fields),
+            ..
+        }) => Err(Error::new_spanned(
+            fields,
+            "global-secrets-manager needs named fields to match the keys of the secret",
+        )),
+        Data::Enum(data) => expand_enum(input, ident, &ty, data, container),
+        Data::Union(DataUnion { union_token, .. }) => Err(Error::new_spanned(
+            union_token,
+            "global-secrets-manager can only be applied to structs and enums",
+        )),
+    }
+}
+
+/// Rejects the options of a `writable` type that make its value differ from the one stored, since `save()` would write it over that one.
+fn writable(writable: &Path, container: &ContainerAttrs, field_attrs: &[FieldAttrs]) -> Result<()> {
+    // These options rewrite the payload before it is read: they move keys, copy them from other secrets or from the environment, or reshape them.
+    let field = |set: fn(&FieldAttrs) -> bool| field_attrs.iter().any(set);
+    let rewriting = [
+        (
+            "pointer",
+            container.pointer.is_some() || field(|attrs| attrs.pointer.is_some()),
+        ),
+        ("extends", !container.extends.is_empty()),
+        ("common", field(|attrs| attrs.common.is_some())),
+        ("deref_secret", field(|attrs| attrs.deref_secret.is_some())),
+        ("env", field(|attrs| attrs.env.is_some())),
+        ("env_prefix", container.env_prefix.is_some()),
+        ("alias", field(|attrs| !attrs.aliases.is_empty())),
+        ("split", field(|attrs| attrs.split.is_some())),
+    ];
+    if let Some((option, _)) = rewriting.iter().find(|(_, set)| *set) {
+        return Err(Error::new_spanned(
+            writable,
+            format!(
+                "`{}` rewrites the payload before it is read, and can't be combined with `writable`: `save()` would write the rewritten value over the secret",
+                option
+            ),
+        ));
+    }
+    for attrs in field_attrs {
+        // Decrypted at its first access, the field keeps the ciphertext.
+        if let (Some(kms_decrypt), None) = (&attrs.kms_decrypt, &attrs.lazy_decode) {
+            return Err(Error::new_spanned(
+                kms_decrypt,
+                "`kms_decrypt` replaces the ciphertext by its plaintext, which `save()` of a `writable` type would write back unencrypted; add `lazy_decode` to keep the ciphertext",
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn expand_struct(
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    ty: &TokenStream,
+    secret_name: &str,
+    container: &ContainerAttrs,
+) -> Result<TokenStream> {
+    let ident = &input.ident;
+    registry::register(ident, secret_name, container)?;
+    let field_attrs = fields
+        .named
+        .iter()
+        .map(FieldAttrs::from_field)
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(path) = &container.writable {
+        writable(path, container, &field_attrs)?;
+    }
+    let global = global(
+        ident,
+        ty,
+        container,
+        {
+            let deserialize = schema::deserialize(input, secret_name, quote!(&Self::placeholder()));
+            quote!(#deserialize.map_err(Into::into))
+        },
+        integrations::backend(input, fields, container)?,
+    );
+    let audit = container
+        .audit
+        .as_ref()
+        .map(|hook| audit::expand(ident, secret_name, hook));
+    let provider = provider::expand(input, fields, container);
+    let kind = kind::expand(input, fields, ty, container)?;
+    let secret_id = secret_id(secret_name, container);
+    let environment = environment(secret_name, container);
+    let manage = manage::expand(input, ty, secret_name, container);
+    let env = env::expand(ident, fields, &field_attrs, ty, container);
+    let render = render::expand(ident, fields, ty, container);
+    let lazy = lazy::expand(fields, &field_attrs, ty, secret_name, container)?;
+    let overrides = env::overrides(input, fields, &field_attrs, ty, container)?;
+    let merge = merge::expand(input, fields, &field_attrs, ty, secret_name, container)?;
+    let common = common::expand(input, fields, &field_attrs, ty, secret_name)?;
+    let deref = deref::expand(input, fields, &field_attrs, ty, secret_name, container)?;
+    let validate = validate::expand(fields, &field_attrs, ty, secret_name, container);
+    let schema = schema::expand(input, fields, ty, secret_name, container)?;
+    let builder = builder::expand(input, fields, ty, secret_name, container)?;
+    #[cfg(feature = "json-schema")]
+    let json_schema = crate::json_schema::expand(input, fields, ty, secret_name, container)?;
+    #[cfg(not(feature = "json-schema"))]
+    let json_schema = TokenStream::new();
+    #[cfg(feature = "compile-time-check")]
+    // The keys of the `common` fields come from other secrets, the payloads of backends from other services,
+    // and the names of the instances from the program.
+    if container.tags.is_none()
+        && container.backend.is_none()
+        && container.instances.is_none()
+        && field_attrs.iter().all(|attrs| attrs.common.is_none())
+    {
+        crate::check::secret(input, fields, secret_name)?;
+    }
+    let target = Target {
+        input,
+        fields,
+        field_attrs: &field_attrs,
+        ty,
+        secret_name,
+        container,
+    };
+    let payload = integrations::payload(&target)?;
+    let deserialize = schema::deserialize(input, secret_name, quote!(&rt_str));
+    let integrations = integrations::expand(&target);
+    let conventional_name = conventional_name();
+    let http = http(input, ident, ty, secret_name, &field_attrs, container);
+    let tenant = tenant::expand(input, ty, container)?;
+    let instance = instance::expand(ty, container);
+    let instance_name = instance::name(secret_name, container);
+    Ok(quote! {
+        global_secrets_manager_runtime::__sdk! {
+            #global
+            #audit
+
+            impl #ty {
+                /// Fetches the secret with a client managed by the application, such as one with interceptors or a LocalStack endpoint.
+                pub async fn get_with_client(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+                    Ok(Self::read_with_client(client).await?.0)
+                }
+
+                /// The id of the secret and the payload it is read from, embedded by the structs holding the type in a `#[gsm(common)]` field.
+                #[doc(hidden)]
+                pub async fn common_payload(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<(String, String), Box<dyn std::error::Error + Send + Sync>> {
+                    let secret_id = Self::secret_id(client).await?;
+                    let (_, payload) = Self::read_secret(client, &secret_id).await?;
+                    Self::check_staleness(client).await;
+                    Ok((secret_id, payload))
+                }
+
+                /// Fetches and reads the secret, along with the payload it is read from.
+                async fn read_with_client(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<(Self, String), Box<dyn std::error::Error + Send + Sync>> {
+                    let read = Self::read_secret(client, &Self::secret_id(client).await?).await?;
+                    Self::check_staleness(client).await;
+                    Ok(read)
+                }
+
+                /// Fetches and reads the secret `secret_id`, along with the payload it is read from.
+                async fn read_secret(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, secret_id: &str) -> Result<(Self, String), Box<dyn std::error::Error + Send + Sync>> {
+                    let rt_str = Self::get_secret_or_env(client, secret_id).await?;
+                    let rt_str = Self::with_bases(client, rt_str).await?;
+                    let rt_str = Self::with_common(client, rt_str).await?;
+                    let rt_str = Self::with_pointers(rt_str)?;
+                    let rt_str = Self::with_aliases(rt_str)?;
+                    let rt_str = Self::with_deref(client, rt_str).await?;
+                    #payload
+                    let rt_str = Self::with_env(rt_str)?;
+                    let rt_str = Self::with_splits(rt_str)?;
+                    Self::check_keys(&rt_str)?;
+                    let secrets: Self = #deserialize
+                        .map_err(|err| Self::field_error(&rt_str).map_or(err, |reason| global_secrets_manager_runtime::GsmError::decode(#secret_name, reason)))?;
+                    secrets.validate_secret()?;
+                    Ok((secrets, rt_str))
+                }
+
+                #secret_id
+            }
+
+            #provider
+            #manage
+            #merge
+            #common
+            #deref
+            #tenant
+            #instance
+            #integrations
+        }
+
+        impl #ty {
+            #conventional_name
+            #environment
+            #instance_name
+        }
+
+        #http
+        #kind
+        #env
+        #overrides
+        #validate
+        #render
+        #lazy
+        #schema
+        #json_schema
+        #builder
+    })
+}
+
+/// `get_with_http()`, fetching the secret with the `http` module of the runtime crate where the SDK doesn't run, such as on WASI and edge runtimes,
+/// and with its `blocking` feature, `get_blocking()` and, without the SDK, the global constant it loads.
+///
+/// The secrets looked up by `tags`, merged with `extends`, embedding `common` ones, following `deref_secret` ones, decrypted by `kms_decrypt` or read from a backend need the SDK,
+/// so their types don't get them.
+fn http(
+    input: &DeriveInput,
+    ident: &Ident,
+    ty: &TokenStream,
+    secret_name: &str,
+    field_attrs: &[FieldAttrs],
+    container: &ContainerAttrs,
+) -> TokenStream {
+    let needs_sdk = container.tags.is_some()
+        || container.backend.is_some()
+        || !container.extends.is_empty()
+        || field_attrs.iter().any(|attrs| {
+            attrs.kms_decrypt.is_some() || attrs.common.is_some() || attrs.deref_secret.is_some()
+        });
+    if needs_sdk {
+        return TokenStream::new();
+    }
+    let deserialize = schema::deserialize(input, secret_name, quote!(&rt_str));
+    let name = if !container.environments.is_empty() {
+        quote!(Self::environment()?.0)
+    } else if container.instances.is_some() {
+        quote!(&Self::instance_name(None)?)
+    } else {
+        quote!(#secret_name)
+    };
+    let (stored, init) = if container.rwlock() {
+        (
+            quote!(std::sync::RwLock<#ty>),
+            quote!(std::sync::RwLock::new(<#ty>::get_blocking().unwrap())),
+        )
+    } else {
+        (ty.clone(), quote!(<#ty>::get_blocking().unwrap()))
+    };
+    let without_sdk = container.no_static.is_none().then(|| {
+        quote! {
+            global_secrets_manager_runtime::__without_sdk! {
+                pub static #ident: global_secrets_manager_runtime::once_cell::sync::Lazy<#stored> = global_secrets_manager_runtime::once_cell::sync::Lazy::new(|| #init);
+            }
+        }
+    });
+    quote! {
+        global_secrets_manager_runtime::__http! {
+            impl #ty {
+                /// Fetches the secret over HTTP with the credentials, the region and the endpoint of `client`, sending the request signed at `now` with `send`,
+                /// where the SDK doesn't run, such as on WASI and edge runtimes.
+                pub async fn get_with_http<F, Fut>(client: &global_secrets_manager_runtime::http::Client, now: std::time::SystemTime, send: F) -> Result<Self, Box<dyn std::error::Error + Send + Sync>>
+                where
+                    F: FnOnce(global_secrets_manager_runtime::http::Request) -> Fut,
+                    Fut: std::future::Future<Output = Result<global_secrets_manager_runtime::http::Response, Box<dyn std::error::Error + Send + Sync>>>,
+                {
+                    let rt_str = client.fetch(&Self::conventional_name(#name)?, now, send).await?;
+                    Self::from_http(rt_str)
+                }
+
+                /// Reads the payload fetched over HTTP as `get_with_client()` reads the one fetched by the SDK.
+                fn from_http(rt_str: String) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+                    let rt_str = Self::with_pointers(rt_str)?;
+                    let rt_str = Self::with_aliases(rt_str)?;
+                    let rt_str = Self::with_env(rt_str)?;
+                    let rt_str = Self::with_splits(rt_str)?;
+                    Self::check_keys(&rt_str)?;
+                    let secrets: Self = #deserialize
+                        .map_err(|err| Self::field_error(&rt_str).map_or(err, |reason| global_secrets_manager_runtime::GsmError::decode(#secret_name, reason)))?;
+                    secrets.validate_secret()?;
+                    Ok(secrets)
+                }
+            }
+        }
+
+        global_secrets_manager_runtime::__blocking! {
+            impl #ty {
+                /// Fetches the secret with a blocking request, with the credentials, the region and the endpoint of the environment,
+                /// for programs that don't otherwise use async.
+                pub fn get_blocking() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+                    global_secrets_manager_runtime::dotenvy::dotenv().ok();
+                    let client = global_secrets_manager_runtime::http::Client::from_env()?;
+                    let rt_str = client.fetch_blocking(&Self::conventional_name(#name)?)?;
+                    Self::from_http(rt_str)
+                }
+            }
+
+            #without_sdk
+        }
+    }
+}
+
+/// The id of the secret: its name, or the ARN of the only secret with the tags of `#[gsm(tags = "...")]`.
+fn secret_id(secret_name: &str, container: &ContainerAttrs) -> TokenStream {
+    let tags = match &container.tags {
+        Some(tags) => tags,
+        None if !container.environments.is_empty() => {
+            return quote! {
+                async fn secret_id(_client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                    Self::conventional_name(Self::environment()?.0)
+                }
+            }
+        }
+        None if container.instances.is_some() => {
+            return quote! {
+                async fn secret_id(_client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                    Self::conventional_name(&Self::instance_name(None)?)
+                }
+            }
+        }
+        None => {
+            return quote! {
+                async fn secret_id(_client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                    Self::conventional_name(#secret_name)
+                }
+            }
+        }
+    };
+    let (keys, values): (Vec<_>, Vec<_>) = tags.iter().map(|(key, value)| (key, value)).unzip();
+    let description = tags
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(", ");
+    quote! {
+        /// Looks the secret up by its tags, as the list of secrets can only be filtered by tag keys and values separately.
+        async fn secret_id(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+            const TAGS: &[(&str, &str)] = &[#((#keys, #values)),*];
+            let mut request = client.list_secrets();
+            for (key, value) in TAGS {
+                request = request
+                    .filters(global_secrets_manager_runtime::aws_sdk_secretsmanager::types::Filter::builder().key(global_secrets_manager_runtime::aws_sdk_secretsmanager::types::FilterNameStringType::TagKey).values(*key).build())
+                    .filters(global_secrets_manager_runtime::aws_sdk_secretsmanager::types::Filter::builder().key(global_secrets_manager_runtime::aws_sdk_secretsmanager::types::FilterNameStringType::TagValue).values(*value).build());
+            }
+            let mut found = Vec::new();
+            let mut next_token = None;
+            loop {
+                let resp = request.clone().set_next_token(next_token).send().await?;
+                for entry in resp.secret_list() {
+                    let tagged = |(key, value): &(&str, &str)| {
+                        entry.tags().iter().any(|tag| tag.key() == Some(*key) && tag.value() == Some(*value))
+                    };
+                    if TAGS.iter().all(tagged) {
+                        found.extend(entry.arn().map(str::to_owned));
+                    }
+                }
+                next_token = resp.next_token().map(str::to_owned);
+                if next_token.is_none() {
+                    break;
+                }
+            }
+            match found.len() {
+                1 => Ok(found.remove(0)),
+                0 => Err(format!("no secret is tagged {}", #description).into()),
+                n => Err(format!("{} secrets are tagged {}", n, #description).into()),
+            }
+        }
+    }
+}
+
+/// `environment()` when `#[gsm(env("...", ...))]` is set, returning the name of the secret, the endpoint and the region of the environment selected by `env_var`.
+fn environment(secret_name: &str, container: &ContainerAttrs) -> TokenStream {
+    if container.environments.is_empty() {
+        return TokenStream::new();
+    }
+    let env_var = env_var(container);
+    let expected = container
+        .environments
+        .iter()
+        .map(|environment| environment.value.value())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let arms = container.environments.iter().map(|environment| {
+        let value = &environment.value;
+        let name = environment
+            .name
+            .as_ref()
+            .map_or_else(|| secret_name.to_owned(), LitStr::value);
+        let endpoint_url = option(&environment.endpoint_url);
+        let region = option(&environment.region);
+        quote! {
+            if environment.eq_ignore_ascii_case(#value) {
+                return Ok((#name, #endpoint_url, #region));
+            }
+        }
+    });
+    quote! {
+        /// The name of the secret, the endpoint and the region of the environment selected by `#env_var`.
+        fn environment() -> Result<(&'static str, Option<&'static str>, Option<&'static str>), Box<dyn std::error::Error + Send + Sync>> {
+            let environment = std::env::var(#env_var)
+                .map_err(|_| format!("`{}` must be set to one of: {}", #env_var, #expected))?;
+            #(#arms)*
+            Err(format!("`{}` is `{}`, expected one of: {}", #env_var, environment, #expected).into())
+        }
+    }
+}
+
+/// Statements loading the `shared_config` of the global constant: the name of application of `app_name`, the endpoint and the region of the selected environment,
+/// then the roles of `#[gsm(role_chain(...))]`, each assumed with the credentials of the previous one.
+pub fn shared_config(container: &ContainerAttrs) -> TokenStream {
+    let from_env = match &container.app_name {
+        Some(app_name) => {
+            quote!(global_secrets_manager_runtime::config_loader().app_name(global_secrets_manager_runtime::aws_config::AppName::new(#app_name)?))
+        }
+        None => quote!(global_secrets_manager_runtime::config_loader()),
+    };
+    let loader = if container.environments.is_empty() {
+        quote!(let loader = #from_env;)
+    } else {
+        quote! {
+            let mut loader = #from_env;
+            let (_, endpoint_url, region) = Self::environment()?;
+            if let Some(endpoint_url) = endpoint_url {
+                loader = loader.endpoint_url(endpoint_url);
+            }
+            if let Some(region) = region {
+                loader = loader.region(global_secrets_manager_runtime::aws_config::Region::new(region));
+            }
+        }
+    };
+    if container.role_chain.is_empty() {
+        return quote! {
+            #loader
+            let shared_config = loader.load().await;
+        };
+    }
+    let roles = &container.role_chain;
+    quote! {
+        #loader
+        let mut shared_config = loader.load().await;
+        for role_arn in [#(#roles),*] {
+            shared_config = global_secrets_manager_runtime::assume_role(&shared_config, role_arn).await;
+        }
+    }
+}
+
+/// The variable selecting the environment, `APP_ENV` unless set by `#[gsm(env_var = "...")]`.
+fn env_var(container: &ContainerAttrs) -> String {
+    container
+        .env_var
+        .as_ref()
+        .map_or_else(|| "APP_ENV".to_owned(), LitStr::value)
+}
+
+fn option(lit: &Option<LitStr>) -> TokenStream {
+    match lit {
+        Some(lit) => quote!(Some(#lit)),
+        None => quote!(None),
+    }
+}
+
+/// The attribute and the statement reporting an access to the audited constant from the caller's location, or nothing.
+fn audit_access(ident: &Ident, container: &ContainerAttrs) -> (TokenStream, TokenStream) {
+    match &container.audit {
+        Some(_) => {
+            let wrapper = audit::wrapper(ident);
+            (
+                quote!(#[track_caller]),
+                quote!(#wrapper::audit(None, std::panic::Location::caller());),
+            )
+        }
+        None => (TokenStream::new(), TokenStream::new()),
+    }
+}
+
+/// The accessors of the global constant at `lazy_ref`, reading it without loading it or by value,
+/// and the guards of `read()` and `write()` when it is stored in a lock by `#[gsm(storage = "rwlock")]`.
+fn accessors(
+    ident: &Ident,
+    ty: &TokenStream,
+    container: &ContainerAttrs,
+    lazy_ref: &TokenStream,
+) -> TokenStream {
+    let (track_caller, audit) = audit_access(ident, container);
+    let cache_hit = integrations::cache_hit(ident, ty);
+    let hit = integrations::read_hit(ident, ty, lazy_ref);
+    let with_secret = with_secret(
+        ident,
+        container,
+        &audit,
+        &if container.rwlock() {
+            quote!(Self::read())
+        } else {
+            quote!({
+                #hit
+                global_secrets_manager_runtime::once_cell::sync::Lazy::force(#lazy_ref)
+            })
+        },
+    );
+    let is_initialized = quote! {
+        /// Whether the global constant is loaded, without loading it, for health endpoints and shutdown paths.
+        pub fn is_initialized() -> bool {
+            global_secrets_manager_runtime::once_cell::sync::Lazy::get(#lazy_ref).is_some()
+        }
+    };
+    if !container.rwlock() {
+        return quote! {
+            #is_initialized
+            #with_secret
+
+            /// The value of the global constant if it is loaded, without loading it: unlike dereferencing the constant, it never fetches the secret.
+            #track_caller
+            pub fn peek() -> Option<&'static Self> {
+                let secrets = global_secrets_manager_runtime::once_cell::sync::Lazy::get(#lazy_ref)?;
+                #cache_hit
+                #audit
+                Some(secrets)
+            }
+
+            /// A clone of the global constant, loading it if needed, for consumers that need the secret by value.
+            #track_caller
+            pub fn cloned() -> Self
+            where
+                for<'a> Self: Clone,
+            {
+                #hit
+                let secrets = global_secrets_manager_runtime::once_cell::sync::Lazy::force(#lazy_ref);
+                #audit
+                secrets.clone()
+            }
+
+            /// The global constant behind an `Arc`, loading it if needed, so that async tasks can hold a snapshot across awaits without borrowing the constant.
+            /// The constant is cloned once, at the first call; the next calls only clone the `Arc`.
+            #track_caller
+            pub fn shared() -> std::sync::Arc<Self>
+            where
+                for<'a> Self: Clone,
+            {
+                static SHARED: global_secrets_manager_runtime::once_cell::sync::OnceCell<std::sync::Arc<#ty>> = global_secrets_manager_runtime::once_cell::sync::OnceCell::new();
+                #hit
+                let secrets = global_secrets_manager_runtime::once_cell::sync::Lazy::force(#lazy_ref);
+                #audit
+                std::sync::Arc::clone(SHARED.get_or_init(|| std::sync::Arc::new(secrets.clone())))
+            }
+        };
+    }
+    // A panic while the lock was held can't leave the secret half-loaded, so poisoning is ignored.
+    quote! {
+        #is_initialized
+        #with_secret
+
+        /// Locks the global constant for reading, loading it if needed.
+        pub fn read() -> std::sync::RwLockReadGuard<'static, Self> {
+            #hit
+            global_secrets_manager_runtime::once_cell::sync::Lazy::force(#lazy_ref).read().unwrap_or_else(std::sync::PoisonError::into_inner)
+        }
+
+        /// Locks the global constant for writing, loading it if needed, to replace a field such as an exchanged short-lived token.
+        pub fn write() -> std::sync::RwLockWriteGuard<'static, Self> {
+            #hit
+            global_secrets_manager_runtime::once_cell::sync::Lazy::force(#lazy_ref).write().unwrap_or_else(std::sync::PoisonError::into_inner)
+        }
+
+        /// Locks the global constant for reading if it is loaded, without loading it: unlike `read()`, it never fetches the secret.
+        pub fn peek() -> Option<std::sync::RwLockReadGuard<'static, Self>> {
+            let lock = global_secrets_manager_runtime::once_cell::sync::Lazy::get(#lazy_ref)?;
+            #cache_hit
+            Some(lock.read().unwrap_or_else(std::sync::PoisonError::into_inner))
+        }
+
+        /// A clone of the current value of the global constant, loading it if needed, for consumers that need the secret by value.
+        pub fn cloned() -> Self
+        where
+            for<'a> Self: Clone,
+        {
+            Self::read().clone()
+        }
+
+        /// A snapshot of the current value of the global constant behind an `Arc`, loading it if needed, so that async tasks can hold it across awaits without holding the lock.
+        /// Each call clones the value, since it may have been written since the last one.
+        pub fn shared() -> std::sync::Arc<Self>
+        where
+            for<'a> Self: Clone,
+        {
+            std::sync::Arc::new(Self::cloned())
+        }
+    }
+}
+
+/// `with_secret()`, lending the global constant `secrets` to a closure, and timing how long it is held when `max_hold` or `on_hold` is set.
+fn with_secret(
+    ident: &Ident,
+    container: &ContainerAttrs,
+    audit: &TokenStream,
+    secrets: &TokenStream,
+) -> TokenStream {
+    let type_name = ident.to_string();
+    let warning = container.max_hold.map(|max_hold| {
+        let warning = integrations::warning(quote! {
+            "`{}` was held for {:?} at {}, more than the {:?} allowed",
+            #type_name,
+            held,
+            location,
+            max_hold
+        });
+        quote! {
+            let max_hold = std::time::Duration::from_millis(#max_hold);
+            if held > max_hold {
+                #warning;
+            }
+        }
+    });
+    let hook = container
+        .on_hold
+        .as_ref()
+        .map(|hook| quote!(#hook(#type_name, held, location);));
+    let call = if warning.is_none() && hook.is_none() {
+        quote!(f(&secrets))
+    } else {
+        quote! {
+            let location = std::panic::Location::caller();
+            let started = std::time::Instant::now();
+            let result = f(&secrets);
+            let held = started.elapsed();
+            #warning
+            #hook
+            result
+        }
+    };
+    quote! {
+        /// Lends the global constant to `f`, loading it if needed, so that the secret is only borrowed for a scope rather than stashed in long-lived copies.
+        /// How long it is held is reported to `#[gsm(on_hold = "...")]`, and beyond `#[gsm(max_hold = "...")]` as a warning.
+        #[track_caller]
+        pub fn with_secret<R>(f: impl FnOnce(&Self) -> R) -> R {
+            let secrets = #secrets;
+            #audit
+            #call
+        }
+    }
+}
+
+/// `conventional_name()`, naming a secret after the template of `GSM_NAME_TEMPLATE`, such as `{name}-{APP_ENV}`, when it is set.
+///
+/// The template is read at runtime, so that one build follows the convention of every environment it is deployed to.
+fn conventional_name() -> TokenStream {
+    quote! {
+        /// The name of the secret after the template of `GSM_NAME_TEMPLATE`, where `{name}` stands for the name and `{VAR}` for the variable `VAR`.
+        /// ARNs are left as they are.
+        fn conventional_name(name: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+            let template = match std::env::var("GSM_NAME_TEMPLATE") {
+                Ok(template) if !name.starts_with("arn:") => template,
+                _ => return Ok(name.to_owned()),
+            };
+            let mut conventional = String::with_capacity(template.len() + name.len());
+            let mut rest = template.as_str();
+            while let Some(start) = rest.find('{') {
+                conventional.push_str(&rest[..start]);
+                let end = start + rest[start..].find('}').ok_or("unmatched brace in `GSM_NAME_TEMPLATE`")?;
+                match &rest[start + 1..end] {
+                    "name" => conventional.push_str(name),
+                    var => conventional.push_str(
+                        &std::env::var(var).map_err(|_| format!("`GSM_NAME_TEMPLATE` refers to `{}`, which is not set", var))?,
+                    ),
+                }
+                rest = &rest[end + 1..];
+            }
+            conventional.push_str(rest);
+            Ok(conventional)
+        }
+    }
+}
+
+/// The global constant and the items shared by every type holding one: the preloading, the constructor from a configuration, the raw fetch, the deserialization and the initializer of the constant.
+/// Under `#[gsm(no_static)]`, `fetch()` and `load()` take the place of the constant and the items about it.
+///
+/// The constant of an audited struct is wrapped in the type generated by `audit::expand`.
+/// `dry_run` is the value loaded instead of the secret under `GSM_DRY_RUN`.
+fn global(
+    ident: &Ident,
+    ty: &TokenStream,
+    container: &ContainerAttrs,
+    dry_run: TokenStream,
+    backend: Option<TokenStream>,
+) -> TokenStream {
+    let audited = container.audit.is_some();
+    let shared_config = shared_config(container);
+    let (fetch, replicated) = replica::expand(container);
+    #[cfg(feature = "chaos")]
+    let fetch = crate::chaos::fetch(fetch);
+    let backend_name = container
+        .backend
+        .as_ref()
+        .map_or("aws-secrets-manager", |backend| backend.name());
+    let fetch = integrations::fetch(fetch, backend_name);
+    let retried = integrations::load(retried(fetch, container), backend_name);
+    let secret_value = integrations::secret_value().unwrap_or_else(|| {
+        quote!(Ok(resp.secret_string.ok_or_else(|| {
+            global_secrets_manager_runtime::GsmError::decode(secret_id, "it has no string value")
+        })?))
+    });
+    let fetch_secret = backend.unwrap_or_else(|| {
+        quote! {
+            let resp = client
+                .get_secret_value()
+                .secret_id(secret_id)
+                .send()
+                .await
+                .map_err(|err| global_secrets_manager_runtime::fetch_error(secret_id, err))?;
+            #secret_value
+        }
+    });
+    let fetching = quote! {
+        /// Fetches the secret with the credentials and region of a configuration loaded by the application.
+        pub async fn get_with_config(config: &global_secrets_manager_runtime::aws_config::SdkConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+            Self::get_with_client(&global_secrets_manager_runtime::aws_sdk_secretsmanager::Client::new(config)).await
+        }
+
+        #replicated
+
+        async fn get_secret(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, secret_id: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+            #retried
+        }
+
+        async fn fetch_secret(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, secret_id: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+            #fetch_secret
+        }
+    };
+    if container.no_static.is_some() {
+        let type_name = ident.to_string();
+        return quote! {
+            impl #ty {
+                /// Fetches the secret with the configuration of the environment, without storing it anywhere,
+                /// or returns placeholders without calling AWS when `GSM_DRY_RUN` is set.
+                pub async fn fetch() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+                    global_secrets_manager_runtime::dotenvy::dotenv().ok();
+                    if matches!(std::env::var("GSM_DRY_RUN").as_deref(), Ok("1") | Ok("true")) {
+                        return #dry_run;
+                    }
+                    #shared_config
+                    Self::get_with_config(&shared_config).await
+                }
+
+                /// Same as `fetch()`, blocking until the secret is fetched, for code that doesn't otherwise use async.
+                /// An async runtime can't be blocked, so it fails when called from one.
+                pub fn load() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+                    if global_secrets_manager_runtime::executor::in_async_context() {
+                        return Err(format!("`{}::load()` can't block an async runtime, use `fetch()` there", #type_name).into());
+                    }
+                    global_secrets_manager_runtime::executor::block_on(Self::fetch())?
+                }
+
+                #fetching
+            }
+        };
+    }
+    let rwlock = container.rwlock();
+    let (stored, lazy) = if rwlock {
+        (
+            quote!(std::sync::RwLock<#ty>),
+            quote!(global_secrets_manager_runtime::once_cell::sync::Lazy::new(|| std::sync::RwLock::new(<#ty>::init()))),
+        )
+    } else {
+        (
+            ty.clone(),
+            quote!(global_secrets_manager_runtime::once_cell::sync::Lazy::new(|| <#ty>::init())),
+        )
+    };
+    let (track_caller, audit) = audit_access(ident, container);
+    let wrapper = audit::wrapper(ident);
+    let hit = integrations::read_hit(ident, ty, &quote!(&self.0));
+    // The lock is read through `read()` and `write()`, which count the reads themselves.
+    let (declaration, lazy_ref) = if audited {
+        let declare = audit::declare(
+            ident,
+            ty,
+            true,
+            quote!(Self::audit(None, std::panic::Location::caller()); #hit),
+        );
+        (
+            quote! {
+                #declare
+                pub static #ident: #wrapper = #wrapper(#lazy);
+            },
+            quote!(&#ident.0),
+        )
+    } else if rwlock {
+        (
+            quote!(pub static #ident: global_secrets_manager_runtime::once_cell::sync::Lazy<#stored> = #lazy;),
+            quote!(&#ident),
+        )
+    } else {
+        // The dereferences are counted by a wrapper with the `metrics` feature of the runtime, which the plain constant stands in for without it.
+        let declare = audit::declare(ident, ty, false, hit);
+        (
+            quote! {
+                global_secrets_manager_runtime::__metrics! {
+                    #declare
+                    pub static #ident: #wrapper = #wrapper(#lazy);
+
+                    impl #ty {
+                        fn lazy_constant() -> &'static global_secrets_manager_runtime::once_cell::sync::Lazy<#ty> {
+                            &#ident.0
+                        }
+                    }
+                }
+
+                global_secrets_manager_runtime::__without_metrics! {
+                    pub static #ident: global_secrets_manager_runtime::once_cell::sync::Lazy<#ty> = #lazy;
+
+                    impl #ty {
+                        fn lazy_constant() -> &'static global_secrets_manager_runtime::once_cell::sync::Lazy<#ty> {
+                            &#ident
+                        }
+                    }
+                }
+            },
+            quote!(<#ty>::lazy_constant()),
+        )
+    };
+    let accessors = accessors(ident, ty, container, &lazy_ref);
+    let type_name = ident.to_string();
+    let cache_hit = integrations::cache_hit(ident, ty);
+    let refreshed = integrations::refreshed(ident, ty);
+    let global_items = integrations::global_items();
+    quote! {
+        #declaration
+
+        impl #ty {
+            /// Loads the global constant without blocking the async runtime, such as at the startup of a server.
+            /// Dereferencing the constant for the first time from async code would otherwise block a worker, or panic when it is a tokio worker.
+            pub async fn try_preload() -> Result<&'static #stored, Box<dyn std::error::Error + Send + Sync>> {
+                if let Some(secrets) = global_secrets_manager_runtime::once_cell::sync::Lazy::get(#lazy_ref) {
+                    #cache_hit
+                    return Ok(secrets);
+                }
+                Self::load_once().await?;
+                let secrets = global_secrets_manager_runtime::once_cell::sync::Lazy::force(#lazy_ref);
+                // Drops the preloaded value if the constant was initialized by someone else in the meantime.
+                Self::preloaded().lock().unwrap_or_else(std::sync::PoisonError::into_inner).take();
+                Ok(secrets)
+            }
+
+            /// The global constant, or `None` if the secret can't be loaded, so that non-critical code paths can degrade gracefully
+            /// where dereferencing the constant would take down the process.
+            #track_caller
+            pub fn try_current() -> Option<&'static #stored> {
+                Self::current().ok()
+            }
+
+            /// Same as `try_current()`, but returns the error of the last failed load.
+            ///
+            /// The secret is loaded at the first call, blocking as dereferencing the constant does, unless it is called from an async runtime,
+            /// which can't be blocked: the secret must be preloaded there. Once a load failed, its error is returned without fetching the secret again,
+            /// until a `try_preload()` succeeds.
+            #track_caller
+            pub fn current() -> Result<&'static #stored, Box<dyn std::error::Error + Send + Sync>> {
+                if let Some(secrets) = global_secrets_manager_runtime::once_cell::sync::Lazy::get(#lazy_ref) {
+                    #cache_hit
+                    #audit
+                    return Ok(secrets);
+                }
+                if let Some(err) = Self::last_error().lock().unwrap_or_else(std::sync::PoisonError::into_inner).as_ref() {
+                    return Err(err.clone().into());
+                }
+                if global_secrets_manager_runtime::executor::in_async_context() {
+                    return Err(format!("`{}` must be preloaded to be read from an async runtime", #type_name).into());
+                }
+                global_secrets_manager_runtime::executor::block_on(Self::load_once())??;
+                let secrets = global_secrets_manager_runtime::once_cell::sync::Lazy::force(#lazy_ref);
+                Self::preloaded().lock().unwrap_or_else(std::sync::PoisonError::into_inner).take();
+                #audit
+                Ok(secrets)
+            }
+
+            /// Error of the last failed load, returned by `current()` until a load succeeds.
+            fn last_error() -> &'static std::sync::Mutex<Option<String>> {
+                static LAST_ERROR: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+                &LAST_ERROR
+            }
+
+            /// Records the error of a failed load for `current()`.
+            fn failed(err: Box<dyn std::error::Error + Send + Sync>) -> Box<dyn std::error::Error + Send + Sync> {
+                *Self::last_error().lock().unwrap_or_else(std::sync::PoisonError::into_inner) = Some(err.to_string());
+                err
+            }
+
+            #accessors
+
+            /// Loads the secret by value without storing it in the global constant, for short-lived tools such as CLIs and batch jobs.
+            /// The secret is fetched at each call, blocking as dereferencing the constant does, and a value pending from a concurrent `try_preload()` is left to the constant.
+            pub fn into_inner() -> Self {
+                Self::load_or_panic()
+            }
+
+            /// Same as `try_preload()`, but panics if the secret can't be loaded, as dereferencing the constant does.
+            pub async fn preload() -> &'static #stored {
+                Self::try_preload().await.unwrap()
+            }
+
+            #fetching
+            #global_items
+
+            /// Value handed over to the constant by `try_preload()`.
+            fn preloaded() -> &'static std::sync::Mutex<Option<#ty>> {
+                static PRELOADED: std::sync::Mutex<Option<#ty>> = std::sync::Mutex::new(None);
+                &PRELOADED
+            }
+
+            fn init() -> Self {
+                if let Some(secrets) = Self::preloaded().lock().unwrap_or_else(std::sync::PoisonError::into_inner).take() {
+                    return secrets;
+                }
+                global_secrets_manager_runtime::executor::block_on(Self::load_once()).unwrap().unwrap();
+                Self::preloaded().lock().unwrap_or_else(std::sync::PoisonError::into_inner).take().expect("the secret was just loaded")
+            }
+
+            /// Loads the secret into `preloaded()`, unless the constant or `preloaded()` already holds it.
+            ///
+            /// Single flight: concurrent first reads, by `try_preload()`, `current()` or dereferencing the constant, wait for the one fetching the secret
+            /// instead of fetching it too. The lock is released before the constant is initialized with the value, which may wait for a concurrent dereference.
+            async fn load_once() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+                static LOADING: global_secrets_manager_runtime::once_cell::sync::Lazy<global_secrets_manager_runtime::executor::Mutex<()>> = global_secrets_manager_runtime::once_cell::sync::Lazy::new(|| global_secrets_manager_runtime::executor::Mutex::new(()));
+                let _loading = LOADING.lock().await;
+                let preloaded = Self::preloaded().lock().unwrap_or_else(std::sync::PoisonError::into_inner).is_some();
+                if preloaded || global_secrets_manager_runtime::once_cell::sync::Lazy::get(#lazy_ref).is_some() {
+                    return Ok(());
+                }
+                let secrets = Self::load().await.map_err(Self::failed)?;
+                Self::last_error().lock().unwrap_or_else(std::sync::PoisonError::into_inner).take();
+                *Self::preloaded().lock().unwrap_or_else(std::sync::PoisonError::into_inner) = Some(secrets);
+                #refreshed
+                Ok(())
+            }
+
+            fn load_or_panic() -> Self {
+                global_secrets_manager_runtime::executor::block_on(Self::load()).unwrap().unwrap()
+            }
+
+            /// Fetches the secret for the constant, or returns placeholders without calling AWS when `GSM_DRY_RUN` is set.
+            async fn load() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+                global_secrets_manager_runtime::dotenvy::dotenv().ok();
+                if matches!(std::env::var("GSM_DRY_RUN").as_deref(), Ok("1") | Ok("true")) {
+                    return #dry_run;
+                }
+                #shared_config
+                Self::get_with_config(&shared_config).await
+            }
+        }
+    }
+}
+
+/// The fetch `fetch`, retried with the backoff of `#[gsm(retry(...))]` while it fails with the kinds of errors listed there.
+fn retried(fetch: TokenStream, container: &ContainerAttrs) -> TokenStream {
+    if container.retry.is_empty() {
+        return fetch;
+    }
+    let kinds = container.retry.iter().map(|retry| {
+        let Retry {
+            kind,
+            retries,
+            backoff,
+        } = retry;
+        quote! {
+            #kind: Some(global_secrets_manager_runtime::Backoff {
+                retries: #retries,
+                delay: std::time::Duration::from_millis(#backoff),
+            }),
+        }
+    });
+    quote! {
+        const RETRY: global_secrets_manager_runtime::RetryPolicy = global_secrets_manager_runtime::RetryPolicy {
+            #(#kinds)*
+            ..global_secrets_manager_runtime::RetryPolicy::NONE
+        };
+        let mut failures = 0;
+        loop {
+            let err = match #fetch {
+                Ok(secret) => return Ok(secret),
+                Err(err) => err,
+            };
+            failures += 1;
+            match RETRY.delay(&*err, failures) {
+                Some(delay) => global_secrets_manager_runtime::executor::sleep(delay).await,
+                None => return Err(err),
+            }
+        }
+    }
+}
+
+/// A unit struct only stands for the secret, so it gets existence checks instead of a global constant.
+/// The constant couldn't be declared anyway: a unit struct already occupies its name in the value namespace.
+fn expand_unit(ty: &TokenStream, secret_name: &str, container: &ContainerAttrs) -> TokenStream {
+    let conventional_name = conventional_name();
+    let shared_config = shared_config(container);
+    let write_to = manage::write_to(quote!(Self::conventional_name(#secret_name)?));
+    quote! {
+        global_secrets_manager_runtime::__sdk! {
+            impl #ty {
+                #conventional_name
+                #write_to
+
+                /// Whether the secret exists and can be read. Errors other than a missing secret, such as denied access, are returned as is.
+                pub async fn exists() -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+                    #shared_config
+                    let client = global_secrets_manager_runtime::aws_sdk_secretsmanager::Client::new(&shared_config);
+                    match client.get_secret_value().secret_id(Self::conventional_name(#secret_name)?).send().await {
+                        Ok(_) => Ok(true),
+                        Err(err) => {
+                            let err = err.into_service_error();
+                            if err.is_resource_not_found_exception() {
+                                Ok(false)
+                            } else {
+                                Err(err.into())
+                            }
+                        }
+                    }
+                }
+
+                /// Blocking preflight check that fails unless the secret exists and can be read.
+                pub fn verify() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+                    global_secrets_manager_runtime::dotenvy::dotenv().ok();
+                    if global_secrets_manager_runtime::executor::block_on(Self::exists())?? {
+                        Ok(())
+                    } else {
+                        Err(format!("secret `{}` does not exist", Self::conventional_name(#secret_name)?).into())
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Each variant of an enum holds the secrets of one environment; `env_var` selects the variant, hence the secret, at load time.
+fn expand_enum(
+    input: &DeriveInput,
+    ident: &Ident,
+    ty: &TokenStream,
+    data: &DataEnum,
+    container: &ContainerAttrs,
+) -> Result<TokenStream> {
+    if let Some(name) = &container.name {
+        return Err(Error::new_spanned(
+            name,
+            "the secret of an enum is named per variant, with `#[gsm(name = \"...\")]`",
+        ));
+    }
+    let env_var = env_var(container);
+
+    let mut arms = Vec::new();
+    let mut values = Vec::new();
+    for variant in &data.variants {
+        let variant_attrs = VariantAttrs::from_attrs(&variant.attrs)?;
+        let inner = match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+            _ => return Err(Error::new_spanned(
+                variant,
+                "each variant must hold the secrets of its environment, as in `Prod(ProdSecrets)`",
+            )),
+        };
+        let secret_name = match &variant_attrs.name {
+            Some(name) => name.value(),
+            None => match inner {
+                Type::Path(path) => path.path.segments.last().unwrap().ident.to_string(),
+                _ => {
+                    return Err(Error::new_spanned(
+                        inner,
+                        "the name of the secret can't be taken from this type; set it with `#[gsm(name = \"...\")]`",
+                    ))
+                }
+            },
+        };
+        let value = variant_attrs
+            .rename
+            .as_ref()
+            .map_or_else(|| variant.ident.to_string(), |rename| rename.value());
+        let variant_ident = &variant.ident;
+        let deserialize = schema::deserialize(input, &secret_name, quote!(&rt_str));
+        arms.push(quote! {
+            if environment.eq_ignore_ascii_case(#value) {
+                let rt_str = Self::get_secret(client, &Self::conventional_name(#secret_name)?).await?;
+                return Ok(Self::#variant_ident(#deserialize?));
+            }
+        });
+        values.push(value);
+    }
+    let expected = values.join(", ");
+    let global = global(
+        ident,
+        ty,
+        container,
+        quote!(Err(
+            "`GSM_DRY_RUN` is not supported for enums, whose variants may hold any type".into()
+        )),
+        None,
+    );
+
+    let conventional_name = conventional_name();
+
+    Ok(quote! {
+        global_secrets_manager_runtime::__sdk! {
+            #global
+
+            impl #ty {
+                /// Fetches the secret of the selected environment with a client managed by the application, such as one with interceptors or a LocalStack endpoint.
+                pub async fn get_with_client(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+                    let environment = std::env::var(#env_var)
+                        .map_err(|_| format!("`{}` must be set to one of: {}", #env_var, #expected))?;
+                    #(#arms)*
+                    Err(format!("`{}` is `{}`, expected one of: {}", #env_var, environment, #expected).into())
+                }
+
+                #conventional_name
+            }
+        }
+    })
+}
+
+/// The type stored in the global constant.
+///
+/// Lifetime parameters become `'static`, since the payload is leaked and borrowed fields can point into it.
+/// Type and const parameters can't be given a value and are rejected.
+fn concrete_type(input: &DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let Generics { params, .. } = &input.generics;
+    if params.is_empty() {
+        return Ok(quote!(#ident));
+    }
+    let lifetimes = params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Lifetime(_) => Ok(quote!('static)),
+            GenericParam::Type(_) | GenericParam::Const(_) => Err(Error::new_spanned(
+                param,
+                "global-secrets-manager does not support type or const parameters: the global constant needs a concrete type",
+            )),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(quote!(#ident<#(#lifetimes),*>))
+}
+
+/// The first of the fields named after one of `names`.
+pub fn find_field<'a>(fields: &'a FieldsNamed, names: &[&str]) -> Option<&'a Ident> {
+    fields
+        .named
+        .iter()
+        .filter_map(|f| f.ident.as_ref())
+        .find(|ident| names.iter().any(|name| ident == name))
+}
+
+/// A local `encode` function percent-encoding a component of a URL, such as a password.
+pub fn percent_encode() -> TokenStream {
+    quote! {
+        fn encode(value: &str) -> String {
+            value
+                .bytes()
+                .map(|b| match b {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+                    _ => format!("%{:02X}", b),
+                })
+                .collect()
+        }
+    }
+}
+
+/// A local `base64` function encoding bytes in standard base64, with padding.
+#[cfg(any(feature = "conjur", feature = "akeyless", feature = "gcp-kms-file"))]
+pub fn base64_encode() -> TokenStream {
+    quote! {
+        fn base64(bytes: &[u8]) -> String {
+            const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+            let mut encoded = String::new();
+            for chunk in bytes.chunks(3) {
+                let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (u32::from(*b) << (16 - 8 * i)));
+                for i in 0..4 {
+                    if i <= chunk.len() {
+                        encoded.push(ALPHABET[((n >> (18 - 6 * i)) & 63) as usize] as char);
+                    } else {
+                        encoded.push('=');
+                    }
+                }
+            }
+            encoded
+        }
+    }
+}
+
+/// A local `base64` function decoding standard or URL-safe base64, such as the ciphertexts of KMS. Padding and whitespace are ignored.
+pub fn base64_decode() -> TokenStream {
+    quote! {
+        fn base64(encoded: &str) -> Option<Vec<u8>> {
+            let mut decoded = Vec::with_capacity(encoded.len() * 3 / 4);
+            let (mut buffer, mut bits) = (0u32, 0);
+            for b in encoded.bytes().filter(|b| !b.is_ascii_whitespace() && *b != b'=') {
+                let value = match b {
+                    b'A'..=b'Z' => b - b'A',
+                    b'a'..=b'z' => b - b'a' + 26,
+                    b'0'..=b'9' => b - b'0' + 52,
+                    b'+' | b'-' => 62,
+                    b'/' | b'_' => 63,
+                    _ => return None,
+                };
+                buffer = buffer << 6 | u32::from(value);
+                bits += 6;
+                if bits >= 8 {
+                    bits -= 8;
+                    decoded.push((buffer >> bits) as u8);
+                }
+            }
+            Some(decoded)
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{parenthesized, Attribute, DeriveInput, FieldsNamed, Ident, LitStr, Result, Visibility};
+
+use crate::attr::ContainerAttrs;
+use crate::expand;
+
+/// Input of `secrets! { ... }`: any number of declarations.
+pub struct Secrets(Vec<Declaration>);
+
+/// `#[attrs] vis Ident("secret name") { fields }`, where the secret name is optional.
+struct Declaration {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    ident: Ident,
+    name: Option<LitStr>,
+    fields: FieldsNamed,
+}
+
+impl Parse for Secrets {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut declarations = Vec::new();
+        while !input.is_empty() {
+            declarations.push(input.parse()?);
+        }
+        Ok(Self(declarations))
+    }
+}
+
+impl Parse for Declaration {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let ident = input.parse()?;
+        let name = if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            Some(content.parse()?)
+        } else {
+            None
+        };
+        let fields = input.parse()?;
+        Ok(Self {
+            attrs,
+            vis,
+            ident,
+            name,
+            fields,
+        })
+    }
+}
+
+pub fn expand(Secrets(declarations): Secrets) -> Result<TokenStream> {
+    let mut expanded = TokenStream::new();
+    for Declaration {
+        attrs,
+        vis,
+        ident,
+        name,
+        fields,
+    } in declarations
+    {
+        let input: DeriveInput = syn::parse2(quote! {
+            #(#attrs)*
+            #vis struct #ident #fields
+        })?;
+        let mut container = ContainerAttrs::from_attrs(&input.attrs)?;
+        if let Some(name) = name {
+            container.set_name(name)?;
+        }
+        expanded.extend(expand::declare(container, input)?);
+    }
+    Ok(expanded)
+}
@@ -0,0 +1,82 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::attr::ContainerAttrs;
+use crate::expand;
+
+/// `instance_name()` when `#[gsm(instances = "...")]` is set, returning the name of the secret of an instance, or of the one selected by the variable.
+pub fn name(secret_name: &str, container: &ContainerAttrs) -> TokenStream {
+    let var = match &container.instances {
+        Some(var) => var.value(),
+        None => return TokenStream::new(),
+    };
+    let placeholder = format!("{{{}}}", var);
+    quote! {
+        /// The name of the secret of the instance `instance`, or of the one selected by the variable when it is `None`.
+        fn instance_name(instance: Option<&str>) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+            let instance = match instance {
+                Some(instance) => instance.to_owned(),
+                None => std::env::var(#var).map_err(|_| format!("`{}` must be set to the instance of `{}`", #var, #secret_name))?,
+            };
+            Ok(#secret_name.replace(#placeholder, &instance))
+        }
+    }
+}
+
+/// `get()`, reading the secret of an instance named by `#[gsm(instances = "...")]`, when it is set.
+///
+/// Each instance is fetched once and kept for the rest of the program, as the global constant is, so that an application
+/// talking to several deployments of the same dependency holds one value for each of them.
+pub fn expand(ty: &TokenStream, container: &ContainerAttrs) -> TokenStream {
+    let var = match &container.instances {
+        Some(var) => var.value(),
+        None => return TokenStream::new(),
+    };
+    let shared_config = expand::shared_config(container);
+    let doc = format!(
+        "The secret of the instance `instance`, named with it in place of `{{{}}}`, fetched at the first read and kept for the rest of the program.",
+        var
+    );
+    let doc_constant = format!(
+        "The global constant holds the instance selected by `{}`. Concurrent first reads fetch the secret once.",
+        var
+    );
+    quote! {
+        impl #ty {
+            #[doc = #doc]
+            #[doc = #doc_constant]
+            /// The instances are made of letters, digits, `-`, `_` and `.`, so that one can't name another secret.
+            pub async fn get(instance: &str) -> Result<&'static Self, Box<dyn std::error::Error + Send + Sync>> {
+                /// The secrets of the instances read so far, and the client reading them.
+                static INSTANCES: global_secrets_manager_runtime::once_cell::sync::Lazy<
+                    global_secrets_manager_runtime::executor::Mutex<(
+                        std::collections::HashMap<String, &'static #ty>,
+                        Option<global_secrets_manager_runtime::aws_sdk_secretsmanager::Client>,
+                    )>,
+                > = global_secrets_manager_runtime::once_cell::sync::Lazy::new(|| global_secrets_manager_runtime::executor::Mutex::new(Default::default()));
+
+                if instance.is_empty() || !instance.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.')) {
+                    return Err(format!("`{}` is not a valid instance: expected letters, digits, `-`, `_` and `.`", instance).into());
+                }
+                let mut instances = INSTANCES.lock().await;
+                let (secrets, client) = &mut *instances;
+                if let Some(secrets) = secrets.get(instance) {
+                    return Ok(*secrets);
+                }
+
+                let client = match client {
+                    Some(client) => client.clone(),
+                    None => {
+                        global_secrets_manager_runtime::dotenvy::dotenv().ok();
+                        #shared_config
+                        client.insert(global_secrets_manager_runtime::aws_sdk_secretsmanager::Client::new(&shared_config)).clone()
+                    }
+                };
+                let secret_id = Self::conventional_name(&Self::instance_name(Some(instance))?)?;
+                let read: &'static Self = Box::leak(Box::new(Self::read_secret(&client, &secret_id).await?.0));
+                secrets.insert(instance.to_owned(), read);
+                Ok(read)
+            }
+        }
+    }
+}
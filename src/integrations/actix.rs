@@ -0,0 +1,27 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::Target;
+
+/// Handlers take `&'static MySecrets` as an extractor, or the preloaded constant is installed as app data.
+pub fn expand(target: &Target) -> TokenStream {
+    let ty = target.ty;
+    quote! {
+        impl actix_web::FromRequest for &'static #ty {
+            type Error = actix_web::Error;
+            type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self, Self::Error>>>>;
+
+            fn from_request(_req: &actix_web::HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+                Box::pin(async { Ok(<#ty>::preload().await) })
+            }
+        }
+
+        impl #ty {
+            /// Preloads the global constant for `App::app_data`, so that handlers can take `web::Data<&'static Self>`.
+            /// Call it before `HttpServer::new`, whose factory runs on every worker.
+            pub async fn actix_data() -> actix_web::web::Data<&'static Self> {
+                actix_web::web::Data::new(Self::preload().await)
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::attr::ContainerAttrs;
+use crate::expand;
+
+/// The body of `fetch_secret()` reading the static secret of `backend = "akeyless"` with the REST API of Akeyless, through reqwest.
+///
+/// The access id is read from `AKEYLESS_ACCESS_ID` and the gateway from `AKEYLESS_GATEWAY_URL`, defaulting to the public API.
+/// With `AKEYLESS_ACCESS_KEY` set, the client authenticates with the API key; otherwise with the AWS IAM identity of the environment,
+/// proven by a `GetCallerIdentity` request of STS signed with its credentials, which Akeyless sends to STS itself.
+pub fn fetch(container: &ContainerAttrs) -> TokenStream {
+    let path = &container.path;
+    let label = format!(
+        "akeyless {}",
+        container.backend_source().unwrap_or_default()
+    );
+    let base64 = expand::base64_encode();
+    quote! {
+        #base64
+
+        let _ = secret_id;
+        let transport = |err: reqwest::Error| global_secrets_manager_runtime::GsmError::Transport {
+            secret: #label.to_owned(),
+            source: err.into(),
+        };
+        let access_id = std::env::var("AKEYLESS_ACCESS_ID").map_err(|_| global_secrets_manager_runtime::GsmError::Credentials {
+            secret: #label.to_owned(),
+            source: "`AKEYLESS_ACCESS_ID` is not set".into(),
+        })?;
+        let gateway = std::env::var("AKEYLESS_GATEWAY_URL").unwrap_or_else(|_| "https://api.akeyless.io".to_owned());
+        let gateway = gateway.trim_end_matches('/');
+        let auth = match std::env::var("AKEYLESS_ACCESS_KEY") {
+            Ok(access_key) => global_secrets_manager_runtime::serde_json::json!({
+                "access-type": "api_key",
+                "access-id": access_id,
+                "access-key": access_key,
+            }),
+            Err(_) => {
+                let mut config = global_secrets_manager_runtime::config_loader();
+                if let Some(region) = client.config().region() {
+                    config = config.region(region.clone());
+                }
+                let credentials = global_secrets_manager_runtime::credentials(&config.load().await)
+                    .await
+                    .map_err(|source| global_secrets_manager_runtime::GsmError::Credentials {
+                        secret: #label.to_owned(),
+                        source,
+                    })?;
+                let request = global_secrets_manager_runtime::sigv4::get_caller_identity(&credentials, std::time::SystemTime::now());
+                // STS reads the headers by their canonical names, such as `X-Amz-Date`.
+                let headers: global_secrets_manager_runtime::serde_json::Map<String, global_secrets_manager_runtime::serde_json::Value> = request
+                    .headers
+                    .iter()
+                    .map(|(name, value)| {
+                        let name: Vec<String> = name
+                            .split('-')
+                            .map(|part| {
+                                let mut chars = part.chars();
+                                chars.next().map(|c| c.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+                            })
+                            .collect();
+                        (name.join("-"), global_secrets_manager_runtime::serde_json::json!([value]))
+                    })
+                    .collect();
+                let cloud_id = global_secrets_manager_runtime::serde_json::json!({
+                    "sts_request_method": request.method,
+                    "sts_request_url": base64(request.url.as_bytes()),
+                    "sts_request_body": base64(request.body.as_bytes()),
+                    "sts_request_headers": base64(global_secrets_manager_runtime::serde_json::Value::Object(headers).to_string().as_bytes()),
+                });
+                global_secrets_manager_runtime::serde_json::json!({
+                    "access-type": "aws_iam",
+                    "access-id": access_id,
+                    "cloud-id": base64(cloud_id.to_string().as_bytes()),
+                })
+            }
+        };
+        let http = reqwest::Client::new();
+        let resp = http
+            .post(format!("{}/auth", gateway))
+            .header("Content-Type", "application/json")
+            .body(auth.to_string())
+            .send()
+            .await
+            .map_err(transport)?;
+        if !resp.status().is_success() {
+            return Err(global_secrets_manager_runtime::GsmError::Credentials {
+                secret: #label.to_owned(),
+                source: format!("Akeyless answered {} to the authentication of `{}`", resp.status(), access_id).into(),
+            }
+            .into());
+        }
+        let decode = |err: global_secrets_manager_runtime::serde_json::Error| {
+            global_secrets_manager_runtime::GsmError::decode(#label, format!("the answer of Akeyless is not the expected JSON: {}", err))
+        };
+        let token: global_secrets_manager_runtime::serde_json::Value =
+            global_secrets_manager_runtime::serde_json::from_str(&resp.text().await.map_err(transport)?).map_err(decode)?;
+        let token = token["token"].as_str().unwrap_or_default();
+        let resp = http
+            .post(format!("{}/get-secret-value", gateway))
+            .header("Content-Type", "application/json")
+            .body(global_secrets_manager_runtime::serde_json::json!({ "names": [#path], "token": token }).to_string())
+            .send()
+            .await
+            .map_err(transport)?;
+        let status = resp.status().as_u16();
+        let source = || format!("Akeyless answered {} to the read of the static secret", status).into();
+        match status {
+            200 => {}
+            404 => return Err(global_secrets_manager_runtime::GsmError::NotFound { secret: #label.to_owned() }.into()),
+            401 | 403 => {
+                return Err(global_secrets_manager_runtime::GsmError::AccessDenied { secret: #label.to_owned(), source: source() }.into())
+            }
+            429 => {
+                return Err(global_secrets_manager_runtime::GsmError::Throttled { secret: #label.to_owned(), source: source() }.into())
+            }
+            _ => {
+                return Err(global_secrets_manager_runtime::GsmError::Service { secret: #label.to_owned(), source: source() }.into())
+            }
+        }
+        let values: global_secrets_manager_runtime::serde_json::Value =
+            global_secrets_manager_runtime::serde_json::from_str(&resp.text().await.map_err(transport)?).map_err(decode)?;
+        match values[#path].as_str() {
+            Some(value) => Ok(value.to_owned()),
+            None => Err(global_secrets_manager_runtime::GsmError::NotFound { secret: #label.to_owned() }.into()),
+        }
+    }
+}
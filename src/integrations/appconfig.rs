@@ -0,0 +1,88 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::attr::AppConfig;
+
+/// The body of `fetch_secret()` reading the configuration profile of AppConfig with its data plane, aws-sdk-appconfigdata.
+///
+/// A configuration session is started by the first fetch, and the next ones poll it with the token of the previous poll.
+/// The fetches before the interval returned by AppConfig has elapsed get the last configuration without calling it, as do the polls
+/// returning no configuration, which AppConfig does while it is unchanged. The client is built as the KMS one is, from the default
+/// configuration in the region of the Secrets Manager client.
+pub fn fetch(config: &AppConfig) -> TokenStream {
+    let AppConfig {
+        application,
+        environment,
+        profile,
+        poll_interval,
+        ..
+    } = config;
+    let label = config.source();
+    let poll_interval = poll_interval.map(|seconds| {
+        let seconds = seconds as i32;
+        quote!(.required_minimum_poll_interval_in_seconds(#seconds))
+    });
+    quote! {
+        /// The session polled by the fetches: the token of the next poll, the time it is allowed from, and the last configuration.
+        static SESSION: std::sync::Mutex<Option<(String, std::time::Instant, String)>> = std::sync::Mutex::new(None);
+        let _ = secret_id;
+        let error = |err: Box<dyn std::error::Error + Send + Sync>, code: Option<String>| {
+            global_secrets_manager_runtime::GsmError::service(#label, code.as_deref(), err)
+        };
+        let session = SESSION.lock().unwrap_or_else(std::sync::PoisonError::into_inner).take();
+        let (token, last) = match session {
+            Some((token, next_poll, last)) if std::time::Instant::now() < next_poll => {
+                *SESSION.lock().unwrap_or_else(std::sync::PoisonError::into_inner) = Some((token, next_poll, last.clone()));
+                return Ok(last);
+            }
+            Some((token, _, last)) => (Some(token), Some(last)),
+            None => (None, None),
+        };
+        let mut config = global_secrets_manager_runtime::config_loader();
+        if let Some(region) = client.config().region() {
+            config = config.region(region.clone());
+        }
+        let appconfig = aws_sdk_appconfigdata::Client::new(&config.load().await);
+        let token = match token {
+            Some(token) => token,
+            None => {
+                let resp = appconfig
+                    .start_configuration_session()
+                    .application_identifier(#application)
+                    .environment_identifier(#environment)
+                    .configuration_profile_identifier(#profile)
+                    #poll_interval
+                    .send()
+                    .await
+                    .map_err(|err| {
+                        let code = aws_sdk_appconfigdata::error::ProvideErrorMetadata::code(&err).map(str::to_owned);
+                        error(err.into(), code)
+                    })?;
+                resp.initial_configuration_token
+                    .ok_or_else(|| global_secrets_manager_runtime::GsmError::decode(#label, "AppConfig returned no configuration token"))?
+            }
+        };
+        let resp = appconfig
+            .get_latest_configuration()
+            .configuration_token(token)
+            .send()
+            .await
+            .map_err(|err| {
+                let code = aws_sdk_appconfigdata::error::ProvideErrorMetadata::code(&err).map(str::to_owned);
+                error(err.into(), code)
+            })?;
+        let payload = match (resp.configuration.filter(|blob| !blob.as_ref().is_empty()), last) {
+            (Some(blob), _) => String::from_utf8(blob.into_inner())
+                .map_err(|_| global_secrets_manager_runtime::GsmError::decode(#label, "the configuration is not UTF-8"))?,
+            // Unchanged since the last poll.
+            (None, Some(last)) => last,
+            (None, None) => return Err(global_secrets_manager_runtime::GsmError::decode(#label, "AppConfig returned no configuration").into()),
+        };
+        let next_token = resp
+            .next_poll_configuration_token
+            .ok_or_else(|| global_secrets_manager_runtime::GsmError::decode(#label, "AppConfig returned no token for the next poll"))?;
+        let next_poll = std::time::Instant::now() + std::time::Duration::from_secs(resp.next_poll_interval_in_seconds.max(0) as u64);
+        *SESSION.lock().unwrap_or_else(std::sync::PoisonError::into_inner) = Some((next_token, next_poll, payload.clone()));
+        Ok(payload)
+    }
+}
@@ -0,0 +1,26 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::Target;
+
+/// Handlers take `Secrets<MySecrets>` of the runtime as an extractor, served by the global constant, which rejects the request when it can't be loaded.
+pub fn expand(target: &Target) -> TokenStream {
+    let ty = target.ty;
+    quote! {
+        impl global_secrets_manager_runtime::axum::GlobalSecret for #ty {
+            async fn try_preload() -> Result<&'static Self, Box<dyn std::error::Error + Send + Sync>> {
+                <#ty>::try_preload().await
+            }
+        }
+
+        impl #ty {
+            /// Preloads the global constant and serves it as the state of `router`, for handlers taking `State<Secrets<Self>>`,
+            /// or fails with the error of the load.
+            pub async fn axum_state<S>(
+                router: axum::Router<global_secrets_manager_runtime::axum::Secrets<Self>>,
+            ) -> Result<axum::Router<S>, Box<dyn std::error::Error + Send + Sync>> {
+                Ok(router.with_state(global_secrets_manager_runtime::axum::Secrets(Self::try_preload().await?)))
+            }
+        }
+    }
+}
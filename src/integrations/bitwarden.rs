@@ -0,0 +1,81 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::attr::Bitwarden;
+
+/// The body of `fetch_secret()` reading Bitwarden Secrets Manager with the `bitwarden` crate, its SDK, logged in with the access token of a machine account.
+///
+/// The values are encrypted by Bitwarden for the machine account, so they are read through the SDK, which decrypts them, rather than its REST API.
+/// The secrets of a `project` are the keys of the payload, named by their keys in Bitwarden; a `secret` holds the payload itself.
+pub fn fetch(bitwarden: &Bitwarden) -> TokenStream {
+    let label = format!("bitwarden {}", bitwarden.source());
+    let access_token_env = bitwarden
+        .access_token_env
+        .as_ref()
+        .map_or_else(|| "BWS_ACCESS_TOKEN".to_owned(), |var| var.value());
+    let settings = match &bitwarden.server_url {
+        Some(server_url) => quote! {
+            Some(bitwarden::ClientSettings {
+                identity_url: format!("{}/identity", #server_url.trim_end_matches('/')),
+                api_url: format!("{}/api", #server_url.trim_end_matches('/')),
+                ..Default::default()
+            })
+        },
+        None => quote!(None),
+    };
+    let read = match (&bitwarden.project, &bitwarden.secret) {
+        (Some(project), _) => quote! {
+            let project_id = #project
+                .parse()
+                .map_err(|_| global_secrets_manager_runtime::GsmError::decode(#label, "the id of the project is not a UUID"))?;
+            let ids = bitwarden_client
+                .secrets()
+                .list_by_project(&bitwarden::secrets_manager::secrets::SecretIdentifiersByProjectRequest { project_id })
+                .await
+                .map_err(|err| error(err.into()))?
+                .data
+                .into_iter()
+                .map(|secret| secret.id)
+                .collect();
+            let secrets = bitwarden_client
+                .secrets()
+                .get_by_ids(bitwarden::secrets_manager::secrets::SecretsGetRequest { ids })
+                .await
+                .map_err(|err| error(err.into()))?;
+            Ok(Self::from_strings(secrets.data.into_iter().map(|secret| (secret.key, secret.value)).collect()))
+        },
+        (None, secret) => quote! {
+            let id = #secret
+                .parse()
+                .map_err(|_| global_secrets_manager_runtime::GsmError::decode(#label, "the id of the secret is not a UUID"))?;
+            let secret = bitwarden_client
+                .secrets()
+                .get(&bitwarden::secrets_manager::secrets::SecretGetRequest { id })
+                .await
+                .map_err(|err| error(err.into()))?;
+            Ok(secret.value)
+        },
+    };
+    quote! {
+        use bitwarden::secrets_manager::ClientSecretsExt as _;
+        let _ = (client, secret_id);
+        let error = |source: Box<dyn std::error::Error + Send + Sync>| global_secrets_manager_runtime::GsmError::Service {
+            secret: #label.to_owned(),
+            source,
+        };
+        let access_token = std::env::var(#access_token_env).map_err(|_| global_secrets_manager_runtime::GsmError::Credentials {
+            secret: #label.to_owned(),
+            source: format!("`{}` holds no access token of a machine account", #access_token_env).into(),
+        })?;
+        let bitwarden_client = bitwarden::Client::new(#settings);
+        bitwarden_client
+            .auth()
+            .login_access_token(&bitwarden::auth::login::AccessTokenLoginRequest { access_token, state_file: None })
+            .await
+            .map_err(|err| global_secrets_manager_runtime::GsmError::Credentials {
+                secret: #label.to_owned(),
+                source: err.into(),
+            })?;
+        #read
+    }
+}
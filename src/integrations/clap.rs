@@ -0,0 +1,37 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::Target;
+
+/// Defaults of clap arguments taken from the fields marked `#[gsm(clap)]`.
+pub fn expand(target: &Target) -> TokenStream {
+    let Target {
+        input,
+        fields,
+        field_attrs,
+        ty,
+        ..
+    } = target;
+    let ident = &input.ident;
+    let (names, ids): (Vec<_>, Vec<_>) = fields
+        .named
+        .iter()
+        .zip(field_attrs.iter())
+        .filter_map(|(field, attrs)| Some((&field.ident, attrs.clap.as_ref()?)))
+        .unzip();
+    quote! {
+        impl #ty {
+            /// Makes the fields marked `#[gsm(clap)]` the defaults of the arguments of `command` with the same id.
+            /// The secret is loaded by this call, and the defaults are hidden from the help so that they are not printed.
+            pub fn clap_defaults(mut command: clap::Command) -> clap::Command {
+                #(
+                    if command.get_arguments().any(|arg| arg.get_id() == #ids) {
+                        let value: &'static str = Box::leak(#ident.#names.to_string().into_boxed_str());
+                        command = command.mut_arg(#ids, |arg| arg.default_value(value).hide_default_value(true));
+                    }
+                )*
+                command
+            }
+        }
+    }
+}
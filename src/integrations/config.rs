@@ -0,0 +1,31 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::Target;
+use crate::expand;
+
+/// The raw payload of the secret as a `config::Source`, to be layered in a `config::Config`.
+/// The paths are absolute since applications commonly have a `config` module of their own.
+pub fn expand(target: &Target) -> TokenStream {
+    let ty = target.ty;
+    let type_name = target.input.ident.to_string();
+    let shared_config = expand::shared_config(target.container);
+    quote! {
+        impl #ty {
+            /// Fetches the secret as a layer of a `config::Config`, reached as the global constant reaches it.
+            /// An async runtime can't be blocked, so it fails when called from one.
+            pub fn config_source() -> Result<::config::File<::config::FileSourceString, ::config::FileFormat>, Box<dyn std::error::Error + Send + Sync>> {
+                if global_secrets_manager_runtime::executor::in_async_context() {
+                    return Err(format!("`{}::config_source()` can't block an async runtime, build the configuration before starting it", #type_name).into());
+                }
+                global_secrets_manager_runtime::dotenvy::dotenv().ok();
+                let secret = global_secrets_manager_runtime::executor::block_on(async {
+                    #shared_config
+                    let client = global_secrets_manager_runtime::aws_sdk_secretsmanager::Client::new(&shared_config);
+                    Self::get_secret(&client, &Self::secret_id(&client).await?).await
+                })??;
+                Ok(::config::File::from_str(&secret, ::config::FileFormat::Json))
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, FieldsNamed, LitStr, Result};
+
+use crate::attr::Conjur;
+use crate::expand;
+use crate::schema;
+
+/// The body of `fetch_secret()` reading the variables of Conjur with reqwest: one for each key, named `{policy}/{key}`.
+///
+/// The host logs in with its API key, from `CONJUR_AUTHN_API_KEY`, for an access token sent with the reads of the variables.
+/// Variables that don't exist or have no value yet leave their key missing, as optional fields expect.
+pub fn fetch(input: &DeriveInput, fields: &FieldsNamed, conjur: &Conjur) -> Result<TokenStream> {
+    let names: Vec<_> = schema::schema(input, fields)?
+        .keys
+        .into_iter()
+        .map(|key| key.names[0].clone())
+        .collect();
+    let label = format!("conjur {}", conjur.policy.value());
+    let policy = conjur.policy.value().trim_matches('/').to_owned();
+    let option = |lit: &Option<LitStr>, var: &str| match lit {
+        Some(lit) => quote!(#lit.to_owned()),
+        None => quote!(var(#var)?),
+    };
+    let url = option(&conjur.url, "CONJUR_APPLIANCE_URL");
+    let account = option(&conjur.account, "CONJUR_ACCOUNT");
+    let login = option(&conjur.login, "CONJUR_AUTHN_LOGIN");
+    let encode = expand::percent_encode();
+    let base64 = expand::base64_encode();
+    Ok(quote! {
+        #encode
+        #base64
+
+        let _ = (client, secret_id);
+        let var = |name: &str| {
+            std::env::var(name).map_err(|_| global_secrets_manager_runtime::GsmError::Credentials {
+                secret: #label.to_owned(),
+                source: format!("`{}` is not set", name).into(),
+            })
+        };
+        let transport = |err: reqwest::Error| global_secrets_manager_runtime::GsmError::Transport {
+            secret: #label.to_owned(),
+            source: err.into(),
+        };
+        let url = #url;
+        let url = url.trim_end_matches('/');
+        let account = #account;
+        let login = #login;
+        let http = reqwest::Client::new();
+        let resp = http
+            .post(format!("{}/authn/{}/{}/authenticate", url, encode(&account), encode(&login)))
+            .body(var("CONJUR_AUTHN_API_KEY")?)
+            .send()
+            .await
+            .map_err(transport)?;
+        if !resp.status().is_success() {
+            return Err(global_secrets_manager_runtime::GsmError::Credentials {
+                secret: #label.to_owned(),
+                source: format!("Conjur answered {} to the authentication of `{}`", resp.status(), login).into(),
+            }
+            .into());
+        }
+        let authorization = format!("Token token=\"{}\"", base64(&resp.bytes().await.map_err(transport)?));
+        let mut values = Vec::new();
+        for name in [#(#names),*] {
+            let id = format!("{}/{}", #policy, name);
+            let resp = http
+                .get(format!("{}/secrets/{}/variable/{}", url, encode(&account), encode(&id)))
+                .header("Authorization", &authorization)
+                .send()
+                .await
+                .map_err(transport)?;
+            let status = resp.status().as_u16();
+            let source = || format!("Conjur answered {} to the read of the variable of key `{}`", status, Self::key_name(name)).into();
+            match status {
+                200 => values.push((name.to_owned(), resp.text().await.map_err(transport)?)),
+                404 => {}
+                401 | 403 => {
+                    return Err(global_secrets_manager_runtime::GsmError::AccessDenied { secret: #label.to_owned(), source: source() }.into())
+                }
+                429 => {
+                    return Err(global_secrets_manager_runtime::GsmError::Throttled { secret: #label.to_owned(), source: source() }.into())
+                }
+                _ => {
+                    return Err(global_secrets_manager_runtime::GsmError::Service { secret: #label.to_owned(), source: source() }.into())
+                }
+            }
+        }
+        Ok(Self::from_strings(values))
+    })
+}
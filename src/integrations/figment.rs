@@ -0,0 +1,23 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::Target;
+
+/// A `figment::Provider` serving the fields of the struct, which therefore also has to derive `serde::Serialize`.
+pub fn expand(target: &Target) -> TokenStream {
+    let Target {
+        ty, secret_name, ..
+    } = target;
+    let metadata = format!("AWS Secrets Manager secret `{}`", secret_name);
+    quote! {
+        impl figment::Provider for #ty {
+            fn metadata(&self) -> figment::Metadata {
+                figment::Metadata::named(#metadata)
+            }
+
+            fn data(&self) -> Result<figment::value::Map<figment::Profile, figment::value::Dict>, figment::Error> {
+                figment::providers::Serialized::defaults(self).data()
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::attr::GcpKmsFile;
+use crate::expand;
+
+/// The body of `fetch_secret()` reading a local file encrypted with Google Cloud KMS, decrypted by the `decrypt` method of its REST API through reqwest.
+///
+/// The file holds the ciphertext, binary as `gcloud kms encrypt` writes it, or in base64. The access token is the one of the
+/// credentials of the environment, found by gcp_auth as the Google Cloud clients do, such as `GOOGLE_APPLICATION_CREDENTIALS`
+/// or the service account of the workload.
+pub fn fetch(file: &GcpKmsFile) -> TokenStream {
+    let (path, key) = (&file.file, &file.key);
+    let label = format!("gcp-kms-file {}", path.value());
+    let base64_decode = expand::base64_decode();
+    let base64_encode = expand::base64_encode();
+    quote! {
+        #base64_decode
+
+        let _ = (client, secret_id);
+        let transport = |err: reqwest::Error| global_secrets_manager_runtime::GsmError::Transport {
+            secret: #label.to_owned(),
+            source: err.into(),
+        };
+        let contents = match std::fs::read(#path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(global_secrets_manager_runtime::GsmError::NotFound { secret: #label.to_owned() }.into())
+            }
+            Err(err) => {
+                return Err(global_secrets_manager_runtime::GsmError::Service { secret: #label.to_owned(), source: err.into() }.into())
+            }
+        };
+        let ciphertext = match std::str::from_utf8(&contents) {
+            Ok(text) if base64(text).is_some() => text.split_whitespace().collect::<String>(),
+            _ => {
+                #base64_encode
+                base64(&contents)
+            }
+        };
+        let token = async {
+            gcp_auth::provider()
+                .await?
+                .token(&["https://www.googleapis.com/auth/cloudkms"])
+                .await
+        }
+        .await
+        .map_err(|err| global_secrets_manager_runtime::GsmError::Credentials {
+            secret: #label.to_owned(),
+            source: err.into(),
+        })?;
+        let resp = reqwest::Client::new()
+            .post(format!("https://cloudkms.googleapis.com/v1/{}:decrypt", #key))
+            .header("Authorization", format!("Bearer {}", token.as_str()))
+            .header("Content-Type", "application/json")
+            .body(global_secrets_manager_runtime::serde_json::json!({ "ciphertext": ciphertext }).to_string())
+            .send()
+            .await
+            .map_err(transport)?;
+        let status = resp.status().as_u16();
+        let source = || format!("Cloud KMS answered {} to the decryption of the file with `{}`", status, #key).into();
+        match status {
+            200 => {}
+            400 => {
+                return Err(global_secrets_manager_runtime::GsmError::decode(#label, format!("the file is not a ciphertext of `{}`", #key)).into())
+            }
+            401 | 403 => {
+                return Err(global_secrets_manager_runtime::GsmError::AccessDenied { secret: #label.to_owned(), source: source() }.into())
+            }
+            429 => {
+                return Err(global_secrets_manager_runtime::GsmError::Throttled { secret: #label.to_owned(), source: source() }.into())
+            }
+            _ => {
+                return Err(global_secrets_manager_runtime::GsmError::Service { secret: #label.to_owned(), source: source() }.into())
+            }
+        }
+        let body: global_secrets_manager_runtime::serde_json::Value =
+            global_secrets_manager_runtime::serde_json::from_str(&resp.text().await.map_err(transport)?)
+                .map_err(|_| global_secrets_manager_runtime::GsmError::decode(#label, "the answer of Cloud KMS is not JSON"))?;
+        let plaintext = body["plaintext"]
+            .as_str()
+            .and_then(base64)
+            .ok_or_else(|| global_secrets_manager_runtime::GsmError::decode(#label, "the answer of Cloud KMS holds no plaintext"))?;
+        Ok(String::from_utf8(plaintext)
+            .map_err(|_| global_secrets_manager_runtime::GsmError::decode(#label, "the plaintext is not UTF-8"))?)
+    }
+}
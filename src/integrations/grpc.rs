@@ -0,0 +1,65 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::attr::Grpc;
+
+/// The body of `fetch_secret()` calling the `GetSecret` of `proto/secret_service.proto` with tonic, for the name of the struct.
+///
+/// The messages are declared with prost in the body, as tonic-build would generate them, so that applications need no build script.
+/// The payload answered is read as the one of a secret of Secrets Manager.
+pub fn fetch(grpc: &Grpc) -> TokenStream {
+    let endpoint = match &grpc.endpoint {
+        Some(endpoint) => quote!(#endpoint.to_owned()),
+        None => quote! {
+            std::env::var("GSM_GRPC_ENDPOINT").map_err(|_| global_secrets_manager_runtime::GsmError::Transport {
+                secret: secret_id.to_owned(),
+                source: "`GSM_GRPC_ENDPOINT` is not set".into(),
+            })?
+        },
+    };
+    quote! {
+        #[derive(Clone, PartialEq, prost::Message)]
+        struct GetSecretRequest {
+            #[prost(string, tag = "1")]
+            name: String,
+        }
+
+        #[derive(Clone, PartialEq, prost::Message)]
+        struct GetSecretResponse {
+            #[prost(string, tag = "1")]
+            payload: String,
+        }
+
+        let _ = client;
+        let transport = |err: Box<dyn std::error::Error + Send + Sync>| global_secrets_manager_runtime::GsmError::Transport {
+            secret: secret_id.to_owned(),
+            source: err,
+        };
+        let channel = tonic::transport::Endpoint::from_shared(#endpoint)
+            .map_err(|err| transport(err.into()))?
+            .connect()
+            .await
+            .map_err(|err| transport(err.into()))?;
+        let mut grpc = tonic::client::Grpc::new(channel);
+        grpc.ready().await.map_err(|err| transport(err.into()))?;
+        let resp = grpc
+            .unary(
+                tonic::Request::new(GetSecretRequest { name: secret_id.to_owned() }),
+                tonic::codegen::http::uri::PathAndQuery::from_static("/gsm.v1.SecretService/GetSecret"),
+                tonic::codec::ProstCodec::<GetSecretRequest, GetSecretResponse>::default(),
+            )
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => global_secrets_manager_runtime::GsmError::NotFound { secret: secret_id.to_owned() },
+                tonic::Code::PermissionDenied | tonic::Code::Unauthenticated => {
+                    global_secrets_manager_runtime::GsmError::AccessDenied { secret: secret_id.to_owned(), source: status.into() }
+                }
+                tonic::Code::ResourceExhausted => {
+                    global_secrets_manager_runtime::GsmError::Throttled { secret: secret_id.to_owned(), source: status.into() }
+                }
+                tonic::Code::Unavailable => transport(status.into()),
+                _ => global_secrets_manager_runtime::GsmError::Service { secret: secret_id.to_owned(), source: status.into() },
+            })?;
+        Ok(resp.into_inner().payload)
+    }
+}
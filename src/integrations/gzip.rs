@@ -0,0 +1,32 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::expand;
+
+/// The payload of the response `resp`, decompressed with flate2 when it is gzipped.
+///
+/// Compressed payloads are detected by the gzip magic bytes: binary secrets starting with them, and string secrets
+/// starting with their base64 encoding, `H4sI`, which JSON never does.
+pub fn secret_value() -> TokenStream {
+    let base64 = expand::base64_decode();
+    quote! {
+        #base64
+
+        let compressed = match (resp.secret_string(), resp.secret_binary()) {
+            (Some(string), _) if string.starts_with("H4sI") => {
+                base64(string).ok_or_else(|| global_secrets_manager_runtime::GsmError::decode(secret_id, "it is not valid base64"))?
+            }
+            (Some(string), _) => return Ok(string.to_owned()),
+            (None, Some(binary)) if binary.as_ref().starts_with(&[0x1f, 0x8b]) => binary.as_ref().to_vec(),
+            (None, Some(binary)) => {
+                return String::from_utf8(binary.as_ref().to_vec())
+                    .map_err(|_| global_secrets_manager_runtime::GsmError::decode(secret_id, "the binary secret is neither gzipped nor UTF-8").into())
+            }
+            (None, None) => return Err(global_secrets_manager_runtime::GsmError::decode(secret_id, "it has no value").into()),
+        };
+        let mut decompressed = String::new();
+        std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(compressed.as_slice()), &mut decompressed)
+            .map_err(|err| global_secrets_manager_runtime::GsmError::decode(secret_id, format!("it can't be decompressed: {}", err)))?;
+        Ok(decompressed)
+    }
+}
@@ -0,0 +1,75 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::attr::HttpJson;
+
+/// The body of `fetch_secret()` reading the JSON document of `http_json(...)` with a GET of reqwest.
+///
+/// The bearer token is read from its variable, and the PEM files of the client certificate and the authority from the disk, on each fetch,
+/// so that rotated credentials are picked up by the next refresh.
+pub fn fetch(http: &HttpJson) -> TokenStream {
+    let url = &http.url;
+    let label = format!("http-json {}", url.value());
+    let read_pem = (http.identity.is_some() || http.ca.is_some()).then(|| quote! {
+        let read_pem = |path: &str| {
+            std::fs::read(path).map_err(|err| global_secrets_manager_runtime::GsmError::Credentials {
+                secret: #label.to_owned(),
+                source: format!("could not read `{}`: {}", path, err).into(),
+            })
+        };
+        let invalid_pem = |path: &str, err: reqwest::Error| global_secrets_manager_runtime::GsmError::Credentials {
+            secret: #label.to_owned(),
+            source: format!("`{}` is not a valid PEM file: {}", path, err).into(),
+        };
+    });
+    let identity = http.identity.as_ref().map(|identity| {
+        quote! {
+            let identity = reqwest::Identity::from_pem(&read_pem(#identity)?).map_err(|err| invalid_pem(#identity, err))?;
+            builder = builder.identity(identity);
+        }
+    });
+    let ca = http.ca.as_ref().map(|ca| {
+        quote! {
+            let ca = reqwest::Certificate::from_pem(&read_pem(#ca)?).map_err(|err| invalid_pem(#ca, err))?;
+            builder = builder.add_root_certificate(ca);
+        }
+    });
+    let token = http.token_env.as_ref().map(|token_env| {
+        quote! {
+            let token = std::env::var(#token_env).map_err(|_| global_secrets_manager_runtime::GsmError::Credentials {
+                secret: #label.to_owned(),
+                source: format!("`{}` holds no bearer token", #token_env).into(),
+            })?;
+            request = request.bearer_auth(token);
+        }
+    });
+    quote! {
+        let _ = (client, secret_id);
+        #read_pem
+        let transport = |err: reqwest::Error| global_secrets_manager_runtime::GsmError::Transport {
+            secret: #label.to_owned(),
+            source: err.into(),
+        };
+        #[allow(unused_mut)]
+        let mut builder = reqwest::Client::builder();
+        #identity
+        #ca
+        #[allow(unused_mut)]
+        let mut request = builder
+            .build()
+            .map_err(transport)?
+            .get(#url)
+            .header("Accept", "application/json");
+        #token
+        let resp = request.send().await.map_err(transport)?;
+        let status = resp.status().as_u16();
+        let source = || format!("the endpoint answered {}", status).into();
+        match status {
+            200 => Ok(resp.text().await.map_err(transport)?),
+            404 => Err(global_secrets_manager_runtime::GsmError::NotFound { secret: #label.to_owned() }.into()),
+            401 | 403 => Err(global_secrets_manager_runtime::GsmError::AccessDenied { secret: #label.to_owned(), source: source() }.into()),
+            429 => Err(global_secrets_manager_runtime::GsmError::Throttled { secret: #label.to_owned(), source: source() }.into()),
+            _ => Err(global_secrets_manager_runtime::GsmError::Service { secret: #label.to_owned(), source: source() }.into()),
+        }
+    }
+}
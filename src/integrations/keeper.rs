@@ -0,0 +1,81 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, FieldsNamed, Result};
+
+use crate::attr::Keeper;
+use crate::schema;
+
+/// The body of `fetch_secret()` reading a record of Keeper Secrets Manager with its SDK, the keeper-secrets-manager-core crate.
+///
+/// The values are encrypted for the device, so they are read through the SDK, which decrypts them. Each key is the custom field
+/// of its label, or else the standard field of its type, such as `login` or `password`; the keys the record doesn't hold are left missing.
+/// The SDK blocks on its requests, so it runs on a blocking thread of tokio.
+pub fn fetch(input: &DeriveInput, fields: &FieldsNamed, keeper: &Keeper) -> Result<TokenStream> {
+    let names: Vec<_> = schema::schema(input, fields)?
+        .keys
+        .into_iter()
+        .map(|key| key.names[0].clone())
+        .collect();
+    let record = &keeper.record;
+    let label = format!("keeper record/{}", record.value());
+    let config = keeper
+        .config
+        .as_ref()
+        .map_or_else(|| "ksm-config.json".to_owned(), |config| config.value());
+    let token_env = keeper
+        .token_env
+        .as_ref()
+        .map_or_else(|| "KSM_TOKEN".to_owned(), |var| var.value());
+    Ok(quote! {
+        let _ = (client, secret_id);
+        let token = std::env::var(#token_env).ok().filter(|token| !token.is_empty());
+        let read = move || -> Result<Vec<(String, String)>, global_secrets_manager_runtime::GsmError> {
+            use keeper_secrets_manager_core::core::{ClientOptions, SecretsManager};
+            use keeper_secrets_manager_core::enums::KvStoreType;
+            use keeper_secrets_manager_core::storage::FileKeyValueStorage;
+
+            let service = |err: keeper_secrets_manager_core::custom_error::KSMRError| global_secrets_manager_runtime::GsmError::Service {
+                secret: #label.to_owned(),
+                source: err.to_string().into(),
+            };
+            let storage = KvStoreType::File(FileKeyValueStorage::new_config_storage(#config.to_owned()).map_err(service)?);
+            // The token is only needed until the device is bound, and the configuration saved.
+            let options = match token {
+                Some(token) => ClientOptions::new_client_options_with_token(token, storage),
+                None => ClientOptions::new_client_options(storage),
+            };
+            let mut secrets_manager = SecretsManager::new(options).map_err(|err| global_secrets_manager_runtime::GsmError::Credentials {
+                secret: #label.to_owned(),
+                source: format!("the device is not bound to Keeper, set `{}` to a one-time access token: {}", #token_env, err).into(),
+            })?;
+            let record = secrets_manager
+                .get_secrets(vec![#record.to_owned()])
+                .map_err(service)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| global_secrets_manager_runtime::GsmError::NotFound { secret: #label.to_owned() })?;
+            let mut values = Vec::new();
+            for name in [#(#names),*] {
+                let value = record
+                    .get_custom_field_value(name, true)
+                    .or_else(|_| record.get_standard_field_value(name, true));
+                match value {
+                    Ok(value) if value.is_null() => {}
+                    Ok(value) => match value.as_str() {
+                        Some(value) => values.push((name.to_owned(), value.to_owned())),
+                        None => values.push((name.to_owned(), value.to_string())),
+                    },
+                    Err(_) => {}
+                }
+            }
+            Ok(values)
+        };
+        let values = global_secrets_manager_runtime::tokio::task::spawn_blocking(read)
+            .await
+            .map_err(|err| global_secrets_manager_runtime::GsmError::Service {
+                secret: #label.to_owned(),
+                source: err.into(),
+            })??;
+        Ok(Self::from_strings(values))
+    })
+}
@@ -0,0 +1,82 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Result;
+
+use super::Target;
+use crate::attr::ContainerAttrs;
+use crate::expand;
+use crate::schema;
+
+/// Statements replacing the base64 KMS ciphertexts of the fields marked `#[gsm(kms_decrypt)]` by their plaintext in `rt_str`, before the payload is deserialized.
+///
+/// A client of another service can't be built from `client`, so the KMS client gets the `shared_config` of the type, with its endpoint and `role_chain`.
+pub fn decrypt(target: &Target) -> Result<TokenStream> {
+    let keys = schema::schema(target.input, target.fields)?.keys;
+    let names: Vec<_> = target
+        .fields
+        .named
+        .iter()
+        .zip(target.field_attrs)
+        .filter(|(_, attrs)| attrs.kms_decrypt.is_some() && attrs.lazy_decode.is_none())
+        .filter_map(|(field, _)| {
+            keys.iter()
+                .find(|key| Some(&key.field) == field.ident.as_ref())
+        })
+        .flat_map(|key| &key.names)
+        .collect();
+    if names.is_empty() {
+        return Ok(TokenStream::new());
+    }
+    let secret_name = target.secret_name;
+    let base64 = expand::base64_decode();
+    let shared_config = expand::shared_config(target.container);
+    Ok(quote! {
+        let rt_str = {
+            #base64
+
+            let mut payload: global_secrets_manager_runtime::serde_json::Value = global_secrets_manager_runtime::serde_json::from_str(&rt_str).unwrap_or(global_secrets_manager_runtime::serde_json::Value::Null);
+            match payload.as_object_mut() {
+                Some(object) => {
+                    #shared_config
+                    let shared_config = match client.config().region() {
+                        Some(region) => shared_config.to_builder().region(region.clone()).build(),
+                        None => shared_config,
+                    };
+                    let kms = aws_sdk_kms::Client::new(&shared_config);
+                    for name in [#(#names),*] {
+                        let ciphertext = match object.get(name) {
+                            Some(global_secrets_manager_runtime::serde_json::Value::String(ciphertext)) => ciphertext,
+                            _ => continue,
+                        };
+                        let blob = base64(ciphertext).ok_or_else(|| global_secrets_manager_runtime::GsmError::decode(#secret_name, format!("key `{}` is not a base64 ciphertext", Self::key_name(name))))?;
+                        let resp = kms.decrypt().ciphertext_blob(aws_sdk_kms::primitives::Blob::new(blob)).send().await?;
+                        let plaintext = resp.plaintext().ok_or_else(|| format!("secret `{}`: KMS returned no plaintext for key `{}`", #secret_name, Self::key_name(name)))?;
+                        let plaintext = String::from_utf8(plaintext.as_ref().to_vec())
+                            .map_err(|_| global_secrets_manager_runtime::GsmError::decode(#secret_name, format!("the plaintext of key `{}` is not UTF-8", Self::key_name(name))))?;
+                        object.insert(name.to_owned(), global_secrets_manager_runtime::serde_json::Value::String(plaintext));
+                    }
+                    global_secrets_manager_runtime::serde_json::to_string(&payload)?
+                }
+                // Left to serde.
+                None => rt_str,
+            }
+        };
+    })
+}
+
+/// Statements decrypting the bytes `blob` of the ciphertext of `name` into `plaintext`, for the fields marked `#[gsm(lazy_decode)]`.
+///
+/// The value holds no client by then, so the KMS client gets the `shared_config` of the type, as in [`decrypt`].
+pub fn decrypt_lazily(secret_name: &str, container: &ContainerAttrs) -> TokenStream {
+    let shared_config = expand::shared_config(container);
+    quote! {
+        #shared_config
+        let kms = aws_sdk_kms::Client::new(&shared_config);
+        let resp = kms.decrypt().ciphertext_blob(aws_sdk_kms::primitives::Blob::new(blob)).send().await?;
+        let plaintext = resp
+            .plaintext()
+            .ok_or_else(|| format!("secret `{}`: KMS returned no plaintext for field `{}`", #secret_name, Self::key_name(name)))?
+            .as_ref()
+            .to_vec();
+    }
+}
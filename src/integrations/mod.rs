@@ -0,0 +1,218 @@
+//! Code generated for third-party crates, each behind the feature of the same name.
+//! The generated code refers to these crates by name, so they only need to be dependencies of the application.
+
+use proc_macro2::TokenStream;
+use syn::{DeriveInput, FieldsNamed, Ident};
+
+use crate::attr::{ContainerAttrs, FieldAttrs};
+
+#[cfg(feature = "actix-web")]
+mod actix;
+#[cfg(feature = "akeyless")]
+mod akeyless;
+#[cfg(feature = "appconfig")]
+mod appconfig;
+#[cfg(feature = "axum")]
+mod axum;
+#[cfg(feature = "bitwarden")]
+mod bitwarden;
+#[cfg(feature = "clap")]
+mod clap;
+#[cfg(feature = "config")]
+mod config;
+#[cfg(feature = "conjur")]
+mod conjur;
+#[cfg(feature = "figment")]
+mod figment;
+#[cfg(feature = "gcp-kms-file")]
+mod gcp_kms_file;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "gzip")]
+mod gzip;
+#[cfg(feature = "http-json")]
+mod http_json;
+#[cfg(feature = "keeper")]
+mod keeper;
+#[cfg(feature = "kms")]
+pub mod kms;
+#[cfg(feature = "reqwest-middleware")]
+mod reqwest;
+#[cfg(feature = "rocket")]
+pub mod rocket;
+#[cfg(feature = "s3")]
+mod s3;
+#[cfg(feature = "sqlx")]
+mod sqlx;
+#[cfg(feature = "validator")]
+mod validator;
+
+/// What the integrations need to know about a struct holding secrets.
+#[allow(dead_code)]
+pub struct Target<'a> {
+    pub input: &'a DeriveInput,
+    pub fields: &'a FieldsNamed,
+    /// The options of each field, in the order of `fields`.
+    pub field_attrs: &'a [FieldAttrs],
+    /// The type stored in the global constant.
+    pub ty: &'a TokenStream,
+    pub secret_name: &'a str,
+    pub container: &'a ContainerAttrs,
+}
+
+/// The items of every enabled integration.
+///
+/// Those handing out `&'static` references to the global constant are left out when it is stored in a lock by `#[gsm(storage = "rwlock")]`.
+#[allow(unused_variables, unused_mut)]
+pub fn expand(target: &Target) -> TokenStream {
+    let mut expanded = TokenStream::new();
+    // The integrations handing out the global constant need one, borrowed for the whole program.
+    let global = target.container.no_static.is_none();
+    let borrowed = global && !target.container.rwlock();
+    #[cfg(feature = "figment")]
+    expanded.extend(figment::expand(target));
+    #[cfg(feature = "config")]
+    expanded.extend(config::expand(target));
+    #[cfg(feature = "clap")]
+    if borrowed {
+        expanded.extend(clap::expand(target));
+    }
+    #[cfg(feature = "axum")]
+    if borrowed {
+        expanded.extend(axum::expand(target));
+    }
+    #[cfg(feature = "actix-web")]
+    if borrowed {
+        expanded.extend(actix::expand(target));
+    }
+    #[cfg(feature = "sqlx")]
+    expanded.extend(sqlx::expand(target));
+    #[cfg(feature = "reqwest-middleware")]
+    if borrowed {
+        expanded.extend(reqwest::expand(target));
+    }
+    expanded
+}
+
+/// The body of the raw fetch, awaiting the future `fetch` with `secret_id` in scope, from the backend named `backend`,
+/// through the runtime, which records it with the `metrics` and `opentelemetry` features of its own.
+pub fn fetch(fetch: TokenStream, backend: &str) -> TokenStream {
+    quote::quote!(global_secrets_manager_runtime::telemetry::fetch(secret_id, #backend, &attempts, #fetch).await)
+}
+
+/// The body of `get_secret()` running the load `load`, made of the attempts of `fetch()` and the delays between them, with `secret_id` in scope,
+/// through the runtime, which records it with its `tracing` feature.
+pub fn load(load: TokenStream, backend: &str) -> TokenStream {
+    quote::quote! {
+        let attempts = global_secrets_manager_runtime::telemetry::Attempts::default();
+        global_secrets_manager_runtime::telemetry::load(secret_id, #backend, &attempts, async { #load }).await
+    }
+}
+
+/// The body of `fetch_secret()` reading the payload from the backend of the struct, with `client` and `secret_id` in scope,
+/// or `None` when it reads Secrets Manager.
+#[allow(unused_variables)]
+pub fn backend(
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    container: &ContainerAttrs,
+) -> syn::Result<Option<TokenStream>> {
+    let backend = match &container.backend {
+        Some(backend) => backend,
+        None => return Ok(None),
+    };
+    match backend {
+        #[cfg(feature = "appconfig")]
+        crate::attr::Backend::AppConfig(config) => Ok(Some(appconfig::fetch(config))),
+        #[cfg(feature = "s3")]
+        crate::attr::Backend::S3(_) => Ok(Some(s3::fetch(container))),
+        #[cfg(feature = "bitwarden")]
+        crate::attr::Backend::Bitwarden(bitwarden) => Ok(Some(bitwarden::fetch(bitwarden))),
+        #[cfg(feature = "conjur")]
+        crate::attr::Backend::Conjur(conjur) => Ok(Some(conjur::fetch(input, fields, conjur)?)),
+        #[cfg(feature = "akeyless")]
+        crate::attr::Backend::Akeyless(_) => Ok(Some(akeyless::fetch(container))),
+        #[cfg(feature = "keeper")]
+        crate::attr::Backend::Keeper(keeper) => Ok(Some(keeper::fetch(input, fields, keeper)?)),
+        #[cfg(feature = "gcp-kms-file")]
+        crate::attr::Backend::GcpKmsFile(file) => Ok(Some(gcp_kms_file::fetch(file))),
+        #[cfg(feature = "http-json")]
+        crate::attr::Backend::HttpJson(http) => Ok(Some(http_json::fetch(http))),
+        #[cfg(feature = "grpc")]
+        crate::attr::Backend::Grpc(grpc) => Ok(Some(grpc::fetch(grpc))),
+        #[allow(unreachable_patterns)]
+        _ => Err(syn::Error::new_spanned(
+            backend.path(),
+            format!(
+                "this option needs the `{}` feature of global-secrets-manager",
+                backend.feature()
+            ),
+        )),
+    }
+}
+
+/// The statements turning the response `resp` of `GetSecretValue` into the payload, when an integration reads it instead of the string value.
+pub fn secret_value() -> Option<TokenStream> {
+    #[cfg(feature = "gzip")]
+    return Some(gzip::secret_value());
+    #[cfg(not(feature = "gzip"))]
+    None
+}
+
+/// Statements run when the global constant `ident` of type `ty` is found already loaded, counted by the `metrics` feature of the runtime.
+pub fn cache_hit(ident: &Ident, ty: &TokenStream) -> TokenStream {
+    let name = ident.to_string();
+    quote::quote!(global_secrets_manager_runtime::telemetry::cache_hit(#name, <#ty>::loaded_at());)
+}
+
+/// Statements run by a read of the global constant at `lazy_ref`, before it is loaded if needed, which find it already loaded or not.
+pub fn read_hit(ident: &Ident, ty: &TokenStream, lazy_ref: &TokenStream) -> TokenStream {
+    let hit = cache_hit(ident, ty);
+    quote::quote! {
+        if global_secrets_manager_runtime::once_cell::sync::Lazy::get(#lazy_ref).is_some() {
+            #hit
+        }
+    }
+}
+
+/// Statements run when the global constant `ident` of type `ty` is loaded or replaced.
+pub fn refreshed(ident: &Ident, ty: &TokenStream) -> TokenStream {
+    let name = ident.to_string();
+    quote::quote!(global_secrets_manager_runtime::telemetry::refreshed(#name, <#ty>::loaded_at());)
+}
+
+/// The items of the type of the global constant that the statements of `cache_hit()` and `refreshed()` use.
+pub fn global_items() -> TokenStream {
+    quote::quote! {
+        /// When the global constant was last loaded or replaced, for the `gsm_refresh_staleness_seconds` gauge.
+        fn loaded_at() -> &'static global_secrets_manager_runtime::telemetry::LoadedAt {
+            static LOADED_AT: global_secrets_manager_runtime::telemetry::LoadedAt = std::sync::Mutex::new(None);
+            &LOADED_AT
+        }
+    }
+}
+
+/// Statements rewriting the payload `rt_str` fetched by `get_with_client()`, before it is deserialized.
+#[allow(unused_variables, unused_mut)]
+pub fn payload(target: &Target) -> syn::Result<TokenStream> {
+    let mut statements = TokenStream::new();
+    #[cfg(feature = "kms")]
+    statements.extend(kms::decrypt(target)?);
+    Ok(statements)
+}
+
+/// Statements run by `validate_secret()` with the deserialized secret as `self`.
+#[allow(unused_variables, unused_mut)]
+pub fn validate(secret_name: &str) -> TokenStream {
+    let mut checks = TokenStream::new();
+    #[cfg(feature = "validator")]
+    checks.extend(validator::validate(secret_name));
+    checks
+}
+
+/// A warning with the format arguments `args`, as a tracing event with the `tracing` feature of the runtime, or on the standard error.
+pub fn warning(args: TokenStream) -> TokenStream {
+    quote::quote!(global_secrets_manager_runtime::telemetry::warning(
+        format_args!(#args)
+    ))
+}
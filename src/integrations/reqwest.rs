@@ -0,0 +1,74 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use super::Target;
+
+/// A reqwest-middleware middleware setting the headers of the fields marked `#[gsm(bearer)]` or `#[gsm(header = "...")]`.
+/// The expansion of `#[async_trait]` is written out so that the application does not need async-trait.
+pub fn expand(target: &Target) -> TokenStream {
+    let Target {
+        input,
+        fields,
+        field_attrs,
+        ty,
+        ..
+    } = target;
+    let (names, headers): (Vec<_>, Vec<_>) = fields
+        .named
+        .iter()
+        .zip(field_attrs.iter())
+        .filter_map(|(field, attrs)| Some((&field.ident, attrs.header.as_ref()?)))
+        .unzip();
+    if headers.is_empty() {
+        return TokenStream::new();
+    }
+    let vis = &input.vis;
+    let auth = format_ident!("{}Auth", input.ident);
+    let doc = format!(
+        "Sets the headers of the secret `{}` on every request.",
+        target.secret_name
+    );
+    let header_names = headers.iter().map(|header| &header.name);
+    let formats = headers
+        .iter()
+        .map(|header| if header.bearer { "Bearer {}" } else { "{}" });
+    quote! {
+        #[doc = #doc]
+        /// The value is read from the global constant each time, so clients built with it never need to be rebuilt.
+        #[derive(Clone, Copy, Debug, Default)]
+        #vis struct #auth;
+
+        impl #ty {
+            /// The middleware to give to `reqwest_middleware::ClientBuilder::with`.
+            pub fn auth_middleware() -> #auth {
+                #auth
+            }
+        }
+
+        impl reqwest_middleware::Middleware for #auth {
+            fn handle<'life0, 'life1, 'life2, 'async_trait>(
+                &'life0 self,
+                mut req: reqwest::Request,
+                extensions: &'life1 mut http::Extensions,
+                next: reqwest_middleware::Next<'life2>,
+            ) -> std::pin::Pin<Box<dyn std::future::Future<Output = reqwest_middleware::Result<reqwest::Response>> + Send + 'async_trait>>
+            where
+                'life0: 'async_trait,
+                'life1: 'async_trait,
+                'life2: 'async_trait,
+                Self: 'async_trait,
+            {
+                Box::pin(async move {
+                    let secrets = <#ty>::preload().await;
+                    #(
+                        let mut value = reqwest::header::HeaderValue::try_from(format!(#formats, secrets.#names))
+                            .map_err(reqwest_middleware::Error::middleware)?;
+                        value.set_sensitive(true);
+                        req.headers_mut().insert(#header_names, value);
+                    )*
+                    next.run(req, extensions).await
+                })
+            }
+        }
+    }
+}
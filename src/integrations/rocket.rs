@@ -0,0 +1,22 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::preload::{self, PreloadAll};
+
+/// `rocket_fairing!(Type, ...)`, one fairing loading the secrets of all the types registered with it at ignition, as `preload_all!` does,
+/// so that a Rocket application fails at boot with the report of every secret that couldn't be loaded, rather than mid-request.
+pub fn fairing(input: PreloadAll) -> TokenStream {
+    let preload = preload::expand(input);
+    let warning = super::warning(quote!("{}", err));
+    quote! {
+        rocket::fairing::AdHoc::try_on_ignite("Secrets", |rocket| async move {
+            match #preload.await {
+                Ok(()) => Ok(rocket),
+                Err(err) => {
+                    #warning;
+                    Err(rocket)
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,37 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::attr::ContainerAttrs;
+
+/// The body of `fetch_secret()` reading the object of `backend = "s3"` with aws-sdk-s3, for payloads larger than Secrets Manager holds.
+///
+/// S3 decrypts the objects encrypted with SSE-KMS as it returns them, so the credentials need `kms:Decrypt` on their key too.
+/// The client is built as the KMS one is, from the default configuration in the region of the Secrets Manager client.
+pub fn fetch(container: &ContainerAttrs) -> TokenStream {
+    let (bucket, key) = (&container.bucket, &container.key);
+    let label = container.backend_source().unwrap_or_default();
+    quote! {
+        let _ = secret_id;
+        let mut config = global_secrets_manager_runtime::config_loader();
+        if let Some(region) = client.config().region() {
+            config = config.region(region.clone());
+        }
+        let s3 = aws_sdk_s3::Client::new(&config.load().await);
+        let resp = s3
+            .get_object()
+            .bucket(#bucket)
+            .key(#key)
+            .send()
+            .await
+            .map_err(|err| {
+                let code = aws_sdk_s3::error::ProvideErrorMetadata::code(&err).map(str::to_owned);
+                global_secrets_manager_runtime::GsmError::service(#label, code.as_deref(), err.into())
+            })?;
+        let body = resp.body.collect().await.map_err(|err| global_secrets_manager_runtime::GsmError::Transport {
+            secret: #label.to_owned(),
+            source: err.into(),
+        })?;
+        Ok(String::from_utf8(body.into_bytes().to_vec())
+            .map_err(|_| global_secrets_manager_runtime::GsmError::decode(#label, "the object is not UTF-8"))?)
+    }
+}
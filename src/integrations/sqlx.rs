@@ -0,0 +1,64 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::Target;
+use crate::expand::{find_field, percent_encode};
+
+/// Connection helpers for structs holding database credentials, recognized by the keys of the secrets RDS manages:
+/// `host`, `username` (or `user`) and `password`, optionally with `port`, `dbname` (or `database`) and `engine`.
+pub fn expand(target: &Target) -> TokenStream {
+    let Target { fields, ty, .. } = target;
+    let field = |names: &[&str]| find_field(fields, names);
+    let (Some(host), Some(username), Some(password)) = (
+        field(&["host"]),
+        field(&["username", "user"]),
+        field(&["password"]),
+    ) else {
+        return TokenStream::new();
+    };
+    let port = field(&["port"]);
+    let dbname = field(&["dbname", "database"]);
+
+    let engine = match field(&["engine"]) {
+        Some(engine) => quote!(self.#engine.to_string()),
+        None => quote!(String::from("postgres")),
+    };
+    let port_url = port.map(|port| quote!(url.push_str(&format!(":{}", self.#port));));
+    let dbname_url = dbname
+        .map(|dbname| quote!(url.push_str(&format!("/{}", encode(&self.#dbname.to_string())));));
+    let encode = percent_encode();
+    let port_options = port.map(|port| quote!(let options = options.port(self.#port);));
+    let dbname_options =
+        dbname.map(|dbname| quote!(let options = options.database(&self.#dbname);));
+
+    quote! {
+        impl #ty {
+            /// The connection URL of the database, with the credentials percent-encoded.
+            /// The scheme is the `engine` key when there is one, and `postgres` otherwise.
+            pub fn database_url(&self) -> String {
+                #encode
+                let mut url = format!(
+                    "{}://{}:{}@{}",
+                    #engine,
+                    encode(&self.#username.to_string()),
+                    encode(&self.#password.to_string()),
+                    self.#host,
+                );
+                #port_url
+                #dbname_url
+                url
+            }
+
+            /// The options to connect to the database with `sqlx::PgPool::connect_with`.
+            pub fn to_pg_connect_options(&self) -> sqlx::postgres::PgConnectOptions {
+                let options = sqlx::postgres::PgConnectOptions::new()
+                    .host(&self.#host)
+                    .username(&self.#username)
+                    .password(&self.#password);
+                #port_options
+                #dbname_options
+                options
+            }
+        }
+    }
+}
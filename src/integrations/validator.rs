@@ -0,0 +1,39 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// A call to `validator::Validate` when the struct implements it.
+/// Derive macros don't see the derives of their siblings, so the implementation is detected by autoref specialization instead.
+/// Only the fields and the codes of the failed rules are reported, as the errors of validator carry the values.
+pub fn validate(secret_name: &str) -> TokenStream {
+    quote! {
+        struct Checked<'a, T>(&'a T);
+        trait Validated {
+            fn failed(&self) -> Option<validator::ValidationErrors>;
+        }
+        impl<T: validator::Validate> Validated for &Checked<'_, T> {
+            fn failed(&self) -> Option<validator::ValidationErrors> {
+                validator::Validate::validate(self.0).err()
+            }
+        }
+        trait Unvalidated {
+            fn failed(&self) -> Option<validator::ValidationErrors>;
+        }
+        impl<T> Unvalidated for Checked<'_, T> {
+            fn failed(&self) -> Option<validator::ValidationErrors> {
+                None
+            }
+        }
+        if let Some(errors) = (&&Checked(self)).failed() {
+            let mut failed: Vec<String> = errors
+                .field_errors()
+                .into_iter()
+                .map(|(field, errors)| {
+                    let codes: Vec<String> = errors.iter().map(|error| error.code.to_string()).collect();
+                    format!("`{}` ({})", Self::key_name(&field), codes.join(", "))
+                })
+                .collect();
+            failed.sort();
+            return Err(global_secrets_manager_runtime::GsmError::validation(#secret_name, failed.join(", ")).into());
+        }
+    }
+}
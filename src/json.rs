@@ -0,0 +1,177 @@
+//! A scanner of JSON objects reading their keys and the kinds of their values, but never the values themselves.
+//! Shared by the compile-time check and `gsm-codegen`, which can't depend on serde_json.
+
+/// What a value of the object is, with the kinds of the items of arrays and the keys of nested objects.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    String,
+    Integer,
+    Float,
+    Bool,
+    Null,
+    Array(Vec<Kind>),
+    Object(Vec<(String, Kind)>),
+}
+
+/// The keys of a JSON object with the kinds of their values, in order.
+pub fn object(json: &str) -> Option<Vec<(String, Kind)>> {
+    let mut parser = Parser {
+        bytes: json.trim().as_bytes(),
+        pos: 0,
+    };
+    let entries = parser.object()?;
+    (parser.pos == parser.bytes.len()).then_some(entries)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let eaten = self.bytes.get(self.pos) == Some(&byte);
+        if eaten {
+            self.pos += 1;
+        }
+        eaten
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.eat(byte).then_some(())
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        let mut escaped = false;
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            self.pos += 1;
+            match byte {
+                b'\\' if !escaped => escaped = true,
+                b'"' if !escaped => {
+                    let raw = std::str::from_utf8(&self.bytes[start..self.pos - 1]).ok()?;
+                    return Some(unescape(raw));
+                }
+                _ => escaped = false,
+            }
+        }
+        None
+    }
+
+    fn object(&mut self) -> Option<Vec<(String, Kind)>> {
+        let mut entries = Vec::new();
+        self.expect(b'{')?;
+        if !self.eat(b'}') {
+            loop {
+                let key = self.string()?;
+                self.expect(b':')?;
+                entries.push((key, self.value()?));
+                if self.eat(b'}') {
+                    break;
+                }
+                self.expect(b',')?;
+            }
+        }
+        Some(entries)
+    }
+
+    fn array(&mut self) -> Option<Vec<Kind>> {
+        let mut items = Vec::new();
+        self.expect(b'[')?;
+        if !self.eat(b']') {
+            loop {
+                items.push(self.value()?);
+                if self.eat(b']') {
+                    break;
+                }
+                self.expect(b',')?;
+            }
+        }
+        Some(items)
+    }
+
+    fn value(&mut self) -> Option<Kind> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos)? {
+            b'"' => self.string().map(|_| Kind::String),
+            b'{' => self.object().map(Kind::Object),
+            b'[' => self.array().map(Kind::Array),
+            _ => {
+                let start = self.pos;
+                while self.bytes.get(self.pos).is_some_and(|byte| {
+                    !matches!(byte, b',' | b'}' | b']') && !byte.is_ascii_whitespace()
+                }) {
+                    self.pos += 1;
+                }
+                match &self.bytes[start..self.pos] {
+                    b"true" | b"false" => Some(Kind::Bool),
+                    b"null" => Some(Kind::Null),
+                    b"" => None,
+                    number if number.iter().any(|b| matches!(b, b'.' | b'e' | b'E')) => {
+                        Some(Kind::Float)
+                    }
+                    _ => Some(Kind::Integer),
+                }
+            }
+        }
+    }
+}
+
+/// Keys are compared with field names, so only the common escapes matter.
+fn unescape(raw: &str) -> String {
+    raw.replace("\\\"", "\"")
+        .replace("\\/", "/")
+        .replace("\\\\", "\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_values_keep_their_kinds() {
+        assert_eq!(
+            object(r#"{"db": {"port": 5432, "hosts": ["a", 1.5, null]}, "on": false}"#),
+            Some(vec![
+                (
+                    "db".to_owned(),
+                    Kind::Object(vec![
+                        ("port".to_owned(), Kind::Integer),
+                        (
+                            "hosts".to_owned(),
+                            Kind::Array(vec![Kind::String, Kind::Float, Kind::Null])
+                        ),
+                    ]),
+                ),
+                ("on".to_owned(), Kind::Bool),
+            ])
+        );
+    }
+
+    #[test]
+    fn escaped_keys_are_unescaped() {
+        assert_eq!(
+            object(r#"{"a\"b": "c\"}"}"#),
+            Some(vec![("a\"b".to_owned(), Kind::String)])
+        );
+    }
+
+    #[test]
+    fn only_whole_objects_are_read() {
+        assert_eq!(object("[1]"), None);
+        assert_eq!(object(r#"{"a": 1} {}"#), None);
+        assert_eq!(object(r#"{"a": [1, }"#), None);
+    }
+}
@@ -0,0 +1,190 @@
+//! The JSON Schema of the payload a struct reads, built from the types of its fields while expanding, for tools validating the secrets
+//! outside the application, such as the `jsonencode` of Terraform.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, FieldsNamed, GenericArgument, PathArguments, Result, Type};
+
+use crate::attr::ContainerAttrs;
+use crate::schema::{self, Schema};
+
+/// `json_schema()`, the JSON Schema of the secret, as a string built while expanding.
+pub fn expand(
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    ty: &TokenStream,
+    secret_name: &str,
+    container: &ContainerAttrs,
+) -> Result<TokenStream> {
+    let Schema {
+        keys,
+        open,
+        deny_unknown,
+        strict,
+        allow_extra,
+    } = schema::schema(input, fields)?;
+    let mut properties = Vec::new();
+    let mut required = Vec::new();
+    let mut any_of = Vec::new();
+    for key in &keys {
+        let field = fields
+            .named
+            .iter()
+            .find(|field| field.ident.as_ref() == Some(&key.field))
+            .unwrap();
+        let value = match key.ty {
+            Some(_) => type_schema(&field.ty),
+            None => "{}".to_owned(),
+        };
+        for name in &key.names {
+            properties.push(format!("{}:{}", string(name), value));
+        }
+        if !key.required {
+            continue;
+        }
+        if let [name] = &key.names[..] {
+            required.push(string(name));
+        } else {
+            // A key read under an alias too is present under either of its names.
+            let names = key
+                .names
+                .iter()
+                .map(|name| format!("{{\"required\":[{}]}}", string(name)));
+            any_of.push(format!(
+                "{{\"anyOf\":[{}]}}",
+                names.collect::<Vec<_>>().join(",")
+            ));
+        }
+    }
+
+    let mut schema = vec![
+        "\"$schema\":\"https://json-schema.org/draft/2020-12/schema\"".to_owned(),
+        format!("\"title\":{}", string(secret_name)),
+        "\"type\":\"object\"".to_owned(),
+        format!("\"properties\":{{{}}}", properties.join(",")),
+        format!("\"required\":[{}]", required.join(",")),
+    ];
+    if !any_of.is_empty() {
+        schema.push(format!("\"allOf\":[{}]", any_of.join(",")));
+    }
+    if (deny_unknown || strict) && !open {
+        if !allow_extra.is_empty() {
+            let patterns: Vec<_> = allow_extra
+                .iter()
+                .map(|glob| format!("{}:{{}}", string(&pattern(glob))))
+                .collect();
+            schema.push(format!("\"patternProperties\":{{{}}}", patterns.join(",")));
+        }
+        schema.push("\"additionalProperties\":false".to_owned());
+    }
+    let schema = format!("{{{}}}", schema.join(","));
+    let doc = match &container.pointer {
+        Some(pointer) => format!(
+            "The JSON Schema of the object at `{}` of the secret `{}`, which the fields are read from: its keys, their types and the required ones.",
+            pointer.value(),
+            secret_name
+        ),
+        None => format!(
+            "The JSON Schema of the secret `{}`: its keys, their types and the required ones.",
+            secret_name
+        ),
+    };
+
+    Ok(quote! {
+        impl #ty {
+            #[doc = #doc]
+            /// Keys of types the derive doesn't know, such as other structs, accept any value.
+            pub fn json_schema() -> &'static str {
+                #schema
+            }
+        }
+    })
+}
+
+/// The schema of the values of the type `ty`, or `{}`, accepting any value, for the types the derive doesn't know.
+fn type_schema(ty: &Type) -> String {
+    let path = match ty {
+        Type::Reference(reference) => return type_schema(&reference.elem),
+        Type::Paren(paren) => return type_schema(&paren.elem),
+        Type::Group(group) => return type_schema(&group.elem),
+        Type::Slice(slice) => return array(&slice.elem),
+        Type::Array(array_ty) => return array(&array_ty.elem),
+        Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return "{}".to_owned(),
+    };
+    let segment = match path.segments.last() {
+        Some(segment) => segment,
+        None => return "{}".to_owned(),
+    };
+    let args: Vec<&Type> = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    match (segment.ident.to_string().as_str(), &args[..]) {
+        ("String" | "str" | "PathBuf" | "Path" | "OsString", _) => {
+            "{\"type\":\"string\"}".to_owned()
+        }
+        ("char", _) => "{\"type\":\"string\",\"minLength\":1,\"maxLength\":1}".to_owned(),
+        ("bool", _) => "{\"type\":\"boolean\"}".to_owned(),
+        ("u8" | "u16" | "u32" | "u64" | "u128" | "usize", _) => {
+            "{\"type\":\"integer\",\"minimum\":0}".to_owned()
+        }
+        ("i8" | "i16" | "i32" | "i64" | "i128" | "isize", _) => "{\"type\":\"integer\"}".to_owned(),
+        ("f32" | "f64", _) => "{\"type\":\"number\"}".to_owned(),
+        ("Option", [inner]) => format!(
+            "{{\"anyOf\":[{},{{\"type\":\"null\"}}]}}",
+            type_schema(inner)
+        ),
+        ("Box" | "Arc" | "Rc" | "Cow", [inner]) => type_schema(inner),
+        ("Vec" | "VecDeque" | "HashSet" | "BTreeSet", [inner]) => array(inner),
+        ("HashMap" | "BTreeMap", [_, value]) => format!(
+            "{{\"type\":\"object\",\"additionalProperties\":{}}}",
+            type_schema(value)
+        ),
+        _ => "{}".to_owned(),
+    }
+}
+
+fn array(items: &Type) -> String {
+    format!("{{\"type\":\"array\",\"items\":{}}}", type_schema(items))
+}
+
+/// `value` as a JSON string.
+fn string(value: &str) -> String {
+    let mut string = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => string.push_str("\\\""),
+            '\\' => string.push_str("\\\\"),
+            c if (c as u32) < 0x20 => string.push_str(&format!("\\u{:04x}", c as u32)),
+            c => string.push(c),
+        }
+    }
+    string.push('"');
+    string
+}
+
+/// The regular expression of a glob of `allow_extra`, for `patternProperties`.
+fn pattern(glob: &str) -> String {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c if "\\^$.|+()[]{}".contains(c) => {
+                pattern.push('\\');
+                pattern.push(c);
+            }
+            c => pattern.push(c),
+        }
+    }
+    pattern.push('$');
+    pattern
+}
@@ -0,0 +1,110 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Error, FieldsNamed, Result};
+
+use crate::attr::{ContainerAttrs, Kind};
+use crate::expand::{find_field, percent_encode};
+
+/// Helpers for the kind of credentials given by `#[gsm(kind = "...")]`.
+pub fn expand(
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    ty: &TokenStream,
+    container: &ContainerAttrs,
+) -> Result<TokenStream> {
+    match container.kind {
+        None => Ok(TokenStream::new()),
+        Some(Kind::Redis) => redis(input, fields, ty),
+        Some(Kind::Kafka) => kafka(input, fields, ty),
+    }
+}
+
+/// `to_redis_url()`, from `host` and an auth token, optionally with `port`, `username`, `db` and `tls`.
+/// TLS is on unless a `tls` field says otherwise, since ElastiCache only accepts auth tokens over TLS.
+fn redis(input: &DeriveInput, fields: &FieldsNamed, ty: &TokenStream) -> Result<TokenStream> {
+    let field = |names: &[&str]| find_field(fields, names);
+    let (Some(host), Some(token)) = (
+        field(&["host"]),
+        field(&["auth_token", "token", "password"]),
+    ) else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`kind = \"redis\"` needs a `host` field and an `auth_token`, `token` or `password` field",
+        ));
+    };
+    let scheme = match field(&["tls"]) {
+        Some(tls) => quote!(if self.#tls { "rediss" } else { "redis" }),
+        None => quote!("rediss"),
+    };
+    let username = match field(&["username", "user"]) {
+        Some(username) => quote!(encode(&self.#username.to_string())),
+        None => quote!(""),
+    };
+    let port = field(&["port"]).map(|port| quote!(url.push_str(&format!(":{}", self.#port));));
+    let db = field(&["db"]).map(|db| quote!(url.push_str(&format!("/{}", self.#db));));
+    let encode = percent_encode();
+
+    Ok(quote! {
+        impl #ty {
+            /// The connection URL of the Redis server, with the auth token percent-encoded.
+            /// It is assembled from the current values on every call, so that a rotated token is picked up.
+            pub fn to_redis_url(&self) -> String {
+                #encode
+                let mut url = format!("{}://{}:{}@{}", #scheme, #username, encode(&self.#token.to_string()), self.#host);
+                #port
+                #db
+                url
+            }
+        }
+    })
+}
+
+/// `as_kafka_config()`, from the SASL `username` and `password`, optionally with `mechanism`, `security_protocol`,
+/// `bootstrap_servers` and `ssl_ca_location`. The defaults are those of MSK: SCRAM-SHA-512 over TLS.
+fn kafka(input: &DeriveInput, fields: &FieldsNamed, ty: &TokenStream) -> Result<TokenStream> {
+    let field = |names: &[&str]| find_field(fields, names);
+    let (Some(username), Some(password)) = (
+        field(&["sasl_username", "username"]),
+        field(&["sasl_password", "password"]),
+    ) else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`kind = \"kafka\"` needs a `username` and a `password` field",
+        ));
+    };
+    let value = |names: &[&str], default: &str| match field(names) {
+        Some(field) => quote!(self.#field.to_string()),
+        None => quote!(String::from(#default)),
+    };
+    let mechanism = value(&["sasl_mechanism", "mechanism"], "SCRAM-SHA-512");
+    let protocol = value(&["security_protocol"], "SASL_SSL");
+    let optional = [
+        (
+            "bootstrap.servers",
+            field(&["bootstrap_servers", "brokers"]),
+        ),
+        ("ssl.ca.location", field(&["ssl_ca_location"])),
+    ]
+    .into_iter()
+    .filter_map(|(key, field)| {
+        let field = field?;
+        Some(quote!(config.push((String::from(#key), self.#field.to_string()));))
+    });
+
+    Ok(quote! {
+        impl #ty {
+            /// The client configuration of the brokers as key/value pairs, to be set on an `rdkafka::ClientConfig`.
+            /// It is built from the current values on every call, so that rotated credentials are picked up.
+            pub fn as_kafka_config(&self) -> Vec<(String, String)> {
+                let mut config = vec![
+                    (String::from("security.protocol"), #protocol),
+                    (String::from("sasl.mechanism"), #mechanism),
+                    (String::from("sasl.username"), self.#username.to_string()),
+                    (String::from("sasl.password"), self.#password.to_string()),
+                ];
+                #(#optional)*
+                config
+            }
+        }
+    })
+}
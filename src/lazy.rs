@@ -0,0 +1,96 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{Error, FieldsNamed, Result, Type};
+
+use crate::attr::{ContainerAttrs, FieldAttrs};
+use crate::expand;
+
+/// `{field}_bytes()` for each field marked `#[gsm(lazy_decode)]`, decoding its base64 value at the first call, so that a large blob doesn't slow the load down.
+///
+/// The field must be a borrowed `&str`: it points into the leaked payload, which is never freed, so its address identifies the value in the cache of the decoded bytes.
+#[cfg_attr(not(feature = "kms"), allow(unused_variables))]
+pub fn expand(
+    fields: &FieldsNamed,
+    field_attrs: &[FieldAttrs],
+    ty: &TokenStream,
+    secret_name: &str,
+    container: &ContainerAttrs,
+) -> Result<TokenStream> {
+    let mut accessors = Vec::new();
+    for (field, attrs) in fields.named.iter().zip(field_attrs) {
+        if attrs.lazy_decode.is_none() {
+            continue;
+        }
+        let borrowed_str = matches!(&field.ty, Type::Reference(reference)
+            if matches!(&*reference.elem, Type::Path(path) if path.path.is_ident("str")));
+        if !borrowed_str {
+            return Err(Error::new_spanned(
+                &field.ty,
+                "`lazy_decode` needs a `&str` field, borrowed from the payload",
+            ));
+        }
+        let member = field.ident.as_ref().unwrap();
+        let name = member.unraw().to_string();
+        let accessor = format_ident!("{}_bytes", member.unraw());
+        let base64 = expand::base64_decode();
+        let decode = quote! {
+            #base64
+            let blob = base64(self.#member)
+                .ok_or_else(|| global_secrets_manager_runtime::GsmError::decode(#secret_name, format!("field `{}` is not valid base64", Self::key_name(name))))?;
+        };
+        let cache = quote! {
+            static DECODED: std::sync::Mutex<Vec<(usize, &'static [u8])>> = std::sync::Mutex::new(Vec::new());
+            let address = self.#member.as_ptr() as usize;
+            let cached = DECODED.lock().unwrap_or_else(std::sync::PoisonError::into_inner).iter().find(|(key, _)| *key == address).map(|(_, bytes)| *bytes);
+            if let Some(bytes) = cached {
+                return Ok(bytes);
+            }
+        };
+        let store = quote! {
+            let bytes: &'static [u8] = Box::leak(plaintext.into_boxed_slice());
+            let mut decoded = DECODED.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            // Another call may have decoded it in the meantime.
+            match decoded.iter().find(|(key, _)| *key == address) {
+                Some((_, bytes)) => Ok(*bytes),
+                None => {
+                    decoded.push((address, bytes));
+                    Ok(bytes)
+                }
+            }
+        };
+        #[cfg(feature = "kms")]
+        if attrs.kms_decrypt.is_some() {
+            let decrypt = crate::integrations::kms::decrypt_lazily(secret_name, container);
+            accessors.push(quote! {
+                /// The plaintext of the field, decrypted by KMS from its base64 ciphertext at the first call.
+                pub async fn #accessor(&self) -> Result<&'static [u8], Box<dyn std::error::Error + Send + Sync>> {
+                    let name = #name;
+                    #cache
+                    #decode
+                    #decrypt
+                    #store
+                }
+            });
+            continue;
+        }
+        accessors.push(quote! {
+            /// The bytes of the field, decoded from base64 at the first call.
+            pub fn #accessor(&self) -> Result<&'static [u8], Box<dyn std::error::Error + Send + Sync>> {
+                let name = #name;
+                #cache
+                #decode
+                let plaintext = blob;
+                #store
+            }
+        });
+    }
+    if accessors.is_empty() {
+        return Ok(TokenStream::new());
+    }
+    Ok(quote! {
+        impl #ty {
+            #(#accessors)*
+        }
+    })
+}
@@ -1,8 +1,8 @@
-//! 	# What is this?
+//! # What is this?
 //!
-//! This is a procedural macro for easy use of AWS Secrets Manager. 
-//! 	This code allows you to create a global constant of the same type as the name of Secrets Manager by simply creating a structure that matches the key pair set in Secrets Manager. 
-//! 	This way, you can access the secret values stored in Secrets Manager without writing any code to fetch them from AWS. 
+//! This is a procedural macro for easy use of AWS Secrets Manager.
+//! This code allows you to create a global constant of the same type as the name of Secrets Manager by simply creating a structure that matches the key pair set in Secrets Manager.
+//! This way, you can access the secret values stored in Secrets Manager without writing any code to fetch them from AWS.
 //!
 //! ## Pros:
 //! - Key pairs can be retrieved by simply defining a struct with the same structure as the key pair set in Secrets Manager
@@ -11,23 +11,63 @@
 //!
 //! # Example code
 //!
-//!     use global_secrets_manager::GlobalSecretsManager;
+//! ```ignore
+//! use global_secrets_manager::GlobalSecretsManager;
 //!
-//!     /// Please use the same name as Secrets Manager for the name of the structure
-//!     /// Please set the keys of Secrets Manager without any omission or excess
-//!     #[derive(GlobalSecretsManager)]
-//!     #[derive(Debug, serde::Deserialize)]
-//!     pub struct SampleSecrets{ 
-//!         key1: String,
-//!         key2: String,
-//!     }
+//! /// Please use the same name as Secrets Manager for the name of the structure
+//! /// Please set the keys of Secrets Manager without any omission or excess
+//! #[derive(GlobalSecretsManager)]
+//! #[derive(Debug, serde::Deserialize)]
+//! pub struct SampleSecrets{
+//!     key1: String,
+//!     key2: String,
+//! }
 //!
+//! fn main(){
+//!     dbg!(&SampleSecrets.key1); //-> value1
+//!     dbg!(&SampleSecrets.key2); //-> value2
+//! }
+//! ```
 //!
+//! # Generating the structure
 //!
-//!     fn main(){
-//!         dbg!(&SampleSecrets.key1); //-> value1
-//!         dbg!(&SampleSecrets.key2); //-> value2
-//!     }
+//! Instead of copying the keys of an existing secret by hand, `gsm-codegen` prints a structure for it, with the types of its values and `rename` for the keys that aren't field names.
+//! It fetches the secret with the `aws` command line, or reads it from the standard input with `--stdin`, and never prints the values.
+//! Nested objects get structures of their own, arrays the type of their items, and objects whose values are objects with the same keys, such as the settings of each service, are read as a `HashMap` of them.
+//!
+//! ```sh
+//! cargo install global-secrets-manager --bin gsm-codegen
+//! gsm-codegen prod/app-db AppDbSecrets
+//! ```
+//!
+//! # Attribute macro
+//!
+//! `#[global_secret]` does the same as the derive, and also adds the `serde::Deserialize` derive for you.
+//! The name of the secret can be given when it differs from the name of the structure.
+//!
+//! ```ignore
+//! use global_secrets_manager::global_secret;
+//!
+//! #[global_secret(name = "prod/sample")]
+//! pub struct SampleSecrets{
+//!     key1: String,
+//!     key2: String,
+//! }
+//! ```
+//!
+//! With the derive, the same option is written as `#[gsm(name = "prod/sample")]`.
+//!
+//! # Options
+//!
+//! The options of `#[gsm(...)]`, and `#[global_secret(...)]` which takes the same, are described in the documentation of the [`GlobalSecretsManager`](derive@GlobalSecretsManager) derive, also in `docs/reference.md`. In short:
+//!
+//! - Where the secret is read: `name`, `env(...)` for each environment, `role_chain` across accounts, `replicas` in other regions, `retry`, and backends other than Secrets Manager, such as AppConfig, S3 or Bitwarden.
+//! - How the payload is read: `extends` and `common` to share secrets, `deref_secret`, `pointer`, `alias`, `split`, `kms_decrypt`, the variables of `env = "..."` fields and `env_prefix`, and `validate`.
+//! - How the value is held: the global constant by default, `storage = "rwlock"` to replace it, `no_static` for libraries, `tenant` and `instances` for secrets named by a parameter.
+//! - What else is generated: `writable` to save and `rotate_now()` to rotate the secret, existence checks, health checks, exports to child processes, audits and metadata.
+//! - Other macros: `secrets!` declares a struct and its constant in one block, `preload_all!` loads several secrets at startup, and `assert_secret_schema!` checks a struct against the keys of its secret.
+//! - Checks: `strict` keys, schema drift, JSON Schema, pre-deploy and build-time checks, and fakes, dry runs and fault injection for tests.
+//! - Integrations, each behind a feature of its name: figment, config, clap, axum, actix-web, rocket, sqlx, reqwest-middleware, validator, regex, gzip and kms, and tracing, metrics and opentelemetry through the runtime crate.
 //!
 //! # Advance Preparation
 //!
@@ -36,15 +76,22 @@
 //!
 //! The following dependencies are required.
 //!
-//!     aws-config = "0.54.1"
-//!     aws-sdk-secretsmanager = "0.24.0"
-//!     once_cell = "1.17.0"
-//!     dotenvy = "0.15.6"
-//!     serde_json = "1.0.93"
-//!     tokio = { version = "1.21.2", features = ["full"] }
-//!     global-secrets-manager = "0.1.1"
+//! ```toml
+//! global-secrets-manager = "0.1.3"
+//! global-secrets-manager-runtime = "0.1.3"
+//! serde = { version = "1.0.152", features = ["derive"] }
+//! ```
+//!
+//! The generated code reaches the crates it calls, the SDK, once_cell, dotenvy, serde_json and tokio, through global-secrets-manager-runtime, which re-exports them.
+//! Only the current-thread runtime of tokio is needed, so that applications that don't use tokio otherwise don't compile its multi-threaded scheduler.
+//! serde is only needed to derive `Deserialize`: `#[global_secret]` derives it through the runtime crate, and structs deriving it themselves may do the same with `#[serde(crate = "global_secrets_manager_runtime::serde")]`.
 //!
-//! However, it is better to use the latest versions of them.
+//! The runtime crate re-exports the 1.x SDK by default, and calls it where its API differs between versions.
+//! Applications still on the SDK before 1.0 select aws-config 0.55 and aws-sdk-secretsmanager 0.28 with the `aws-sdk-v0` feature instead, so that upgrading the SDK is a change of feature:
+//!
+//! ```toml
+//! global-secrets-manager-runtime = { version = "0.1.3", default-features = false, features = ["aws-sdk-v0"] }
+//! ```
 //!
 //!
 //! ## AWS Secrets Manager settings
@@ -86,16 +133,20 @@
 //! Please obtain your credential information.
 //! If you are using AWS CLI, you can get it with the following command.
 //!
-//!     cat ~/.aws/credentials
+//! ```text
+//! cat ~/.aws/credentials
+//! ```
 //!
 //!
 //! ## .env settings
 //!
 //! Create a .env file in your repository and enter your credential information as follows.
 //!
-//!     AWS_ACCESS_KEY_ID=AAAAA
-//!     AWS_SECRET_ACCESS_KEY = BBBBB
-//!     AWS_REGION = CCCCC
+//! ```text
+//! AWS_ACCESS_KEY_ID=AAAAA
+//! AWS_SECRET_ACCESS_KEY = BBBBB
+//! AWS_REGION = CCCCC
+//! ```
 //!
 //!
 //!
@@ -103,58 +154,102 @@
 //!
 //! For the structure
 //!
-//!     struct SampleSecrets{
-//!         key1:String,
-//!         key2:String
-//!     }
+//! ```ignore
+//! struct SampleSecrets{
+//!     key1:String,
+//!     key2:String
+//! }
+//! ```
 //!
 //! the same name global constant
 //!
-//!     pub static SampleSecrets: once_cell::sync::Lazy<SampleSecrets> = once_cell::sync::Lazy::new(||SampleSecrets::get());
+//! ```ignore
+//! pub static SampleSecrets: once_cell::sync::Lazy<SampleSecrets> = once_cell::sync::Lazy::new(||SampleSecrets::init());
+//! ```
+//!
+//! is defined. This constant is initialized only once when it is first accessed, and it calls the init() method of the structure to fetch the secret values from AWS Secrets Manager.
 //!
-//! is defined. This constant is initialized only once when it is first accessed, and it calls the get() method of the structure to fetch the secret values from AWS Secrets Manager.
+//! Structures may have lifetime parameters, such as `struct SampleSecrets<'a>{ key1: &'a str }`. The constant then holds `SampleSecrets<'static>` and the borrowed fields point into the fetched payload, which is kept for the rest of the program. The other structures own their values, and their payloads are freed once read. Type and const parameters are not supported, since the constant needs a concrete type.
 
-use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+mod assert;
+mod attr;
+mod audit;
+mod builder;
+#[cfg(feature = "chaos")]
+mod chaos;
+#[cfg(feature = "compile-time-check")]
+mod check;
+mod common;
+mod deref;
+mod env;
+mod expand;
+mod inline;
+mod instance;
+mod integrations;
+#[cfg(feature = "compile-time-check")]
+mod json;
+#[cfg(feature = "json-schema")]
+mod json_schema;
+mod kind;
+mod lazy;
+mod manage;
+mod merge;
+mod preload;
+mod provider;
+mod registry;
+mod render;
+mod replica;
+mod schema;
+mod tenant;
+#[cfg(test)]
+mod tests;
+mod validate;
 
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, AttributeArgs, DeriveInput};
 
-#[proc_macro_derive(GlobalSecretsManager)]
-pub fn GlobalSecretsManager_derive(input: TokenStream) -> TokenStream {
-    let DeriveInput { ident, data, .. } = parse_macro_input!(input as DeriveInput);
-    let fields = match data {
-        syn::Data::Struct(data) => data.fields,
-        _ => panic!("Global_sm can only be applied to structs"),
-    };
+#[doc = include_str!("../docs/reference.md")]
+#[proc_macro_derive(GlobalSecretsManager, attributes(gsm))]
+pub fn global_secrets_manager_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand::derive(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    let field_names: Vec<_> = fields
-        .iter()
-        .map(|f| f.ident.as_ref().expect("expected named field"))
-        .collect();
-    let field_types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+/// Does the same as `#[derive(GlobalSecretsManager)]`, with the options of `#[gsm(...)]` as its arguments, and derives `serde::Deserialize` too.
+#[proc_macro_attribute]
+pub fn global_secret(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let input = parse_macro_input!(input as DeriveInput);
+    expand::attribute(args, input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    let expanded = quote! {
-        pub static #ident: once_cell::sync::Lazy<#ident> = once_cell::sync::Lazy::new(||#ident::get());
+#[proc_macro]
+pub fn secrets(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as inline::Secrets);
+    inline::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-        impl #ident {
-            async fn get_secret() -> String {
-                let shared_config = aws_config::from_env().load().await;
-                let client = aws_sdk_secretsmanager::Client::new(&shared_config);
-                let resp = client.get_secret_value().secret_id(std::any::type_name::<Self>().split("::").last().unwrap()).send().await.unwrap();
-                let secret = resp.secret_string.unwrap();
-                secret
-            }
+#[proc_macro]
+pub fn assert_secret_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as assert::AssertSchema);
+    assert::expand(input).into()
+}
 
-            fn get() -> Self {
-                dotenvy::dotenv().ok();
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                let rt_str: String = rt.block_on(Self::get_secret());
-                let rt_bytes: &'static [u8] = Box::leak(rt_str.into_bytes().into_boxed_slice()); 
-                let secret_keys: Self = serde_json::from_slice(rt_bytes).unwrap(); 
-                secret_keys
-            }
-        }
-    };
+#[proc_macro]
+pub fn preload_all(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as preload::PreloadAll);
+    preload::expand(input).into()
+}
 
-    TokenStream::from(expanded)
+#[cfg(feature = "rocket")]
+#[proc_macro]
+pub fn rocket_fairing(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as preload::PreloadAll);
+    integrations::rocket::fairing(input).into()
 }
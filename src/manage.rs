@@ -0,0 +1,416 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::DeriveInput;
+
+use crate::attr::ContainerAttrs;
+use crate::expand;
+use crate::integrations;
+use crate::schema;
+
+/// Operations on the secret for tooling: the health check, its metadata and tags, the rotation, and the methods writing the secret for types marked `#[gsm(writable)]`.
+/// They call Secrets Manager, so the structs of other backends don't get them.
+pub fn expand(
+    input: &DeriveInput,
+    ty: &TokenStream,
+    secret_name: &str,
+    container: &ContainerAttrs,
+) -> TokenStream {
+    let vis = &input.vis;
+    let deserialize = schema::deserialize(
+        input,
+        secret_name,
+        quote!(&global_secrets_manager_runtime::serde_json::to_string(
+            &value
+        )?),
+    );
+    let metadata = format_ident!("{}Metadata", input.ident);
+    let metadata_doc = format!(
+        "What Secrets Manager knows about the secret `{}`, from `DescribeSecret`.",
+        secret_name
+    );
+    let health = format_ident!("{}Health", input.ident);
+    let (healthcheck_doc, load) = match &container.no_static {
+        Some(_) => (
+            "Fetches the secret, then checks that Secrets Manager answers for it, without reading its value again.",
+            quote!(Self::fetch().await),
+        ),
+        None => (
+            "Loads the global constant if needed, then checks that Secrets Manager answers for the secret, without reading its value again.",
+            quote!(Self::try_preload().await),
+        ),
+    };
+    let health_doc = format!(
+        "Whether the secret `{}` is loaded and Secrets Manager answers, for readiness probes.",
+        secret_name
+    );
+    let staleness = staleness(secret_name, container);
+    if container.backend.is_some() {
+        return quote! {
+            impl #ty {
+                #staleness
+            }
+        };
+    }
+    let shared_config = expand::shared_config(container);
+    let write_to = write_to(quote!(Self::secret_id(client).await?));
+    // Only the constant stored in a lock can be replaced; the immutable one may be borrowed for the rest of the program.
+    let (rotated_doc, reload) = if container.rwlock() {
+        let reloaded = schema::deserialize(input, secret_name, quote!(&rt_str));
+        let refreshed = integrations::refreshed(&input.ident, ty);
+        (
+            "The global constant, when it is loaded, is replaced by the rotated value.",
+            quote! {
+                let (rotated, rt_str) = Self::read_with_client(client).await?;
+                if Self::is_initialized() {
+                    let reloaded: Self = #reloaded?;
+                    reloaded.validate_secret()?;
+                    *Self::write() = reloaded;
+                    #refreshed
+                }
+                Ok(rotated)
+            },
+        )
+    } else {
+        (
+            "The global constant isn't refreshed: it keeps the value it was loaded with, which only the returned value replaces, unless it is stored in a lock by `#[gsm(storage = \"rwlock\")]`.",
+            quote!(Self::get_with_client(client).await),
+        )
+    };
+    let operations = quote! {
+        #[doc = #health_doc]
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        #vis enum #health {
+            /// The secret is loaded and Secrets Manager answers.
+            Ready,
+            /// The secret is loaded, but Secrets Manager doesn't answer, with the error. The loaded value is still served.
+            Degraded(String),
+            /// The secret can't be loaded, with the error.
+            Unavailable(String),
+        }
+
+        impl #health {
+            /// Whether the application can serve, which it can while the secret is loaded.
+            pub fn is_ready(&self) -> bool {
+                !matches!(self, Self::Unavailable(_))
+            }
+        }
+
+        #[doc = #metadata_doc]
+        #[derive(Clone, Debug)]
+        #vis struct #metadata {
+            /// Id of the version labelled `AWSCURRENT`.
+            pub version_id: Option<String>,
+            pub created: Option<std::time::SystemTime>,
+            pub last_changed: Option<std::time::SystemTime>,
+            pub last_rotated: Option<std::time::SystemTime>,
+            pub next_rotation: Option<std::time::SystemTime>,
+            pub rotation_enabled: bool,
+        }
+
+        impl #ty {
+            #staleness
+            #write_to
+
+            /// A client reaching the secret as the global constant does, with the name of application, the environment and the roles of `role_chain`, for `metadata()`, `tags()` and `rotate_now()`.
+            pub async fn sdk_client() -> Result<global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, Box<dyn std::error::Error + Send + Sync>> {
+                global_secrets_manager_runtime::dotenvy::dotenv().ok();
+                #shared_config
+                Ok(global_secrets_manager_runtime::aws_sdk_secretsmanager::Client::new(&shared_config))
+            }
+
+            #[doc = #healthcheck_doc]
+            pub async fn healthcheck() -> #health {
+                if let Err(err) = #load {
+                    return #health::Unavailable(err.to_string());
+                }
+                let described = async {
+                    let client = Self::sdk_client().await?;
+                    client.describe_secret().secret_id(Self::secret_id(&client).await?).send().await?;
+                    Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+                };
+                match described.await {
+                    Ok(()) => #health::Ready,
+                    Err(err) => #health::Degraded(err.to_string()),
+                }
+            }
+
+            /// The current version and the dates of the secret, such as when it was last rotated, for health dashboards.
+            /// `client` is usually the one of `sdk_client()`.
+            pub async fn metadata(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<#metadata, Box<dyn std::error::Error + Send + Sync>> {
+                let resp = client.describe_secret().secret_id(Self::secret_id(client).await?).send().await?;
+                let time = |date: Option<&_>| date.and_then(|date| std::time::SystemTime::try_from(*date).ok());
+                Ok(#metadata {
+                    version_id: resp.version_ids_to_stages().and_then(|versions| {
+                        versions
+                            .iter()
+                            .find(|(_, stages)| stages.iter().any(|stage| stage == "AWSCURRENT"))
+                            .map(|(version_id, _)| version_id.clone())
+                    }),
+                    created: time(resp.created_date()),
+                    last_changed: time(resp.last_changed_date()),
+                    last_rotated: time(resp.last_rotated_date()),
+                    next_rotation: time(resp.next_rotation_date()),
+                    rotation_enabled: resp.rotation_enabled().unwrap_or_default(),
+                })
+            }
+
+            /// The tags of the secret, as key and value pairs. `client` is usually the one of `sdk_client()`.
+            pub async fn tags(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<Vec<(String, String)>, Box<dyn std::error::Error + Send + Sync>> {
+                let resp = client.describe_secret().secret_id(Self::secret_id(client).await?).send().await?;
+                Ok(resp
+                    .tags()
+                    .iter()
+                    .map(|tag| (tag.key().unwrap_or_default().to_owned(), tag.value().unwrap_or_default().to_owned()))
+                    .collect())
+            }
+
+            /// Starts the rotation of the secret by its rotation function, and returns the value after it.
+            /// With `wait`, waits up to that long for the new version to become `AWSCURRENT`; otherwise the value returned may still be the previous one.
+            /// `client` is usually the one of `sdk_client()`.
+            #[doc = #rotated_doc]
+            pub async fn rotate_now(
+                client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client,
+                wait: Option<std::time::Duration>,
+            ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+                let secret_id = Self::secret_id(client).await?;
+                let resp = client.rotate_secret().secret_id(&secret_id).send().await?;
+                if let (Some(wait), Some(version_id)) = (wait, resp.version_id()) {
+                    let deadline = std::time::Instant::now() + wait;
+                    // The stages of the versions are described without reading the value at each poll.
+                    loop {
+                        let described = client.describe_secret().secret_id(&secret_id).send().await?;
+                        let stages = described.version_ids_to_stages().and_then(|versions| versions.get(version_id));
+                        if stages.is_some_and(|stages| stages.iter().any(|stage| stage == "AWSCURRENT")) {
+                            break;
+                        }
+                        if std::time::Instant::now() >= deadline {
+                            return Err(format!("rotation of secret `{}` did not finish in {:?}", #secret_name, wait).into());
+                        }
+                        global_secrets_manager_runtime::executor::sleep(std::time::Duration::from_secs(1)).await;
+                    }
+                }
+                #reload
+            }
+        }
+    };
+    if container.writable.is_none() {
+        return operations;
+    }
+    // `PutSecretValue` can't choose the key, but `UpdateSecret` writes the version with it.
+    let (write, kms_key_id) = match &container.kms_key_id {
+        Some(kms_key_id) => (
+            quote! {
+                client
+                    .update_secret()
+                    .secret_id(Self::secret_id(client).await?)
+                    .kms_key_id(#kms_key_id)
+            },
+            quote!(kms_key_id.or(Some(#kms_key_id))),
+        ),
+        None => (
+            quote! {
+                client
+                    .put_secret_value()
+                    .secret_id(Self::secret_id(client).await?)
+            },
+            quote!(kms_key_id),
+        ),
+    };
+    // The secret doesn't exist yet, so it can't be looked up by tags, but an environment names its own.
+    let name = if !container.environments.is_empty() {
+        quote!(Self::environment()?.0)
+    } else if container.instances.is_some() {
+        quote!(&Self::instance_name(None)?)
+    } else {
+        quote!(#secret_name)
+    };
+    quote! {
+        #operations
+
+        impl #ty {
+            /// Writes the value as a new version of the secret, labelled `AWSCURRENT`, and returns the id of the version.
+            /// The version is encrypted with the key of `#[gsm(kms_key_id = "...")]`, or with the key of the secret.
+            /// The global constant keeps the value it was loaded with.
+            pub async fn save(&self, client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                let resp = #write
+                    .secret_string(global_secrets_manager_runtime::serde_json::to_string(self)?)
+                    .send()
+                    .await?;
+                Ok(resp.version_id().unwrap_or_default().to_owned())
+            }
+
+            /// What `save()` would change, without writing anything: the keys added, changed and removed, never their values.
+            pub async fn save_dry_run(&self, client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+                let local = global_secrets_manager_runtime::serde_json::to_value(self)?;
+                let remote = global_secrets_manager_runtime::parse_json(#secret_name, &Self::get_secret(client, &Self::secret_id(client).await?).await?)?;
+                let (local, remote) = match (local.as_object(), remote.as_object()) {
+                    (Some(local), Some(remote)) => (local, remote),
+                    _ => return Err(format!("secret `{}` is not a JSON object", #secret_name).into()),
+                };
+                let mut changes = Vec::new();
+                for (key, value) in local {
+                    match remote.get(key) {
+                        None => changes.push(format!("added `{}`", key)),
+                        Some(remote) if remote != value => changes.push(format!("changed `{}`", key)),
+                        Some(_) => {}
+                    }
+                }
+                for key in remote.keys().filter(|key| !local.contains_key(*key)) {
+                    changes.push(format!("removed `{}`", key));
+                }
+                Ok(changes)
+            }
+
+            /// Replaces the string at `key` by a password generated by Secrets Manager with `options`, saves the result as `save()` does and returns it.
+            pub async fn regenerate_field(
+                &self,
+                client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client,
+                key: &str,
+                options: &global_secrets_manager_runtime::PasswordOptions,
+            ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+                let mut value = global_secrets_manager_runtime::serde_json::to_value(self)?;
+                let field = value
+                    .as_object_mut()
+                    .and_then(|object| object.get_mut(key))
+                    .filter(|field| field.is_string())
+                    .ok_or_else(|| format!("secret `{}` has no string key `{}`", #secret_name, Self::key_name(key)))?;
+                let password = global_secrets_manager_runtime::random_password(client, options).await?;
+                *field = global_secrets_manager_runtime::serde_json::Value::String(password);
+                let regenerated: Self = #deserialize?;
+                regenerated.save(client).await?;
+                Ok(regenerated)
+            }
+
+            /// Creates the secret from the value when it doesn't exist yet, such as at the first deployment, and returns whether it was created.
+            /// The secret is encrypted with `kms_key_id`, or with the key of `#[gsm(kms_key_id = "...")]`, or with the default key of the account.
+            pub async fn create_if_missing(
+                &self,
+                client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client,
+                tags: &[(&str, &str)],
+                kms_key_id: Option<&str>,
+            ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+                let secret_name = &Self::conventional_name(#name)?;
+                match client.get_secret_value().secret_id(secret_name).send().await {
+                    Ok(_) => return Ok(false),
+                    Err(err) => {
+                        let err = err.into_service_error();
+                        if !err.is_resource_not_found_exception() {
+                            return Err(err.into());
+                        }
+                    }
+                }
+                let mut create = client
+                    .create_secret()
+                    .name(secret_name)
+                    .secret_string(global_secrets_manager_runtime::serde_json::to_string(self)?)
+                    .set_kms_key_id(#kms_key_id.map(str::to_owned));
+                for (key, value) in tags {
+                    create = create.tags(global_secrets_manager_runtime::aws_sdk_secretsmanager::types::Tag::builder().key(*key).value(*value).build());
+                }
+                match create.send().await {
+                    Ok(_) => Ok(true),
+                    Err(err) => {
+                        // Someone else created it in the meantime.
+                        let err = err.into_service_error();
+                        if err.is_resource_exists_exception() {
+                            Ok(false)
+                        } else {
+                            Err(err.into())
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `write_secret_to()` and `write_secret_to_path()`, writing the value of the secret `secret_id` as it is stored, for bootstrap bundles and other large binary secrets.
+///
+/// The value is written from the buffer of the response, then zeroed, instead of being copied into a payload and a struct.
+/// The writes block, so they run on a thread of the executor meant for it.
+pub fn write_to(secret_id: TokenStream) -> TokenStream {
+    quote! {
+        /// Writes the value of the secret as it is stored, its binary or its string, to `writer` without parsing it, and returns the number of bytes written.
+        /// The whole value is fetched before it is written, on a thread where blocking doesn't stall the async runtime, and its buffer is zeroed once written.
+        pub async fn write_secret_to<W: std::io::Write + Send + 'static>(
+            client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client,
+            mut writer: W,
+        ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+            let secret_id = #secret_id;
+            let resp = client
+                .get_secret_value()
+                .secret_id(&secret_id)
+                .send()
+                .await
+                .map_err(|err| global_secrets_manager_runtime::fetch_error(&secret_id, err))?;
+            let mut bytes = match (resp.secret_binary, resp.secret_string) {
+                (Some(binary), _) => binary.into_inner(),
+                (None, Some(string)) => string.into_bytes(),
+                (None, None) => return Err(global_secrets_manager_runtime::GsmError::decode(&secret_id, "it has no value").into()),
+            };
+            let written = global_secrets_manager_runtime::executor::spawn_blocking(move || {
+                let written = writer.write_all(&bytes).and_then(|()| writer.flush());
+                let len = bytes.len() as u64;
+                // Through `black_box`, so that the zeroing of a buffer about to be freed isn't optimized out.
+                bytes.fill(0);
+                drop(std::hint::black_box(bytes));
+                written.map(|()| len)
+            });
+            Ok(written.await??)
+        }
+
+        /// Writes the value of the secret as it is stored to a new file at `path`, such as on a tmpfs, readable and writable only by its owner on Unix.
+        /// Fails if the file already exists.
+        pub async fn write_secret_to_path(
+            client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client,
+            path: impl AsRef<std::path::Path>,
+        ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+            let path = path.as_ref().to_owned();
+            let file = global_secrets_manager_runtime::executor::spawn_blocking(move || {
+                let mut options = std::fs::OpenOptions::new();
+                options.write(true).create_new(true);
+                #[cfg(unix)]
+                std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+                options.open(path)
+            });
+            Self::write_secret_to(client, file.await??).await
+        }
+    }
+}
+
+/// `check_staleness()`, called after each load, reporting secrets not rotated for longer than `#[gsm(max_age = "...")]`.
+/// Failures to describe the secret are ignored, since the secret itself was loaded.
+fn staleness(secret_name: &str, container: &ContainerAttrs) -> TokenStream {
+    let max_age = match container.max_age {
+        Some(max_age) => max_age,
+        None => {
+            return quote! {
+                async fn check_staleness(_client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) {}
+            }
+        }
+    };
+    let report = match &container.on_stale {
+        Some(hook) => quote!(#hook(#secret_name, age)),
+        None => integrations::warning(quote! {
+            "secret `{}` was last rotated {} days ago, more than the {} days allowed",
+            #secret_name,
+            age.as_secs() / 86400,
+            #max_age / 86400
+        }),
+    };
+    quote! {
+        async fn check_staleness(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) {
+            let metadata = match Self::metadata(client).await {
+                Ok(metadata) => metadata,
+                Err(_) => return,
+            };
+            let age = metadata
+                .last_rotated
+                .or(metadata.created)
+                .and_then(|date| std::time::SystemTime::now().duration_since(date).ok());
+            if let Some(age) = age.filter(|age| age.as_secs() > #max_age) {
+                #report;
+            }
+        }
+    }
+}
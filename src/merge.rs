@@ -0,0 +1,95 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Error, FieldsNamed, Result};
+
+use crate::attr::{ContainerAttrs, FieldAttrs};
+use crate::schema;
+
+/// `with_bases()`, merging the secrets of `#[gsm(extends = "...")]` and the struct's secret into one payload before it is read.
+///
+/// The secrets are merged in order, the struct's own secret last, so that a later secret overrides the keys of the earlier ones
+/// unless `merge = "first_wins"` keeps the first value, for the whole struct or for a field.
+pub fn expand(
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    field_attrs: &[FieldAttrs],
+    ty: &TokenStream,
+    secret_name: &str,
+    container: &ContainerAttrs,
+) -> Result<TokenStream> {
+    let needs_extends = container
+        .merge
+        .as_ref()
+        .or_else(|| field_attrs.iter().find_map(|attrs| attrs.merge.as_ref()));
+    if let (true, Some(merge)) = (container.extends.is_empty(), needs_extends) {
+        return Err(Error::new_spanned(
+            merge,
+            "`merge` needs the secrets of `extends = \"...\"` to merge",
+        ));
+    }
+    if container.extends.is_empty() {
+        return Ok(quote! {
+            impl #ty {
+                async fn with_bases(_client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, rt_str: String) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                    Ok(rt_str)
+                }
+            }
+        });
+    }
+
+    let keys = schema::schema(input, fields)?.keys;
+    let mut arms = Vec::new();
+    for (field, attrs) in fields.named.iter().zip(field_attrs) {
+        let merge = match &attrs.merge {
+            Some(merge) => merge,
+            None => continue,
+        };
+        let names = match keys
+            .iter()
+            .find(|key| Some(&key.field) == field.ident.as_ref())
+        {
+            Some(key) => &key.names,
+            None => {
+                return Err(Error::new_spanned(
+                    merge,
+                    "`merge` can't be set on a field that serde doesn't read from the secret",
+                ))
+            }
+        };
+        let first_wins = merge.value() == "first_wins";
+        arms.push(quote!(#(#names)|* => #first_wins,));
+    }
+    let first_wins = matches!(&container.merge, Some(merge) if merge.value() == "first_wins");
+    let bases = &container.extends;
+
+    Ok(quote! {
+        impl #ty {
+            /// Merges the keys of the secrets of `#[gsm(extends = "...")]` and of the struct's secret, in this order.
+            /// A key present in several secrets takes its last value, or its first one with `#[gsm(merge = "first_wins")]`.
+            async fn with_bases(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, rt_str: String) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                let mut sources = Vec::new();
+                for base in [#(#bases),*] {
+                    sources.push((base, Self::get_secret(client, &Self::conventional_name(base)?).await?));
+                }
+                sources.push((#secret_name, rt_str));
+                let mut merged = global_secrets_manager_runtime::serde_json::Map::new();
+                for (secret, source) in sources {
+                    let object = match global_secrets_manager_runtime::serde_json::from_str(&source) {
+                        Ok(global_secrets_manager_runtime::serde_json::Value::Object(object)) => object,
+                        _ => return Err(global_secrets_manager_runtime::GsmError::decode(secret, "it must be a JSON object to be merged").into()),
+                    };
+                    for (key, value) in object {
+                        let first_wins = match key.as_str() {
+                            #(#arms)*
+                            _ => #first_wins,
+                        };
+                        if !first_wins || !merged.contains_key(&key) {
+                            merged.insert(key, value);
+                        }
+                    }
+                }
+                Ok(global_secrets_manager_runtime::serde_json::Value::Object(merged).to_string())
+            }
+        }
+    })
+}
@@ -0,0 +1,73 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Ident, LitStr, Result, Token, TypePath};
+
+use crate::attr;
+
+/// Input of `preload_all!(Type, ...)` or `preload_all!(deadline = "30s", Type, ...)`.
+pub struct PreloadAll {
+    /// The deadline, in seconds.
+    deadline: u64,
+    types: Punctuated<TypePath, Token![,]>,
+}
+
+impl Parse for PreloadAll {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut deadline = 30;
+        if input.peek(Ident::peek_any) && input.peek2(Token![=]) {
+            let name: Ident = input.parse()?;
+            if name != "deadline" {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "expected `deadline = \"...\"`",
+                ));
+            }
+            input.parse::<Token![=]>()?;
+            deadline = attr::seconds(&input.parse::<LitStr>()?)?;
+            input.parse::<Option<Token![,]>>()?;
+        }
+        let types = Punctuated::parse_terminated(input)?;
+        if types.is_empty() {
+            return Err(input.error("expected the types whose secrets to preload"));
+        }
+        Ok(Self { deadline, types })
+    }
+}
+
+/// A future preloading the global constants of the types concurrently, failing with a report of every secret
+/// that couldn't be loaded, or wasn't loaded before the deadline, so that a misconfigured deployment fails at startup.
+pub fn expand(input: PreloadAll) -> TokenStream {
+    let PreloadAll { deadline, types } = input;
+    let tasks = types.iter().map(|ty| {
+        let name = quote!(#ty).to_string().replace(' ', "");
+        quote! {
+            (#name, global_secrets_manager_runtime::executor::spawn(async { <#ty>::try_preload().await.map(|_| ()) })),
+        }
+    });
+    let count = types.len();
+    quote! {
+        async {
+            let deadline = std::time::Duration::from_secs(#deadline);
+            let until = std::time::Instant::now() + deadline;
+            let tasks = [#(#tasks)*];
+            let mut failures: Vec<String> = Vec::new();
+            for (name, task) in tasks {
+                match global_secrets_manager_runtime::executor::until(until, task).await {
+                    Some(Ok(Ok(()))) => {}
+                    Some(Ok(Err(err))) => failures.push(format!("`{}`: {}", name, err)),
+                    Some(Err(err)) => failures.push(format!("`{}`: the preload panicked: {}", name, err)),
+                    None => failures.push(format!("`{}`: not loaded within {:?}", name, deadline)),
+                }
+            }
+            if failures.is_empty() {
+                Ok(())
+            } else {
+                let report = format!("{} of {} secrets failed to load:\n  {}", failures.len(), #count, failures.join("\n  "));
+                Err(Box::<dyn std::error::Error + Send + Sync>::from(report))
+            }
+        }
+    }
+}
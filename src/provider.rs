@@ -0,0 +1,64 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DeriveInput, FieldsNamed};
+
+use crate::attr::ContainerAttrs;
+use crate::audit;
+
+/// A trait with an accessor per field, implemented by the struct, so that application code can be written against the trait and tested with fakes.
+pub fn expand(
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    container: &ContainerAttrs,
+) -> TokenStream {
+    let DeriveInput {
+        vis,
+        ident,
+        generics,
+        ..
+    } = input;
+    let trait_ident = container
+        .provider
+        .clone()
+        .unwrap_or_else(|| format_ident!("{}Provider", ident));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let names: Vec<_> = fields.named.iter().map(|f| &f.ident).collect();
+    let types: Vec<_> = fields.named.iter().map(|f| &f.ty).collect();
+    let docs = names.iter().map(|name| {
+        format!(
+            "Value of the key `{}` of the secret.",
+            name.as_ref().unwrap()
+        )
+    });
+
+    let wrapper = audit::wrapper(ident);
+    let track_caller = container.audit.as_ref().map(|_| quote!(#[track_caller]));
+    let audits = names.iter().map(|name| {
+        let key = name.as_ref().unwrap().to_string();
+        container
+            .audit
+            .as_ref()
+            .map(|_| quote!(#wrapper::audit(Some(#key), std::panic::Location::caller());))
+    });
+
+    quote! {
+        #[doc = concat!("Accessors of [`", stringify!(#ident), "`], to write code that can be tested without AWS.")]
+        #vis trait #trait_ident #generics #where_clause {
+            #(
+                #[doc = #docs]
+                fn #names(&self) -> &#types;
+            )*
+        }
+
+        impl #impl_generics #trait_ident #ty_generics for #ident #ty_generics #where_clause {
+            #(
+                #track_caller
+                fn #names(&self) -> &#types {
+                    #audits
+                    &self.#names
+                }
+            )*
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Secrets bound by the structs expanded so far, to report two structs reading the same secret with conflicting options.
+//!
+//! The macro runs in a process that may expand several crates, as rust-analyzer does, so bindings are kept per crate,
+//! and a struct expanded again replaces its own binding.
+
+use std::sync::Mutex;
+
+use proc_macro2::Ident;
+use syn::{Error, LitStr, Result};
+
+use crate::attr::{ContainerAttrs, Environment};
+
+struct Binding {
+    krate: String,
+    /// The name of the secret, its tags when it is looked up by them, or what its backend reads.
+    secret: String,
+    ty: String,
+    options: Vec<(&'static str, String)>,
+}
+
+static BINDINGS: Mutex<Vec<Binding>> = Mutex::new(Vec::new());
+
+/// The options that two structs reading the same secret must agree on, as names and values, the unset ones being empty.
+///
+/// The ones reaching the secret differ when they read it from other regions, endpoints or accounts, or write it with another key.
+/// Two writable structs always conflict: saving one would drop the keys that only the other reads, as saving a single one
+/// that isn't `strict` drops the keys it doesn't model.
+fn options(ident: &Ident, container: &ContainerAttrs) -> Vec<(&'static str, String)> {
+    let joined = |values: &[LitStr]| {
+        values
+            .iter()
+            .map(LitStr::value)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let per_environment = |value: fn(&Environment) -> &Option<LitStr>| {
+        container
+            .environments
+            .iter()
+            .map(|environment| {
+                let value = value(environment).as_ref().map(LitStr::value);
+                format!(
+                    "{}: {}",
+                    environment.value.value(),
+                    value.unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut options = vec![
+        ("region", per_environment(|environment| &environment.region)),
+        (
+            "endpoint_url",
+            per_environment(|environment| &environment.endpoint_url),
+        ),
+        ("role_chain", joined(&container.role_chain)),
+        (
+            "app_name",
+            container
+                .app_name
+                .as_ref()
+                .map(LitStr::value)
+                .unwrap_or_default(),
+        ),
+        ("replicas", joined(&container.replicas)),
+        (
+            "kms_key_id",
+            container
+                .kms_key_id
+                .as_ref()
+                .map(LitStr::value)
+                .unwrap_or_default(),
+        ),
+    ];
+    if container.writable.is_some() {
+        options.push(("writable", ident.to_string()));
+    }
+    options
+}
+
+/// Records the secret bound by the struct `ident`, failing if another struct of the crate binds it with conflicting options.
+pub fn register(ident: &Ident, secret_name: &str, container: &ContainerAttrs) -> Result<()> {
+    let krate = std::env::var("CARGO_CRATE_NAME").unwrap_or_default();
+    let secret = match (&container.backend, &container.tags) {
+        (Some(backend), _) => format!(
+            "{} `{}`",
+            backend.name(),
+            container.backend_source().unwrap_or_default()
+        ),
+        (None, Some(tags)) => {
+            let tags: Vec<_> = tags
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            format!("tagged {}", tags.join(", "))
+        }
+        (None, None) => format!("`{}`", secret_name),
+    };
+    let binding = Binding {
+        krate,
+        secret,
+        ty: ident.to_string(),
+        options: options(ident, container),
+    };
+
+    let mut bindings = BINDINGS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    bindings.retain(|other| other.krate != binding.krate || other.ty != binding.ty);
+    for other in bindings
+        .iter()
+        .filter(|other| other.krate == binding.krate && other.secret == binding.secret)
+    {
+        let conflicts: Vec<_> = binding
+            .options
+            .iter()
+            .filter(|(name, value)| {
+                other
+                    .options
+                    .iter()
+                    .any(|(other_name, other_value)| other_name == name && other_value != value)
+            })
+            .map(|(name, _)| format!("`{}`", name))
+            .collect();
+        if !conflicts.is_empty() {
+            return Err(Error::new_spanned(
+                ident,
+                format!(
+                    "the secret {} is also read by `{}`, with conflicting options: {}",
+                    binding.secret,
+                    other.ty,
+                    conflicts.join(", ")
+                ),
+            ));
+        }
+    }
+    bindings.push(binding);
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::{FieldsNamed, Ident};
+
+use crate::attr::ContainerAttrs;
+use crate::audit;
+use crate::expand;
+use crate::schema;
+
+/// `render()`, substituting the fields into a template such as a connection string.
+///
+/// Whether a field implements `Display` is only known to the compiler, so the value is picked by autoref specialization:
+/// the impl for `&Value<T: Display>` is preferred when it applies, and fields of other types fail at runtime instead of failing the derive.
+pub fn expand(
+    ident: &Ident,
+    fields: &FieldsNamed,
+    ty: &TokenStream,
+    container: &ContainerAttrs,
+) -> TokenStream {
+    let arms = fields.named.iter().map(|field| {
+        let member = field.ident.as_ref().unwrap();
+        let name = member.unraw().to_string();
+        let value = if schema::is_option(&field.ty) {
+            quote! {
+                match &self.#member {
+                    Some(value) => (&&Value(value)).text(),
+                    None => return Err(format!("field `{}` is `None`", #name).into()),
+                }
+            }
+        } else {
+            quote!((&&Value(&self.#member)).text())
+        };
+        quote! {
+            #name => Some(#value.ok_or_else(|| format!("field `{}` doesn't implement `Display`", #name))?),
+        }
+    });
+    let audit = container.audit.as_ref().map(|_| {
+        let wrapper = audit::wrapper(ident);
+        quote! {
+            #wrapper::audit(None, std::panic::Location::caller());
+        }
+    });
+    let track_caller = container.audit.as_ref().map(|_| quote!(#[track_caller]));
+    let encode = expand::percent_encode();
+
+    quote! {
+        impl #ty {
+            /// Substitutes the `{field}` placeholders of a template with the values of the fields, as in `postgres://{user}:{password:url}@{host}/{db}`.
+            /// `{field:url}` percent-encodes the value, `{field:json}` escapes it for a JSON string, and `{{` and `}}` stand for braces.
+            #track_caller
+            pub fn render(&self, template: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                #audit
+                #encode
+                struct Value<'a, T>(&'a T);
+                trait Displayed {
+                    fn text(&self) -> Option<String>;
+                }
+                impl<T: std::fmt::Display> Displayed for &Value<'_, T> {
+                    fn text(&self) -> Option<String> {
+                        Some(self.0.to_string())
+                    }
+                }
+                trait Opaque {
+                    fn text(&self) -> Option<String>;
+                }
+                impl<T> Opaque for Value<'_, T> {
+                    fn text(&self) -> Option<String> {
+                        None
+                    }
+                }
+
+                let mut rendered = String::with_capacity(template.len());
+                let mut rest = template;
+                while let Some(start) = rest.find(|c| c == '{' || c == '}') {
+                    rendered.push_str(&rest[..start]);
+                    rest = &rest[start..];
+                    if rest.starts_with("{{") || rest.starts_with("}}") {
+                        rendered.push_str(&rest[..1]);
+                        rest = &rest[2..];
+                        continue;
+                    }
+                    let end = match rest.find('}') {
+                        Some(end) if rest.starts_with('{') => end,
+                        _ => return Err(format!("unmatched brace in template at byte {}", template.len() - rest.len()).into()),
+                    };
+                    let placeholder = &rest[1..end];
+                    rest = &rest[end + 1..];
+                    let (name, escape) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+                    let value: String = match name {
+                        #(#arms)*
+                        _ => None,
+                    }
+                    .ok_or_else(|| format!("template refers to `{}`, which is not a field", name))?;
+                    match escape {
+                        "" => rendered.push_str(&value),
+                        "url" => rendered.push_str(&encode(&value)),
+                        "json" => {
+                            let quoted = global_secrets_manager_runtime::serde_json::Value::String(value).to_string();
+                            rendered.push_str(&quoted[1..quoted.len() - 1]);
+                        }
+                        _ => return Err(format!("unknown escaping `{}` of `{}`, expected `url` or `json`", escape, name).into()),
+                    }
+                }
+                rendered.push_str(rest);
+                Ok(rendered)
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::attr::ContainerAttrs;
+use crate::integrations;
+
+/// The call fetching the secret, and `fetch_replicated()` when `#[gsm(replicas(...))]` is set.
+///
+/// The regions are tried in order of preference, the region of the client first, skipping those whose recent reads mostly failed.
+/// A failing region is probed again after a cooldown, so that the reads switch back once it recovers.
+pub fn expand(container: &ContainerAttrs) -> (TokenStream, TokenStream) {
+    if container.replicas.is_empty() {
+        return (
+            quote!(Self::fetch_secret(client, secret_id)),
+            TokenStream::new(),
+        );
+    }
+    let replicas = &container.replicas;
+    let report = match &container.on_region_switch {
+        Some(hook) => quote!(#hook(secret_id, from, to)),
+        None => integrations::warning(quote! {
+            "reads of secret `{}` switched from region `{}` to `{}`",
+            secret_id,
+            from,
+            to
+        }),
+    };
+
+    let items = quote! {
+        /// Fetches the secret from the first healthy region, in order of preference: the region of the client, then those of `#[gsm(replicas(...))]`.
+        /// A region is unhealthy once half of its recent reads failed with throttling, service or transport errors, and it is probed again after a cooldown;
+        /// the other errors are returned at once.
+        async fn fetch_replicated(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client, secret_id: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+            const REPLICAS: &[&str] = &[#(#replicas),*];
+            const WINDOW: usize = 10;
+            const COOLDOWN: std::time::Duration = std::time::Duration::from_secs(30);
+            /// The outcomes of the recent reads of each region and since when it is unhealthy, by region, and the region of the last read.
+            /// They are kept by region rather than by position, since clients of other regions read the same secret.
+            static HEALTH: std::sync::Mutex<(Vec<(String, std::collections::VecDeque<bool>, Option<std::time::Instant>)>, Option<String>)> =
+                std::sync::Mutex::new((Vec::new(), None));
+
+            let primary = client.config().region().map_or_else(|| "default".to_owned(), ToString::to_string);
+            let mut regions: Vec<&str> = Vec::new();
+            for region in std::iter::once(primary.as_str()).chain(REPLICAS.iter().copied()) {
+                if !regions.contains(&region) {
+                    regions.push(region);
+                }
+            }
+            let order: Vec<&str> = {
+                let health = HEALTH.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+                let usable = |region: &&str| match health.0.iter().find(|(name, _, _)| name == region) {
+                    Some((_, _, Some(since))) => since.elapsed() >= COOLDOWN,
+                    _ => true,
+                };
+                let (healthy, unhealthy): (Vec<&str>, Vec<&str>) = regions.iter().copied().partition(usable);
+                healthy.into_iter().chain(unhealthy).collect()
+            };
+
+            let mut last_err = None;
+            for region in order {
+                let result = if region == primary {
+                    Self::fetch_secret(client, secret_id).await
+                } else {
+                    let config = client
+                        .config()
+                        .to_builder()
+                        .region(global_secrets_manager_runtime::aws_sdk_secretsmanager::config::Region::new(region.to_owned()))
+                        .build();
+                    // An ARN names the region of the secret, which must be that of the replica.
+                    let replica_id = match secret_id.splitn(5, ':').collect::<Vec<_>>()[..] {
+                        ["arn", partition, service, _, rest] => format!("arn:{}:{}:{}:{}", partition, service, region, rest),
+                        _ => secret_id.to_owned(),
+                    };
+                    Self::fetch_secret(&global_secrets_manager_runtime::aws_sdk_secretsmanager::Client::from_conf(config), &replica_id).await
+                };
+                // Only the errors of the region itself count against it and move on to the next one:
+                // the others, such as a missing secret or a denied access, would be the same in every region.
+                if let Err(err) = &result {
+                    if !matches!(
+                        global_secrets_manager_runtime::GsmError::find(&**err),
+                        Some(global_secrets_manager_runtime::GsmError::Throttled { .. } | global_secrets_manager_runtime::GsmError::Service { .. } | global_secrets_manager_runtime::GsmError::Transport { .. })
+                    ) {
+                        return result;
+                    }
+                }
+
+                let switched = {
+                    let mut health = HEALTH.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+                    let at = match health.0.iter().position(|(name, _, _)| name == region) {
+                        Some(at) => at,
+                        None => {
+                            health.0.push((region.to_owned(), Default::default(), None));
+                            health.0.len() - 1
+                        }
+                    };
+                    let (_, outcomes, unhealthy_since) = &mut health.0[at];
+                    if result.is_ok() && unhealthy_since.is_some() {
+                        // Recovered: forget the failures.
+                        outcomes.clear();
+                        *unhealthy_since = None;
+                    }
+                    outcomes.push_back(result.is_ok());
+                    if outcomes.len() > WINDOW {
+                        outcomes.pop_front();
+                    }
+                    let failures = outcomes.iter().filter(|ok| !**ok).count();
+                    // A failed probe of an unhealthy region starts its cooldown again.
+                    if result.is_err() && (unhealthy_since.is_some() || outcomes.len() >= 3 && failures * 2 >= outcomes.len()) {
+                        *unhealthy_since = Some(std::time::Instant::now());
+                    }
+                    let previous = health.1.clone().unwrap_or_else(|| primary.clone());
+                    if result.is_ok() {
+                        health.1 = Some(region.to_owned());
+                    }
+                    (result.is_ok() && previous != region).then_some(previous)
+                };
+                match result {
+                    Ok(secret) => {
+                        if let Some(from) = switched {
+                            let (from, to) = (from.as_str(), region);
+                            #report;
+                        }
+                        return Ok(secret);
+                    }
+                    Err(err) => last_err = Some(err),
+                }
+            }
+            Err(last_err.unwrap())
+        }
+    };
+    (quote!(Self::fetch_replicated(client, secret_id)), items)
+}
@@ -0,0 +1,670 @@
+use proc_macro2::{Group, Ident, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::{
+    Attribute, Data, DeriveInput, Error, FieldsNamed, Lit, LitStr, Meta, NestedMeta, Result, Type,
+};
+
+use crate::attr::{ContainerAttrs, FieldAttrs};
+
+/// A key of the secret read by a field, as serde names it.
+pub struct Key {
+    /// The name of the key, followed by its aliases.
+    pub names: Vec<String>,
+    /// Whether deserialization fails without the key.
+    pub required: bool,
+    /// The type of the field with its lifetimes made `'static`, unless serde deserializes it with a function of its own.
+    pub ty: Option<TokenStream>,
+    /// The field reading the key.
+    pub field: syn::Ident,
+    /// The names of `#[gsm(alias = "...")]`, also listed in `names`, which are renamed to the name of the key before serde reads the payload.
+    pub renamed: Vec<String>,
+    /// The JSON pointer of `#[gsm(pointer = "...")]`, whose value is copied to the key before serde reads the payload.
+    pub pointer: Option<String>,
+    /// The separator of `#[gsm(split)]`, by which a string value is split into an array before serde reads the payload.
+    pub split: Option<String>,
+}
+
+/// The keys the struct reads, and whether it accepts any other, which it does when a field is flattened.
+pub struct Schema {
+    pub keys: Vec<Key>,
+    pub open: bool,
+    /// Whether serde rejects the other keys, by `#[serde(deny_unknown_fields)]`.
+    pub deny_unknown: bool,
+    /// Whether the load fails on the other keys, by `#[gsm(strict)]`.
+    pub strict: bool,
+    /// The globs of `#[gsm(allow_extra(...))]`, matching the other keys that are read by no field on purpose.
+    pub allow_extra: Vec<String>,
+}
+
+/// `verify_schema()`, comparing the keys of the secret with the fields without ever reading the values,
+/// and `check_keys()`, which reports the keys that would fail the deserialization before serde does, since serde's messages may quote the values.
+pub fn expand(
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    ty: &TokenStream,
+    secret_name: &str,
+    container: &ContainerAttrs,
+) -> Result<TokenStream> {
+    let vis = &input.vis;
+    let diff = format_ident!("{}SchemaDiff", input.ident);
+    let diff_doc = format!(
+        "The keys of the secret `{}` that don't match the fields of [`{}`], without their values.",
+        secret_name, input.ident
+    );
+    let Schema {
+        keys,
+        open,
+        deny_unknown,
+        strict,
+        allow_extra,
+    } = schema(input, fields)?;
+    if let Some(glob) = container.allow_extra.first() {
+        if deny_unknown {
+            return Err(Error::new_spanned(
+                glob,
+                "`allow_extra` can't let keys through `#[serde(deny_unknown_fields)]`; use `#[gsm(strict)]` instead",
+            ));
+        }
+        if !strict {
+            return Err(Error::new_spanned(
+                glob,
+                "`allow_extra` lists the keys that `strict` lets through; set `strict` too",
+            ));
+        }
+    }
+    let redact_keys = container.redact_keys.is_some();
+    let invalid = if deny_unknown || strict {
+        quote!(!diff.is_empty())
+    } else {
+        quote!(!diff.missing.is_empty())
+    };
+    let names = keys.iter().map(|key| &key.names);
+    let required = keys.iter().map(|key| key.required);
+    let (typed_names, types): (Vec<_>, Vec<_>) = keys
+        .iter()
+        .filter_map(|key| Some((&key.names, key.ty.as_ref()?)))
+        .unzip();
+    let type_names = types.iter().map(|ty| type_name(ty));
+    let accepts_string: Vec<_> = types
+        .iter()
+        .map(|ty| accepts(ty, quote!(&string.to_string())))
+        .collect();
+    let accepts_text: Vec<_> = types.iter().map(|ty| accepts(ty, quote!(&text))).collect();
+    let renames: Vec<_> = keys
+        .iter()
+        .filter(|key| !key.renamed.is_empty())
+        .map(|key| {
+            let name = &key.names[0];
+            let renamed = &key.renamed;
+            quote! {
+                let mut renamed = None;
+                for alias in [#(#renamed),*] {
+                    renamed = renamed.or(object.remove(alias));
+                }
+                if let Some(value) = renamed {
+                    if !object.contains_key(#name) {
+                        object.insert(#name.to_owned(), value);
+                    }
+                }
+            }
+        })
+        .collect();
+    let with_aliases = if renames.is_empty() {
+        quote!(Ok(rt_str))
+    } else {
+        quote! {
+            let mut payload: global_secrets_manager_runtime::serde_json::Value = match global_secrets_manager_runtime::serde_json::from_str(&rt_str) {
+                Ok(payload) => payload,
+                Err(_) => return Ok(rt_str),
+            };
+            let object = match payload.as_object_mut() {
+                Some(object) => object,
+                None => return Ok(rt_str),
+            };
+            #(#renames)*
+            Ok(global_secrets_manager_runtime::serde_json::to_string(&payload)?)
+        }
+    };
+    let splits: Vec<_> = keys
+        .iter()
+        .filter_map(|key| {
+            let name = &key.names[0];
+            let separator = key.split.as_ref()?;
+            let accepts = match &key.ty {
+                Some(ty) => accepts(ty, quote!(&strings.to_string())),
+                None => quote!(true),
+            };
+            Some(quote! {
+                if let Some(global_secrets_manager_runtime::serde_json::Value::String(text)) = object.get(#name) {
+                    let pieces: Vec<String> = text.split(#separator).map(str::trim).filter(|piece| !piece.is_empty()).map(str::to_owned).collect();
+                    let strings = global_secrets_manager_runtime::serde_json::Value::Array(
+                        pieces.iter().cloned().map(global_secrets_manager_runtime::serde_json::Value::String).collect(),
+                    );
+                    let value = if #accepts {
+                        strings
+                    } else {
+                        global_secrets_manager_runtime::serde_json::Value::Array(
+                            pieces
+                                .into_iter()
+                                .map(|piece| {
+                                    global_secrets_manager_runtime::serde_json::from_str(&piece)
+                                        .unwrap_or(global_secrets_manager_runtime::serde_json::Value::String(piece))
+                                })
+                                .collect(),
+                        )
+                    };
+                    object.insert(#name.to_owned(), value);
+                }
+            })
+        })
+        .collect();
+    let with_splits = if splits.is_empty() {
+        quote!(Ok(rt_str))
+    } else {
+        quote! {
+            let mut payload: global_secrets_manager_runtime::serde_json::Value = match global_secrets_manager_runtime::serde_json::from_str(&rt_str) {
+                Ok(payload) => payload,
+                Err(_) => return Ok(rt_str),
+            };
+            let object = match payload.as_object_mut() {
+                Some(object) => object,
+                None => return Ok(rt_str),
+            };
+            #(#splits)*
+            Ok(global_secrets_manager_runtime::serde_json::to_string(&payload)?)
+        }
+    };
+    let struct_pointer = container.pointer.as_ref().map(|pointer| {
+        quote! {
+            payload = payload
+                .pointer(#pointer)
+                .cloned()
+                .ok_or_else(|| global_secrets_manager_runtime::GsmError::decode(#secret_name, format!("it has no value at `{}`", Self::key_name(#pointer))))?;
+        }
+    });
+    let field_pointers: Vec<_> = keys
+        .iter()
+        .filter_map(|key| {
+            let name = &key.names[0];
+            let pointer = key.pointer.as_ref()?;
+            Some(quote! {
+                if let Some(value) = payload.pointer(#pointer).cloned() {
+                    if let Some(object) = payload.as_object_mut() {
+                        object.insert(#name.to_owned(), value);
+                    }
+                }
+            })
+        })
+        .collect();
+    let with_pointers = if struct_pointer.is_none() && field_pointers.is_empty() {
+        quote!(Ok(rt_str))
+    } else {
+        quote! {
+            let mut payload: global_secrets_manager_runtime::serde_json::Value = match global_secrets_manager_runtime::serde_json::from_str(&rt_str) {
+                Ok(payload) => payload,
+                Err(_) => return Ok(rt_str),
+            };
+            #struct_pointer
+            #(#field_pointers)*
+            Ok(global_secrets_manager_runtime::serde_json::to_string(&payload)?)
+        }
+    };
+    let placeholders = keys.iter().map(|key| {
+        let name = &key.names[0];
+        let accepts = match &key.ty {
+            Some(ty) => accepts(ty, quote!(*value)),
+            None => quote!(true),
+        };
+        quote! {
+            let placeholder = global_secrets_manager_runtime::serde_json::Value::String(format!("<{}.{}>", #secret_name, #name)).to_string();
+            let value = [placeholder.as_str(), "0", "false", "[]", "{}", "null"].into_iter().find(|value| #accepts).unwrap_or(&placeholder);
+            fields.push(format!("  {}: {}", global_secrets_manager_runtime::serde_json::Value::String(#name.to_owned()), value));
+        }
+    });
+    // The backends holding each key as a string don't know the types of the fields.
+    let from_strings = container
+        .backend
+        .as_ref()
+        .filter(|backend| backend.reads_strings())
+        .map(|_| {
+            quote! {
+                /// The payload of the keys held as strings by the backend, such as the secrets of a Bitwarden project: each value stays a string
+                /// where its field takes one, and is read as JSON otherwise, as for the variables of `env_prefix`.
+                fn from_strings(values: Vec<(String, String)>) -> String {
+                    let mut object = global_secrets_manager_runtime::serde_json::Map::new();
+                    for (name, text) in values {
+                        object.insert(name, global_secrets_manager_runtime::serde_json::Value::String(text));
+                    }
+                    #(
+                        for name in [#(#typed_names),*] {
+                            let parsed = match object.get(name) {
+                                Some(string @ global_secrets_manager_runtime::serde_json::Value::String(text)) => {
+                                    if #accepts_string {
+                                        None
+                                    } else {
+                                        global_secrets_manager_runtime::serde_json::from_str::<global_secrets_manager_runtime::serde_json::Value>(text).ok()
+                                    }
+                                }
+                                _ => None,
+                            };
+                            if let Some(value) = parsed {
+                                object.insert(name.to_owned(), value);
+                            }
+                        }
+                    )*
+                    global_secrets_manager_runtime::serde_json::Value::Object(object).to_string()
+                }
+            }
+        });
+    let extra = if open {
+        quote!(Vec::new())
+    } else {
+        quote! {{
+            const ALLOW_EXTRA: &[&str] = &[#(#allow_extra),*];
+            object
+                .keys()
+                .filter(|key| !KEYS.iter().any(|(names, _)| names.contains(&key.as_str())))
+                .filter(|key| !ALLOW_EXTRA.iter().any(|glob| global_secrets_manager_runtime::glob_matches(glob, key)))
+                .cloned()
+                .collect()
+        }}
+    };
+    Ok(quote! {
+        #[doc = #diff_doc]
+        #[derive(Clone, Debug, Default, PartialEq, Eq)]
+        #vis struct #diff {
+            /// Keys required by a field but absent from the secret.
+            pub missing: Vec<String>,
+            /// Keys of the secret that no field reads.
+            pub extra: Vec<String>,
+        }
+
+        impl #diff {
+            /// Whether the secret matches the fields.
+            pub fn is_empty(&self) -> bool {
+                self.missing.is_empty() && self.extra.is_empty()
+            }
+        }
+
+        impl std::fmt::Display for #diff {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "missing keys [{}], unexpected keys [{}]", self.missing.join(", "), self.extra.join(", "))
+            }
+        }
+
+        impl #ty {
+            global_secrets_manager_runtime::__sdk! {
+                /// Fetches the secret and compares its keys with the fields, so that deployments can be stopped when they drift apart.
+                pub async fn verify_schema(client: &global_secrets_manager_runtime::aws_sdk_secretsmanager::Client) -> Result<#diff, Box<dyn std::error::Error + Send + Sync>> {
+                    let secret = Self::get_secret(client, &Self::secret_id(client).await?).await?;
+                    let secret = Self::with_bases(client, secret).await?;
+                    let secret = Self::with_common(client, secret).await?;
+                    Self::schema_diff(&global_secrets_manager_runtime::parse_json(#secret_name, &Self::with_pointers(secret)?)?)
+                }
+
+                /// The payload loaded under `GSM_DRY_RUN`, the one of `skeleton_json()`.
+                fn placeholder() -> String {
+                    Self::skeleton_json()
+                }
+
+                #from_strings
+            }
+
+            /// A JSON document with every key the fields read, optional or not, to create or fill the secret without reading the fields:
+            /// each key holds `"<secret.key>"`, or the first of a few neutral values that its field accepts, such as `0` for a number.
+            pub fn skeleton_json() -> String {
+                let mut fields: Vec<String> = Vec::new();
+                #(#placeholders)*
+                if fields.is_empty() {
+                    return "{}".to_owned();
+                }
+                format!("{{\n{}\n}}", fields.join(",\n"))
+            }
+
+            fn schema_diff(value: &global_secrets_manager_runtime::serde_json::Value) -> Result<#diff, Box<dyn std::error::Error + Send + Sync>> {
+                const KEYS: &[(&[&str], bool)] = &[#((&[#(#names),*], #required)),*];
+                let object = value
+                    .as_object()
+                    .ok_or_else(|| global_secrets_manager_runtime::GsmError::decode(#secret_name, "it is not a JSON object"))?;
+                let missing = KEYS
+                    .iter()
+                    .filter(|(names, required)| *required && !names.iter().any(|name| object.contains_key(*name)))
+                    .map(|(names, _)| names[0].to_owned())
+                    .collect();
+                let extra = #extra;
+                Ok(#diff { missing, extra })
+            }
+
+            /// After serde failed, finds the first field whose value doesn't deserialize into its type, and describes it by its length only.
+            fn field_error(rt_str: &str) -> Option<String> {
+                let value: global_secrets_manager_runtime::serde_json::Value = global_secrets_manager_runtime::serde_json::from_str(rt_str).ok()?;
+                let object = value.as_object()?;
+                #(
+                    let names: &[&str] = &[#(#typed_names),*];
+                    if let Some((name, field)) = names.iter().find_map(|name| Some((name, object.get(*name)?))) {
+                        let text = field.to_string();
+                        if !#accepts_text {
+                            let (kind, len) = match field {
+                                global_secrets_manager_runtime::serde_json::Value::String(s) => ("a string", s.chars().count()),
+                                global_secrets_manager_runtime::serde_json::Value::Array(a) => ("an array", a.len()),
+                                global_secrets_manager_runtime::serde_json::Value::Object(o) => ("an object", o.len()),
+                                global_secrets_manager_runtime::serde_json::Value::Number(_) => ("a number", text.len()),
+                                global_secrets_manager_runtime::serde_json::Value::Bool(_) => ("a boolean", text.len()),
+                                global_secrets_manager_runtime::serde_json::Value::Null => ("null", 0),
+                            };
+                            return Some(format!(
+                                "key `{}` is {} of length {}, which isn't a valid `{}`",
+                                Self::key_name(name), kind, len, #type_names
+                            ));
+                        }
+                    }
+                )*
+                None
+            }
+
+            /// Replaces the payload by the part of `#[gsm(pointer = "...")]` on the struct, then copies the values at the pointers of the fields to their keys.
+            /// Payloads that aren't JSON are left to serde.
+            fn with_pointers(rt_str: String) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                #with_pointers
+            }
+
+            /// Renames the keys of `#[gsm(alias = "...")]` to the names of their fields, dropping them when the key is present under its name too.
+            /// Payloads that aren't JSON objects are left to serde.
+            fn with_aliases(rt_str: String) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                #with_aliases
+            }
+
+            /// Splits the string values of the `#[gsm(split)]` fields into arrays, each piece trimmed and read as JSON where its field takes no string.
+            /// Arrays are left as they are, and payloads that aren't JSON objects to serde.
+            fn with_splits(rt_str: String) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                #with_splits
+            }
+
+            /// Fails with the names of the keys that serde would not accept. Payloads that aren't valid JSON are left to serde.
+            fn check_keys(rt_str: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+                let value: global_secrets_manager_runtime::serde_json::Value = match global_secrets_manager_runtime::serde_json::from_str(rt_str) {
+                    Ok(value) => value,
+                    Err(_) => return Ok(()),
+                };
+                let diff = Self::schema_diff(&value)?;
+                if #invalid && #redact_keys {
+                    let reason = format!("{} keys missing, {} unexpected keys", diff.missing.len(), diff.extra.len());
+                    return Err(global_secrets_manager_runtime::GsmError::decode(#secret_name, reason).into());
+                }
+                if #invalid {
+                    return Err(global_secrets_manager_runtime::GsmError::decode(#secret_name, diff.to_string()).into());
+                }
+                Ok(())
+            }
+
+            /// How a key of the secret is named in error messages: by its name, unless `#[gsm(redact_keys)]` keeps it out of them.
+            fn key_name(name: &str) -> &str {
+                if #redact_keys {
+                    "<redacted>"
+                } else {
+                    name
+                }
+            }
+
+        }
+    })
+}
+
+/// Follows the serde attributes that change the keys: `rename`, `rename_all`, `alias`, `default`, `skip`, `skip_deserializing`, `flatten` and `deny_unknown_fields`.
+pub fn schema(input: &DeriveInput, fields: &FieldsNamed) -> Result<Schema> {
+    let mut rename_all = None;
+    let mut default_all = false;
+    let mut deny_unknown = false;
+    for meta in serde_meta(&input.attrs)? {
+        match &meta {
+            Meta::NameValue(nv) if nv.path.is_ident("rename_all") => {
+                rename_all = lit_string(&nv.lit)
+            }
+            Meta::List(list) if list.path.is_ident("rename_all") => {
+                rename_all = deserialize_name(list)
+            }
+            Meta::Path(path) | Meta::NameValue(syn::MetaNameValue { path, .. })
+                if path.is_ident("default") =>
+            {
+                default_all = true
+            }
+            Meta::Path(path) if path.is_ident("deny_unknown_fields") => deny_unknown = true,
+            _ => {}
+        }
+    }
+
+    let container = ContainerAttrs::from_attrs(&input.attrs)?;
+    let mut schema = Schema {
+        keys: Vec::new(),
+        open: false,
+        deny_unknown,
+        strict: container.strict.is_some(),
+        allow_extra: container.allow_extra.iter().map(LitStr::value).collect(),
+    };
+    for field in &fields.named {
+        let ident = field.ident.as_ref().unwrap().unraw().to_string();
+        let mut name = rename_all
+            .as_deref()
+            .map_or_else(|| ident.clone(), |rule| rename(&ident, rule));
+        let mut aliases = Vec::new();
+        let mut required = !default_all && !is_option(&field.ty);
+        let mut skipped = false;
+        let mut ty = Some(static_lifetimes(field.ty.to_token_stream()));
+        for meta in serde_meta(&field.attrs)? {
+            match &meta {
+                Meta::NameValue(nv) if nv.path.is_ident("rename") => {
+                    name = lit_string(&nv.lit).unwrap_or(name)
+                }
+                Meta::List(list) if list.path.is_ident("rename") => {
+                    name = deserialize_name(list).unwrap_or(name)
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("alias") => {
+                    aliases.extend(lit_string(&nv.lit))
+                }
+                Meta::Path(path) | Meta::NameValue(syn::MetaNameValue { path, .. })
+                    if path.is_ident("default") =>
+                {
+                    required = false
+                }
+                Meta::Path(path)
+                    if path.is_ident("skip") || path.is_ident("skip_deserializing") =>
+                {
+                    skipped = true
+                }
+                Meta::Path(path) if path.is_ident("flatten") => {
+                    skipped = true;
+                    schema.open = true;
+                }
+                Meta::NameValue(nv)
+                    if nv.path.is_ident("with") || nv.path.is_ident("deserialize_with") =>
+                {
+                    ty = None
+                }
+                _ => {}
+            }
+        }
+        let gsm_attrs = FieldAttrs::from_field(field)?;
+        let renamed: Vec<_> = gsm_attrs.aliases.iter().map(LitStr::value).collect();
+        if skipped {
+            let unread = [
+                ("alias", gsm_attrs.aliases.first()),
+                ("pointer", gsm_attrs.pointer.as_ref()),
+                ("split", gsm_attrs.split.as_ref()),
+            ];
+            if let Some((option, Some(lit))) = unread.iter().find(|(_, lit)| lit.is_some()) {
+                return Err(Error::new_spanned(
+                    lit,
+                    format!(
+                        "`{}` can't be set on a field that serde doesn't read from the secret",
+                        option
+                    ),
+                ));
+            }
+        } else {
+            let mut names = vec![name];
+            names.extend(aliases);
+            names.extend(renamed.iter().cloned());
+            schema.keys.push(Key {
+                names,
+                required,
+                ty,
+                field: field.ident.clone().unwrap(),
+                renamed,
+                pointer: gsm_attrs.pointer.as_ref().map(LitStr::value),
+                split: gsm_attrs.split.as_ref().map(LitStr::value),
+            });
+        }
+    }
+    Ok(schema)
+}
+
+/// Whether the struct or the enum has fields borrowing from the payload, which is then leaked so that they can point into it.
+pub fn borrows(input: &DeriveInput) -> bool {
+    let fields = match &input.data {
+        Data::Struct(data) => data.fields.to_token_stream(),
+        Data::Enum(data) => data.variants.to_token_stream(),
+        Data::Union(data) => data.fields.to_token_stream(),
+    };
+    input.generics.lifetimes().next().is_some() || has_lifetime(&fields)
+}
+
+fn has_lifetime(tokens: &TokenStream) -> bool {
+    tokens.clone().into_iter().any(|token| match token {
+        TokenTree::Group(group) => has_lifetime(&group.stream()),
+        TokenTree::Punct(punct) => punct.as_char() == '\'',
+        _ => false,
+    })
+}
+
+/// Deserializes `rt_str`, a `&str` of the payload, into `Self`, leaking the payload only when the fields borrow from it.
+pub fn deserialize(input: &DeriveInput, secret_name: &str, rt_str: TokenStream) -> TokenStream {
+    if borrows(input) {
+        quote!(global_secrets_manager_runtime::deserialize(#secret_name, String::from(#rt_str)))
+    } else {
+        quote!(global_secrets_manager_runtime::deserialize_owned(#secret_name, #rt_str))
+    }
+}
+
+/// Whether `text`, a `&str` of JSON, deserializes into `ty`, leaking it only when the type borrows from it.
+pub fn accepts(ty: &TokenStream, text: TokenStream) -> TokenStream {
+    if has_lifetime(ty) {
+        quote!(global_secrets_manager_runtime::serde_json::from_str::<#ty>(Box::leak(String::from(#text).into_boxed_str())).is_ok())
+    } else {
+        quote!(global_secrets_manager_runtime::serde_json::from_str::<#ty>(#text).is_ok())
+    }
+}
+
+/// Lifetimes of the struct can't be named in the impls of its `'static` instance.
+fn static_lifetimes(tokens: TokenStream) -> TokenStream {
+    let mut lifetime = false;
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => {
+                let mut replaced = Group::new(group.delimiter(), static_lifetimes(group.stream()));
+                replaced.set_span(group.span());
+                TokenTree::Group(replaced)
+            }
+            TokenTree::Punct(punct) => {
+                lifetime = punct.as_char() == '\'';
+                TokenTree::Punct(punct)
+            }
+            TokenTree::Ident(ident) if std::mem::take(&mut lifetime) => {
+                TokenTree::Ident(Ident::new("static", ident.span()))
+            }
+            other => {
+                lifetime = false;
+                other
+            }
+        })
+        .collect()
+}
+
+fn serde_meta(attrs: &[Attribute]) -> Result<Vec<Meta>> {
+    let mut metas = Vec::new();
+    for attr in attrs.iter().filter(|a| a.path.is_ident("serde")) {
+        if let Meta::List(list) = attr.parse_meta()? {
+            metas.extend(list.nested.into_iter().filter_map(|nested| match nested {
+                NestedMeta::Meta(meta) => Some(meta),
+                NestedMeta::Lit(_) => None,
+            }));
+        }
+    }
+    Ok(metas)
+}
+
+fn lit_string(lit: &Lit) -> Option<String> {
+    match lit {
+        Lit::Str(s) => Some(s.value()),
+        _ => None,
+    }
+}
+
+/// The `deserialize` half of `rename(serialize = "...", deserialize = "...")`.
+fn deserialize_name(list: &syn::MetaList) -> Option<String> {
+    list.nested.iter().find_map(|nested| match nested {
+        NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("deserialize") => {
+            lit_string(&nv.lit)
+        }
+        _ => None,
+    })
+}
+
+/// Whether the field is an `Option`, which serde lets be absent.
+pub fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => {
+            path.qself.is_none()
+                && path
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|s| s.ident == "Option")
+        }
+        _ => false,
+    }
+}
+
+/// The type as written, without the spaces between its tokens.
+fn type_name(ty: &TokenStream) -> String {
+    ty.to_string()
+        .replace(" < ", "<")
+        .replace("< ", "<")
+        .replace(" <", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+        .replace(" :: ", "::")
+        .replace(":: ", "::")
+        .replace("& ", "&")
+}
+
+/// The name serde gives to a snake_case field under `rename_all = rule`.
+fn rename(field: &str, rule: &str) -> String {
+    let pascal = || {
+        field
+            .split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars.next().map_or_else(String::new, |first| {
+                    first.to_uppercase().chain(chars).collect()
+                })
+            })
+            .collect::<String>()
+    };
+    match rule {
+        "lowercase" => field.to_lowercase(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => field.to_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_lowercase().chain(chars).collect()
+            })
+        }
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.replace('_', "-").to_uppercase(),
+        _ => field.to_owned(),
+    }
+}
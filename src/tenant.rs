@@ -0,0 +1,93 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Error, Result};
+
+use crate::attr::ContainerAttrs;
+use crate::expand;
+use crate::schema;
+
+/// `for_tenant()`, reading the secret of a tenant, named by `#[gsm(tenant(name = "..."))]`, when it is set.
+///
+/// The secrets are cached for each tenant until their `ttl` runs out, and the least recently read tenants are dropped past the `capacity`,
+/// so that an application serving many tenants keeps the memory of the active ones only.
+/// The fields must own their values: the secrets are fetched again and again, and a payload borrowed from would be leaked each time.
+pub fn expand(
+    input: &DeriveInput,
+    ty: &TokenStream,
+    container: &ContainerAttrs,
+) -> Result<TokenStream> {
+    let tenant = match &container.tenant {
+        Some(tenant) => tenant,
+        None => return Ok(TokenStream::new()),
+    };
+    if schema::borrows(input) {
+        return Err(Error::new_spanned(
+            &tenant.name,
+            "`tenant` fetches the secrets again when their `ttl` runs out, so the fields can't borrow from the payload; use owned types such as `String`",
+        ));
+    }
+    let name = tenant.name.value();
+    let (capacity, ttl) = (tenant.capacity, tenant.ttl);
+    let shared_config = expand::shared_config(container);
+    let doc = format!(
+        "The secret of the tenant `tenant_id`, `{}` with its id, fetched at the first read and cached for {} seconds.",
+        name, ttl
+    );
+    Ok(quote! {
+        impl #ty {
+            #[doc = #doc]
+            /// The ids are made of letters, digits, `-`, `_` and `.`, so that a tenant can't name the secret of another one.
+            pub async fn for_tenant(tenant_id: &str) -> Result<std::sync::Arc<Self>, Box<dyn std::error::Error + Send + Sync>> {
+                const CAPACITY: usize = #capacity;
+                const TTL: std::time::Duration = std::time::Duration::from_secs(#ttl);
+                /// The secrets of the tenants, with when they were fetched and the tick of their last read, and the last tick.
+                static CACHE: global_secrets_manager_runtime::once_cell::sync::Lazy<
+                    std::sync::Mutex<(std::collections::HashMap<String, (std::sync::Arc<#ty>, std::time::Instant, u64)>, u64)>,
+                > = global_secrets_manager_runtime::once_cell::sync::Lazy::new(Default::default);
+                static CLIENT: global_secrets_manager_runtime::once_cell::sync::Lazy<
+                    global_secrets_manager_runtime::executor::Mutex<Option<global_secrets_manager_runtime::aws_sdk_secretsmanager::Client>>,
+                > = global_secrets_manager_runtime::once_cell::sync::Lazy::new(|| global_secrets_manager_runtime::executor::Mutex::new(None));
+
+                if tenant_id.is_empty() || !tenant_id.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.')) {
+                    return Err(format!("`{}` is not a valid tenant id: expected letters, digits, `-`, `_` and `.`", tenant_id).into());
+                }
+                {
+                    let mut cache = CACHE.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+                    let (entries, tick) = &mut *cache;
+                    *tick += 1;
+                    if let Some((secrets, fetched, used)) = entries.get_mut(tenant_id) {
+                        if fetched.elapsed() < TTL {
+                            *used = *tick;
+                            return Ok(secrets.clone());
+                        }
+                    }
+                }
+
+                let client = {
+                    let mut client = CLIENT.lock().await;
+                    match &*client {
+                        Some(client) => client.clone(),
+                        None => {
+                            global_secrets_manager_runtime::dotenvy::dotenv().ok();
+                            #shared_config
+                            client.insert(global_secrets_manager_runtime::aws_sdk_secretsmanager::Client::new(&shared_config)).clone()
+                        }
+                    }
+                };
+                let secret_id = Self::conventional_name(&#name.replace("{tenant}", tenant_id))?;
+                let secrets = std::sync::Arc::new(Self::read_secret(&client, &secret_id).await?.0);
+
+                let mut cache = CACHE.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+                let (entries, tick) = &mut *cache;
+                if entries.len() >= CAPACITY && !entries.contains_key(tenant_id) {
+                    let least_recent = entries.iter().min_by_key(|(_, (_, _, used))| *used).map(|(tenant, _)| tenant.clone());
+                    if let Some(tenant) = least_recent {
+                        entries.remove(&tenant);
+                    }
+                }
+                entries.insert(tenant_id.to_owned(), (secrets.clone(), std::time::Instant::now(), *tick));
+                Ok(secrets)
+            }
+        }
+    })
+}
@@ -0,0 +1,629 @@
+//! Expansions of the derive, checked on the generated tokens and on the errors reported for invalid options.
+
+use proc_macro2::TokenStream;
+use syn::DeriveInput;
+
+use crate::expand;
+
+/// The expansion of the derive on `input`, with its tokens spaced as `quote!` prints them.
+fn expand(input: DeriveInput) -> String {
+    match expand::derive(&input) {
+        Ok(tokens) => tokens.to_string(),
+        Err(err) => panic!("the derive failed: {}", err),
+    }
+}
+
+/// The error reported by the derive on `input`.
+fn error(input: DeriveInput) -> String {
+    match expand::derive(&input) {
+        Ok(_) => panic!("the derive succeeded"),
+        Err(err) => err.to_string(),
+    }
+}
+
+/// Whether `expanded` holds `tokens`, printed as `quote!` prints them.
+fn holds(expanded: &str, tokens: TokenStream) -> bool {
+    expanded.contains(&tokens.to_string())
+}
+
+/// The item of `expanded` starting at `start`, such as `fn current`, up to the next function.
+fn item<'a>(expanded: &'a str, start: &str) -> &'a str {
+    let item = &expanded[expanded
+        .find(start)
+        .unwrap_or_else(|| panic!("no `{}`", start))..];
+    match item[start.len()..].find(" fn ") {
+        Some(end) => &item[..start.len() + end],
+        None => item,
+    }
+}
+
+#[test]
+fn owned_fields_are_deserialized_without_leaking() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "owned")]
+        struct Owned {
+            key: String,
+        }
+    });
+    assert!(holds(
+        &expanded,
+        quote::quote!(global_secrets_manager_runtime::deserialize_owned(
+            "owned", &rt_str
+        ))
+    ));
+    assert!(!expanded.contains("Box :: leak"));
+}
+
+#[test]
+fn borrowed_fields_point_into_the_leaked_payload() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "borrowed")]
+        struct Borrowed<'a> {
+            key: &'a str,
+        }
+    });
+    assert!(holds(
+        &expanded,
+        quote::quote!(global_secrets_manager_runtime::deserialize(
+            "borrowed",
+            String::from(&rt_str)
+        ))
+    ));
+}
+
+#[test]
+fn tenants_are_named_by_the_template() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(tenant(name = "tenants/{tenant}/db"))]
+        struct TenantDb {
+            password: String,
+        }
+    });
+    assert!(holds(
+        &expanded,
+        quote::quote!(Self::conventional_name(
+            &"tenants/{tenant}/db".replace("{tenant}", tenant_id)
+        )?)
+    ));
+}
+
+#[test]
+fn tenants_reject_borrowed_fields() {
+    let error = error(syn::parse_quote! {
+        #[gsm(tenant(name = "tenants/{tenant}/db"))]
+        struct TenantDb<'a> {
+            password: &'a str,
+        }
+    });
+    assert!(error.contains("can't borrow from the payload"), "{}", error);
+}
+
+#[test]
+fn no_static_fetches_without_leaking() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "prod/billing", no_static)]
+        struct BillingCredentials {
+            api_key: String,
+        }
+    });
+    assert!(holds(&expanded, quote::quote!(pub async fn fetch())));
+    assert!(!expanded.contains("Box :: leak"));
+}
+
+#[test]
+fn no_static_rejects_borrowed_fields() {
+    let error = error(syn::parse_quote! {
+        #[gsm(name = "prod/billing", no_static)]
+        struct BillingCredentials<'a> {
+            api_key: &'a str,
+        }
+    });
+    assert!(error.contains("can't borrow from the payload"), "{}", error);
+}
+
+#[test]
+fn into_inner_leaves_the_preloaded_value_to_the_constant() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "cli")]
+        struct Cli {
+            token: String,
+        }
+    });
+    assert!(holds(
+        &expanded,
+        quote::quote!(
+            pub fn into_inner() -> Self {
+                Self::load_or_panic()
+            }
+        )
+    ));
+}
+
+#[test]
+fn operations_reach_the_secret_as_the_constant_does() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "db", role_chain("arn:aws:iam::1:role/a"))]
+        struct Db {
+            password: String,
+        }
+    });
+    let sdk_client = &expanded[expanded.find("pub async fn sdk_client").unwrap()..];
+    let sdk_client = &sdk_client[..sdk_client.find("pub async fn healthcheck").unwrap()];
+    assert!(holds(
+        sdk_client,
+        quote::quote!(global_secrets_manager_runtime::assume_role(
+            &shared_config,
+            role_arn
+        ))
+    ));
+    assert!(holds(
+        &expanded,
+        quote::quote!(let client = Self::sdk_client().await?;)
+    ));
+}
+
+#[cfg(feature = "s3")]
+#[test]
+fn backends_get_no_operations_on_secrets_manager() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(backend = "s3", bucket = "billing-config", key = "prod/certificates.json")]
+        struct Certificates {
+            bundle: String,
+        }
+    });
+    for operation in [
+        "healthcheck",
+        "metadata",
+        "tags",
+        "rotate_now",
+        "sdk_client",
+    ] {
+        assert!(
+            !expanded.contains(&format!("pub async fn {}", operation)),
+            "{}",
+            operation
+        );
+    }
+}
+
+#[test]
+fn first_reads_share_a_single_flight() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "single-flight")]
+        struct SingleFlight {
+            key: String,
+        }
+    });
+    for read in ["fn try_preload", "fn current", "fn init"] {
+        assert!(
+            item(&expanded, read).contains("Self :: load_once ()"),
+            "{}",
+            read
+        );
+    }
+}
+
+#[test]
+fn merge_needs_extends() {
+    let error = error(syn::parse_quote! {
+        #[gsm(name = "prod/billing", merge = "first_wins")]
+        struct Billing {
+            region: String,
+        }
+    });
+    assert!(
+        error.contains("`merge` needs the secrets of `extends"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn fields_override_the_merge_order_of_the_struct() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "prod/billing", extends = "shared/base", merge = "first_wins")]
+        struct Billing {
+            #[gsm(merge = "last_wins")]
+            #[serde(alias = "aws_region")]
+            region: String,
+            db_host: String,
+        }
+    });
+    let with_bases = item(&expanded, "fn with_bases");
+    assert!(holds(
+        with_bases,
+        quote::quote!(for base in ["shared/base"])
+    ));
+    assert!(holds(
+        with_bases,
+        quote::quote!("region" | "aws_region" => false, _ => true,)
+    ));
+}
+
+#[test]
+fn rotation_replaces_the_constant_in_a_lock() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "rotated", storage = "rwlock")]
+        struct Rotated {
+            password: String,
+        }
+    });
+    assert!(holds(
+        item(&expanded, "fn rotate_now"),
+        quote::quote!(*Self::write() = reloaded;)
+    ));
+
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "rotated")]
+        struct Rotated {
+            password: String,
+        }
+    });
+    assert!(!item(&expanded, "fn rotate_now").contains("Self :: write ()"));
+}
+
+#[test]
+fn regenerated_passwords_come_from_the_runtime() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "prod/webhooks", writable)]
+        struct WebhookKeys {
+            signing_key: String,
+        }
+    });
+    let regenerate_field = item(&expanded, "fn regenerate_field");
+    assert!(holds(
+        regenerate_field,
+        quote::quote!(options: &global_secrets_manager_runtime::PasswordOptions)
+    ));
+    assert!(holds(
+        regenerate_field,
+        quote::quote!(global_secrets_manager_runtime::random_password(client, options).await?)
+    ));
+}
+
+#[cfg(feature = "config")]
+#[test]
+fn config_sources_are_fetched_as_the_constant_is() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "app", role_chain("arn:aws:iam::1:role/a"))]
+        struct App {
+            key: String,
+        }
+    });
+    let config_source = item(&expanded, "fn config_source");
+    assert!(holds(
+        config_source,
+        quote::quote!(global_secrets_manager_runtime::executor::in_async_context())
+    ));
+    assert!(holds(
+        config_source,
+        quote::quote!(global_secrets_manager_runtime::assume_role(
+            &shared_config,
+            role_arn
+        ))
+    ));
+}
+
+#[test]
+fn every_read_of_a_loaded_constant_is_a_cache_hit() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "counted")]
+        struct Counted {
+            key: String,
+        }
+    });
+    let hit = "global_secrets_manager_runtime :: telemetry :: cache_hit (\"Counted\"";
+    assert!(expanded.contains("pub static Counted : CountedStatic"));
+    for accessor in [
+        "fn deref",
+        "fn current",
+        "fn peek",
+        "fn cloned",
+        "fn shared",
+        "fn with_secret",
+    ] {
+        assert!(
+            item(&expanded, accessor).contains(hit),
+            "{} doesn't count",
+            accessor
+        );
+    }
+    assert!(item(&expanded, "fn load_once").contains("telemetry :: refreshed (\"Counted\""));
+}
+
+#[test]
+fn reads_of_the_lock_are_cache_hits() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "locked", storage = "rwlock")]
+        struct Locked {
+            key: String,
+        }
+    });
+    let hit = "global_secrets_manager_runtime :: telemetry :: cache_hit (\"Locked\"";
+    assert!(!expanded.contains("LockedStatic"));
+    for accessor in ["fn read", "fn write", "fn peek"] {
+        assert!(
+            item(&expanded, accessor).contains(hit),
+            "{} doesn't count",
+            accessor
+        );
+    }
+    assert!(item(&expanded, "fn rotate_now").contains("telemetry :: refreshed (\"Locked\""));
+}
+
+#[test]
+fn one_span_covers_the_retries_of_a_load() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "traced", retry(throttled(retries = 3)))]
+        struct Traced {
+            key: String,
+        }
+    });
+    let start = expanded.find("fn get_secret").unwrap();
+    let get_secret = &expanded[start..start + expanded[start..].find("fn fetch_secret").unwrap()];
+    assert_eq!(get_secret.matches("telemetry :: load (").count(), 1);
+    let load = get_secret.find("telemetry :: load (").unwrap();
+    assert!(load < get_secret.find("loop").unwrap());
+    assert!(load < get_secret.find("telemetry :: fetch (").unwrap());
+}
+
+#[cfg(feature = "compile-time-check")]
+#[test]
+fn the_globs_of_allow_extra_are_those_of_the_runtime() {
+    let input: DeriveInput = syn::parse_quote! {
+        #[gsm(name = "checked", strict, allow_extra("legacy_*", "old_?"))]
+        struct Checked {
+            key: String,
+            #[serde(default)]
+            optional: String,
+        }
+    };
+    let fields = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => fields,
+        _ => unreachable!(),
+    };
+    let schema = crate::schema::schema(&input, fields).unwrap();
+    let keys = ["legacy_token", "old_1", "old_12", "other"].map(String::from);
+    assert_eq!(
+        schema.diff(&keys),
+        (
+            vec!["key".to_owned()],
+            vec!["old_12".to_owned(), "other".to_owned()]
+        )
+    );
+}
+
+#[cfg(feature = "rocket")]
+#[test]
+fn one_fairing_preloads_all_the_registered_secrets() {
+    let fairing =
+        crate::integrations::rocket::fairing(syn::parse_quote!(AppSecrets, ApiSecrets)).to_string();
+    assert_eq!(fairing.matches("try_on_ignite").count(), 1);
+    assert!(holds(&fairing, quote::quote!(<AppSecrets>::try_preload())));
+    assert!(holds(&fairing, quote::quote!(<ApiSecrets>::try_preload())));
+    assert!(fairing.contains("telemetry :: warning (format_args !"));
+}
+
+#[cfg(feature = "rocket")]
+#[test]
+fn the_types_get_no_fairing_of_their_own() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "launched")]
+        struct Launched {
+            key: String,
+        }
+    });
+    assert!(!expanded.contains("rocket_fairing"));
+}
+
+#[cfg(feature = "axum")]
+#[test]
+fn handlers_extract_the_secrets_without_panicking() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "served")]
+        struct Served {
+            key: String,
+        }
+    });
+    assert!(holds(
+        &expanded,
+        quote::quote!(impl global_secrets_manager_runtime::axum::GlobalSecret for Served)
+    ));
+    assert!(!expanded.contains("Infallible"));
+    assert!(!item(&expanded, "fn axum_state").contains("Self :: preload ()"));
+}
+
+#[cfg(feature = "kms")]
+#[test]
+fn kms_decrypts_with_the_configuration_of_the_secret() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "encrypted", role_chain("arn:aws:iam::1:role/reader"))]
+        struct Encrypted<'a> {
+            #[gsm(kms_decrypt)]
+            key: String,
+            #[gsm(kms_decrypt, lazy_decode)]
+            blob: &'a str,
+        }
+    });
+    let kms = "aws_sdk_kms :: Client :: new (& shared_config)";
+    assert_eq!(expanded.matches(kms).count(), 2);
+    for kms in expanded.match_indices(kms).map(|(at, _)| &expanded[..at]) {
+        let config = &kms[kms.rfind("let mut shared_config").unwrap()..];
+        assert!(config.contains("global_secrets_manager_runtime :: assume_role (& shared_config"));
+    }
+}
+
+#[test]
+fn the_health_of_the_replicas_is_kept_by_region() {
+    let expanded = expand(syn::parse_quote! {
+        #[gsm(name = "replicated", replicas("us-west-2", "eu-west-1"))]
+        struct Replicated {
+            key: String,
+        }
+    });
+    let fetch = item(&expanded, "fn fetch_replicated");
+    assert!(fetch.contains("health . 0 . iter () . position (| (name , _ , _) | name == region)"));
+    assert!(fetch.contains("health . 1 = Some (region . to_owned ())"));
+    assert!(!fetch.contains("resize_with"));
+}
+
+#[cfg(feature = "kms")]
+#[test]
+fn writable_types_reject_decrypted_fields() {
+    let error = error(syn::parse_quote! {
+        #[gsm(name = "writable-kms", writable)]
+        struct WritableKms {
+            #[gsm(kms_decrypt)]
+            api_key: String,
+        }
+    });
+    assert!(error.contains("write back unencrypted"), "{}", error);
+}
+
+#[cfg(feature = "kms")]
+#[test]
+fn writable_types_keep_the_ciphertext_of_lazy_fields() {
+    expand(syn::parse_quote! {
+        #[gsm(name = "writable-lazy-kms", writable)]
+        struct WritableLazyKms<'a> {
+            #[gsm(kms_decrypt, lazy_decode)]
+            api_key: &'a str,
+        }
+    });
+}
+
+#[test]
+fn writable_types_reject_the_options_rewriting_the_payload() {
+    let inputs: [(&str, DeriveInput); 9] = [
+        (
+            "pointer",
+            syn::parse_quote! {
+                #[gsm(name = "writable-pointer", writable, pointer = "/billing")]
+                struct WritablePointer { key: String }
+            },
+        ),
+        (
+            "pointer",
+            syn::parse_quote! {
+                #[gsm(name = "writable-field-pointer", writable)]
+                struct WritableFieldPointer { #[gsm(pointer = "/db/password")] password: String }
+            },
+        ),
+        (
+            "extends",
+            syn::parse_quote! {
+                #[gsm(name = "writable-extends", writable, extends = "shared/base")]
+                struct WritableExtends { key: String }
+            },
+        ),
+        (
+            "common",
+            syn::parse_quote! {
+                #[gsm(name = "writable-common", writable)]
+                struct WritableCommon { #[gsm(common)] base: Base }
+            },
+        ),
+        (
+            "deref_secret",
+            syn::parse_quote! {
+                #[gsm(name = "writable-deref", writable)]
+                struct WritableDeref { #[gsm(deref_secret)] database: String }
+            },
+        ),
+        (
+            "env",
+            syn::parse_quote! {
+                #[gsm(name = "writable-env", writable)]
+                struct WritableEnv { #[gsm(env = "API_KEY")] api_key: String }
+            },
+        ),
+        (
+            "env_prefix",
+            syn::parse_quote! {
+                #[gsm(name = "writable-env-prefix", writable, env_prefix = "APP_")]
+                struct WritableEnvPrefix { api_key: String }
+            },
+        ),
+        (
+            "alias",
+            syn::parse_quote! {
+                #[gsm(name = "writable-alias", writable)]
+                struct WritableAlias { #[gsm(alias = "password")] db_password: String }
+            },
+        ),
+        (
+            "split",
+            syn::parse_quote! {
+                #[gsm(name = "writable-split", writable)]
+                struct WritableSplit { #[gsm(split)] hosts: Vec<String> }
+            },
+        ),
+    ];
+    for (option, input) in inputs {
+        let error = error(input);
+        assert!(
+            error.starts_with(&format!("`{}` rewrites the payload", option)),
+            "{}",
+            error
+        );
+    }
+}
+
+#[test]
+fn type_parameters_are_rejected() {
+    let error = error(syn::parse_quote! {
+        #[gsm(name = "generic")]
+        struct Generic<T> {
+            key: T,
+        }
+    });
+    assert!(
+        error.contains("does not support type or const parameters"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn kinds_need_their_fields() {
+    let error = error(syn::parse_quote! {
+        #[gsm(name = "kindless", kind = "redis")]
+        struct Cache {
+            url: String,
+        }
+    });
+    assert!(error.contains("needs a `host` field"), "{}", error);
+}
+
+#[test]
+fn deref_secret_and_common_are_exclusive() {
+    let error = error(syn::parse_quote! {
+        #[gsm(name = "referencing-twice")]
+        struct Platform {
+            #[gsm(deref_secret, common)]
+            database: Database,
+        }
+    });
+    assert!(error.contains("set one of them"), "{}", error);
+}
+
+#[test]
+fn a_secret_read_from_two_regions_is_a_conflict() {
+    expand(syn::parse_quote! {
+        #[gsm(name = "regional", env("prod", region = "us-east-1"))]
+        struct American {
+            key: String,
+        }
+    });
+    let error = error(syn::parse_quote! {
+        #[gsm(name = "regional", env("prod", region = "eu-west-1"))]
+        struct European {
+            key: String,
+        }
+    });
+    assert!(error.contains("`American`"), "{}", error);
+    assert!(error.contains("region"), "{}", error);
+}
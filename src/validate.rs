@@ -0,0 +1,132 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::FieldsNamed;
+
+use crate::attr::{ContainerAttrs, FieldAttrs};
+use crate::integrations;
+use crate::schema;
+
+/// `validate_secret()`, run after deserialization so that a secret with the right shape but wrong values fails at load.
+///
+/// The checks of the fields come first, then the hook of `#[gsm(validate = "...")]`, then the integrations.
+pub fn expand(
+    fields: &FieldsNamed,
+    field_attrs: &[FieldAttrs],
+    ty: &TokenStream,
+    secret_name: &str,
+    container: &ContainerAttrs,
+) -> TokenStream {
+    let checks = fields.named.iter().zip(field_attrs).map(|(field, attrs)| {
+        let member = field.ident.as_ref().unwrap();
+        let name = member.unraw().to_string();
+        let span = field.ty.span();
+        let non_empty = attrs.non_empty.as_ref().map(|_| {
+            quote_spanned! {span=>
+                if Length::length(value) == 0 {
+                    return Err(global_secrets_manager_runtime::GsmError::validation(#secret_name, format!("field `{}` is empty", Self::key_name(#name))).into());
+                }
+            }
+        });
+        let len = attrs.len.as_ref().filter(|len| len.min > 0 || len.max.is_some()).map(|len| {
+            // Only the bounds there are, as `length < 0` would be linted in the application, and none for `0..`.
+            let mut out_of_range = Vec::new();
+            if len.min > 0 {
+                let min = len.min;
+                out_of_range.push(quote!(length < #min));
+            }
+            if let Some(max) = len.max {
+                out_of_range.push(quote!(length > #max));
+            }
+            let range = &len.range;
+            quote_spanned! {span=>
+                let length = Length::length(value);
+                if #(#out_of_range)||* {
+                    let reason = format!("field `{}` has length {}, expected {}", Self::key_name(#name), length, #range);
+                    return Err(global_secrets_manager_runtime::GsmError::validation(#secret_name, reason).into());
+                }
+            }
+        });
+        let matches = attrs.matches.as_ref().map(|pattern| {
+            quote_spanned! {span=>
+                let regex = regex::Regex::new(#pattern).map_err(|err| format!("invalid pattern of field `{}`: {}", #name, err))?;
+                if !regex.is_match(std::convert::AsRef::<str>::as_ref(value)) {
+                    let reason = format!("field `{}` doesn't match `{}`", Self::key_name(#name), #pattern);
+                    return Err(global_secrets_manager_runtime::GsmError::validation(#secret_name, reason).into());
+                }
+            }
+        });
+        if non_empty.is_none() && len.is_none() && matches.is_none() {
+            return TokenStream::new();
+        }
+        let checks = quote! {
+            #non_empty
+            #len
+            #matches
+        };
+        // Spanned on the field, so that a type without a length is reported there.
+        if schema::is_option(&field.ty) {
+            quote_spanned! {span=>
+                if let Some(value) = &self.#member {
+                    #checks
+                }
+            }
+        } else {
+            quote_spanned! {span=>
+                {
+                    let value = &self.#member;
+                    #checks
+                }
+            }
+        }
+    });
+    let hook = container.validate.as_ref().map(|hook| {
+        quote! {
+            #hook(self).map_err(|err| global_secrets_manager_runtime::GsmError::validation(#secret_name, err.to_string()))?;
+        }
+    });
+    let integrations = integrations::validate(secret_name);
+    quote! {
+        impl #ty {
+            /// Runs the checks of the fields, of `#[gsm(validate = "...")]` and of the enabled integrations.
+            #[allow(unused_variables)]
+            fn validate_secret(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+                /// The length of a value checked by `#[gsm(non_empty)]` or `#[gsm(len = "...")]`, in characters for strings.
+                trait Length {
+                    fn length(&self) -> usize;
+                }
+                impl Length for str {
+                    fn length(&self) -> usize {
+                        self.chars().count()
+                    }
+                }
+                impl Length for String {
+                    fn length(&self) -> usize {
+                        self.chars().count()
+                    }
+                }
+                impl<T> Length for [T] {
+                    fn length(&self) -> usize {
+                        self.len()
+                    }
+                }
+                impl<T> Length for Vec<T> {
+                    fn length(&self) -> usize {
+                        self.len()
+                    }
+                }
+                impl<T: Length + ?Sized> Length for &T {
+                    fn length(&self) -> usize {
+                        T::length(self)
+                    }
+                }
+
+                #(#checks)*
+                #hook
+                #integrations
+                Ok(())
+            }
+        }
+    }
+}